@@ -0,0 +1,13 @@
+-- Add migration script here
+-- audit trail for the status changes made by super admins
+CREATE TABLE IF NOT EXISTS WORKSPACE_STATUS_CHANGES (
+    ID UUID PRIMARY KEY NOT NULL,
+    WORKSPACE UUID NOT NULL,
+    OLD_STATUS WORKSPACE_STATUS NOT NULL,
+    NEW_STATUS WORKSPACE_STATUS NOT NULL,
+    REASON VARCHAR(1000) NOT NULL,
+    CHANGED_BY UUID NOT NULL,
+    CREATED_AT TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT STATUS_WORKSPACE_FK FOREIGN KEY (WORKSPACE) REFERENCES WORKSPACES(ID) ON DELETE CASCADE,
+    CONSTRAINT CHANGED_BY_FK FOREIGN KEY (CHANGED_BY) REFERENCES USERS(ID) ON DELETE CASCADE
+);
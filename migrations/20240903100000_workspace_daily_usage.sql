@@ -0,0 +1,13 @@
+-- Add migration script here
+-- counters for the plan quotas that reset every day
+DROP TYPE IF EXISTS QUOTA;
+CREATE TYPE QUOTA AS ENUM ('DAILY_LOOKUPS', 'VERIFIED_EMAILS', 'VERIFIED_DOMAINS', 'MEMBERS', 'DARK_SEARCH_PAGES');
+
+CREATE TABLE IF NOT EXISTS WORKSPACE_DAILY_USAGE (
+    WORKSPACE UUID NOT NULL,
+    USAGE_DATE DATE NOT NULL DEFAULT CURRENT_DATE,
+    QUOTA QUOTA NOT NULL,
+    USED BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (WORKSPACE, USAGE_DATE, QUOTA),
+    CONSTRAINT USAGE_WORKSPACE_FK FOREIGN KEY (WORKSPACE) REFERENCES WORKSPACES(ID) ON DELETE CASCADE
+);
@@ -0,0 +1,42 @@
+-- Add migration script here
+-- every provider lookup, used to attribute the upstream cost to a workspace
+DROP TYPE IF EXISTS USAGE_PROVIDER;
+CREATE TYPE USAGE_PROVIDER AS ENUM ('DSBREACH', 'DS_DARKENGINE');
+
+DROP TYPE IF EXISTS CACHE_TIER;
+CREATE TYPE CACHE_TIER AS ENUM ('L1', 'L2', 'UPSTREAM');
+
+CREATE TABLE IF NOT EXISTS USAGE_EVENTS (
+    ID UUID PRIMARY KEY NOT NULL,
+    WORKSPACE UUID NOT NULL,
+    MEMBER UUID,
+    USER_ID UUID,
+    -- the email or domain looked up
+    QUERY VARCHAR(255) NOT NULL DEFAULT '',
+    PROVIDER USAGE_PROVIDER NOT NULL,
+    TIER CACHE_TIER NOT NULL,
+    LATENCY_MS BIGINT NOT NULL DEFAULT 0,
+    SUCCEEDED BOOL NOT NULL DEFAULT TRUE,
+    CREATED_AT TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT EVENT_WORKSPACE_FK FOREIGN KEY (WORKSPACE) REFERENCES WORKSPACES(ID) ON DELETE CASCADE,
+    CONSTRAINT EVENT_MEMBER_FK FOREIGN KEY (MEMBER) REFERENCES MEMBERS(ID) ON DELETE SET NULL,
+    CONSTRAINT EVENT_USER_FK FOREIGN KEY (USER_ID) REFERENCES USERS(ID) ON DELETE SET NULL
+);
+CREATE INDEX IF NOT EXISTS USAGE_EVENTS_CREATED_AT_IDX ON USAGE_EVENTS (CREATED_AT);
+
+-- one row per UTC day, workspace, user, provider and tier, a day is rebuilt
+-- from its events on every rollup
+CREATE TABLE IF NOT EXISTS USAGE_DAILY_ROLLUPS (
+    ID UUID PRIMARY KEY NOT NULL DEFAULT uuid_generate_v4(),
+    USAGE_DATE DATE NOT NULL,
+    WORKSPACE UUID NOT NULL,
+    USER_ID UUID,
+    PROVIDER USAGE_PROVIDER NOT NULL,
+    TIER CACHE_TIER NOT NULL,
+    LOOKUPS BIGINT NOT NULL DEFAULT 0,
+    FAILED_LOOKUPS BIGINT NOT NULL DEFAULT 0,
+    TOTAL_LATENCY_MS BIGINT NOT NULL DEFAULT 0,
+    CONSTRAINT ROLLUP_WORKSPACE_FK FOREIGN KEY (WORKSPACE) REFERENCES WORKSPACES(ID) ON DELETE CASCADE,
+    CONSTRAINT ROLLUP_USER_FK FOREIGN KEY (USER_ID) REFERENCES USERS(ID) ON DELETE SET NULL
+);
+CREATE INDEX IF NOT EXISTS USAGE_DAILY_ROLLUPS_DATE_IDX ON USAGE_DAILY_ROLLUPS (USAGE_DATE, WORKSPACE);
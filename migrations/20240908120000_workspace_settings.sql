@@ -0,0 +1,18 @@
+-- Add migration script here
+-- key/value settings, rows without a MEMBER_ID hold the workspace defaults
+CREATE TABLE IF NOT EXISTS WORKSPACE_SETTINGS (
+    ID UUID PRIMARY KEY NOT NULL,
+    MEMBER_ID UUID,
+    WORKSPACE_ID UUID NOT NULL,
+    SETTINGS_NAME VARCHAR(100) NOT NULL,
+    SETTINGS_VALUE VARCHAR(1000) NOT NULL,
+    CREATED_AT TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UPDATED_AT TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT SETTINGS_MEMBER_FK FOREIGN KEY (MEMBER_ID) REFERENCES MEMBERS(ID) ON DELETE CASCADE,
+    CONSTRAINT SETTINGS_WORKSPACE_FK FOREIGN KEY (WORKSPACE_ID) REFERENCES WORKSPACES(ID) ON DELETE CASCADE
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS WORKSPACE_SETTINGS_WORKSPACE_UQ
+    ON WORKSPACE_SETTINGS (WORKSPACE_ID, SETTINGS_NAME) WHERE MEMBER_ID IS NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS WORKSPACE_SETTINGS_MEMBER_UQ
+    ON WORKSPACE_SETTINGS (WORKSPACE_ID, MEMBER_ID, SETTINGS_NAME) WHERE MEMBER_ID IS NOT NULL;
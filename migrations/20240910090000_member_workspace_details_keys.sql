@@ -0,0 +1,53 @@
+-- Add migration script here
+
+-- rows used to carry the workspace id as their primary key, move it to its
+-- own column so every member of a workspace can keep their own preferences
+ALTER TABLE MEMBER_WORKSPACE_DETAILS ADD COLUMN IF NOT EXISTS WORKSPACE_ID UUID;
+ALTER TABLE MEMBER_WORKSPACE_DETAILS ADD COLUMN IF NOT EXISTS USER_ID UUID;
+
+UPDATE MEMBER_WORKSPACE_DETAILS
+SET WORKSPACE_ID = MEMBERS.WORKSPACE, USER_ID = MEMBERS.USER_ID
+FROM MEMBERS
+WHERE MEMBERS.ID = MEMBER_WORKSPACE_DETAILS.MEMBER_ID;
+
+UPDATE MEMBER_WORKSPACE_DETAILS SET ID = uuid_generate_v4() WHERE ID = WORKSPACE_ID;
+
+-- members that never got a details row
+INSERT INTO MEMBER_WORKSPACE_DETAILS (ID, WORKSPACE_ORDER, IS_DEFAULT, IS_FAV, MEMBER_ID, WORKSPACE_ID, USER_ID)
+SELECT uuid_generate_v4(), 0, FALSE, FALSE, MEMBERS.ID, MEMBERS.WORKSPACE, MEMBERS.USER_ID
+FROM MEMBERS
+WHERE NOT EXISTS (SELECT 1 FROM MEMBER_WORKSPACE_DETAILS D WHERE D.MEMBER_ID = MEMBERS.ID);
+
+-- keep a single row per member and a single default per user
+DELETE FROM MEMBER_WORKSPACE_DETAILS A
+USING MEMBER_WORKSPACE_DETAILS B
+WHERE A.MEMBER_ID = B.MEMBER_ID AND A.ID > B.ID;
+
+UPDATE MEMBER_WORKSPACE_DETAILS SET IS_DEFAULT = FALSE
+WHERE IS_DEFAULT AND ID NOT IN (
+    SELECT DISTINCT ON (USER_ID) ID FROM MEMBER_WORKSPACE_DETAILS
+    WHERE IS_DEFAULT
+    ORDER BY USER_ID, WORKSPACE_ORDER, ID
+);
+
+UPDATE MEMBER_WORKSPACE_DETAILS SET IS_DEFAULT = TRUE
+WHERE ID IN (
+    SELECT DISTINCT ON (USER_ID) ID FROM MEMBER_WORKSPACE_DETAILS D
+    WHERE NOT EXISTS (
+        SELECT 1 FROM MEMBER_WORKSPACE_DETAILS O WHERE O.USER_ID = D.USER_ID AND O.IS_DEFAULT
+    )
+    ORDER BY USER_ID, WORKSPACE_ORDER, ID
+);
+
+ALTER TABLE MEMBER_WORKSPACE_DETAILS ALTER COLUMN WORKSPACE_ID SET NOT NULL;
+ALTER TABLE MEMBER_WORKSPACE_DETAILS ALTER COLUMN USER_ID SET NOT NULL;
+ALTER TABLE MEMBER_WORKSPACE_DETAILS ALTER COLUMN IS_DEFAULT SET DEFAULT FALSE;
+ALTER TABLE MEMBER_WORKSPACE_DETAILS
+    ADD CONSTRAINT MWD_WORKSPACE_ID_FK FOREIGN KEY (WORKSPACE_ID) REFERENCES WORKSPACES(ID) ON DELETE CASCADE;
+ALTER TABLE MEMBER_WORKSPACE_DETAILS
+    ADD CONSTRAINT MWD_USER_ID_FK FOREIGN KEY (USER_ID) REFERENCES USERS(ID) ON DELETE CASCADE;
+
+CREATE UNIQUE INDEX IF NOT EXISTS MWD_MEMBER_WORKSPACE_UNIQUE
+    ON MEMBER_WORKSPACE_DETAILS (MEMBER_ID, WORKSPACE_ID);
+CREATE UNIQUE INDEX IF NOT EXISTS MWD_ONE_DEFAULT_PER_USER
+    ON MEMBER_WORKSPACE_DETAILS (USER_ID) WHERE IS_DEFAULT;
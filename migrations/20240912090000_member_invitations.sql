@@ -0,0 +1,12 @@
+-- Add migration script here
+
+ALTER TYPE INVITATION_STATUS ADD VALUE IF NOT EXISTS 'ACCEPTED';
+
+ALTER TABLE INVITATIONS ADD COLUMN IF NOT EXISTS INV_TYPE INVITATION_TYPE NOT NULL DEFAULT 'VERIFY_TOKEN';
+ALTER TABLE INVITATIONS ADD COLUMN IF NOT EXISTS ROLE MEMBER_ROLE NOT NULL DEFAULT 'MEMBER';
+ALTER TABLE INVITATIONS ADD COLUMN IF NOT EXISTS ACCEPTED_AT TIMESTAMP;
+
+CREATE INDEX IF NOT EXISTS INVITATIONS_SENT_TO_TYPE ON INVITATIONS (SENT_TO, INV_TYPE);
+
+-- accounts created for invited members have no password until they onboard
+ALTER TABLE USERS ALTER COLUMN PASSWORD DROP NOT NULL;
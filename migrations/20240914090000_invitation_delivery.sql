@@ -0,0 +1,7 @@
+-- Add migration script here
+
+ALTER TABLE INVITATIONS ADD COLUMN IF NOT EXISTS ERROR VARCHAR(1000);
+ALTER TABLE INVITATIONS ADD COLUMN IF NOT EXISTS RESEND_COUNT INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE INVITATIONS ADD COLUMN IF NOT EXISTS LAST_SENT_AT TIMESTAMP;
+
+CREATE INDEX IF NOT EXISTS INVITATIONS_WORKSPACE_TYPE ON INVITATIONS (WORKSPACE, INV_TYPE);
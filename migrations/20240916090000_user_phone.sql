@@ -0,0 +1,13 @@
+-- Add migration script here
+
+ALTER TABLE USERS ADD COLUMN IF NOT EXISTS PHONE VARCHAR(32);
+ALTER TABLE USERS ADD COLUMN IF NOT EXISTS PHONE_VERIFIED BOOL NOT NULL DEFAULT FALSE;
+
+-- a number can be pending on several accounts but verified on one only
+CREATE UNIQUE INDEX IF NOT EXISTS USERS_VERIFIED_PHONE_UNIQUE ON USERS (PHONE) WHERE PHONE_VERIFIED;
+
+ALTER TABLE INVITATIONS ADD COLUMN IF NOT EXISTS PHONE VARCHAR(32);
+
+-- codes sent by sms expire and lock after too many wrong guesses
+ALTER TABLE INVITATIONS ADD COLUMN IF NOT EXISTS EXPIRES_AT TIMESTAMP;
+ALTER TABLE INVITATIONS ADD COLUMN IF NOT EXISTS ATTEMPTS INT NOT NULL DEFAULT 0;
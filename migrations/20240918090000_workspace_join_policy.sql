@@ -0,0 +1,29 @@
+-- Add migration script here
+-- what happens when someone signs up with an email on a verified domain
+CREATE TYPE JOIN_POLICY AS ENUM ('OFF', 'REQUEST', 'AUTO_JOIN');
+ALTER TABLE WORKSPACES ADD COLUMN JOIN_POLICY JOIN_POLICY NOT NULL DEFAULT 'OFF';
+
+-- requests stay UNVERIFIED until the user confirms their email
+CREATE TYPE JOIN_REQUEST_STATUS AS ENUM ('UNVERIFIED', 'PENDING', 'APPROVED', 'REJECTED');
+
+CREATE TABLE IF NOT EXISTS JOIN_REQUESTS (
+    ID UUID PRIMARY KEY NOT NULL,
+    WORKSPACE UUID NOT NULL,
+    USER_ID UUID NOT NULL,
+    DOMAIN VARCHAR(255) NOT NULL,
+    STATUS JOIN_REQUEST_STATUS NOT NULL DEFAULT 'UNVERIFIED',
+    DECIDED_BY UUID,
+    DECIDED_AT TIMESTAMP,
+    CREATED_AT TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UPDATED_AT TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT JOIN_REQUEST_WORKSPACE_FK FOREIGN KEY (WORKSPACE) REFERENCES WORKSPACES(ID) ON DELETE CASCADE,
+    CONSTRAINT JOIN_REQUEST_USER_FK FOREIGN KEY (USER_ID) REFERENCES USERS(ID) ON DELETE CASCADE,
+    CONSTRAINT JOIN_REQUEST_DECIDED_BY_FK FOREIGN KEY (DECIDED_BY) REFERENCES USERS(ID) ON DELETE SET NULL
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS JOIN_REQUESTS_OPEN_UNIQUE ON JOIN_REQUESTS (WORKSPACE, USER_ID)
+    WHERE STATUS IN ('UNVERIFIED', 'PENDING');
+
+-- sign up verification now marks users VERIFIED, catch up the ones who already joined a workspace
+UPDATE USERS SET STATUS = 'VERIFIED'
+WHERE STATUS = 'REGISTERED' AND EXISTS (SELECT 1 FROM MEMBERS WHERE MEMBERS.USER_ID = USERS.ID);
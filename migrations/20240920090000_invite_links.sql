@@ -0,0 +1,30 @@
+-- Add migration script here
+-- shareable links anyone on the workspace's intranet can use to join
+CREATE TABLE IF NOT EXISTS INVITE_LINKS (
+    ID UUID PRIMARY KEY NOT NULL,
+    WORKSPACE UUID NOT NULL,
+    TOKEN VARCHAR(64) NOT NULL UNIQUE,
+    ROLE MEMBER_ROLE NOT NULL DEFAULT 'MEMBER',
+    DOMAIN VARCHAR(255),
+    MAX_USES INTEGER NOT NULL,
+    USE_COUNT INTEGER NOT NULL DEFAULT 0,
+    EXPIRES_AT TIMESTAMP NOT NULL,
+    REVOKED_AT TIMESTAMP,
+    CREATED_BY UUID NOT NULL,
+    CREATED_AT TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT INVITE_LINK_WORKSPACE_FK FOREIGN KEY (WORKSPACE) REFERENCES WORKSPACES(ID) ON DELETE CASCADE,
+    CONSTRAINT INVITE_LINK_CREATED_BY_FK FOREIGN KEY (CREATED_BY) REFERENCES USERS(ID) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS INVITE_LINKS_WORKSPACE_IDX ON INVITE_LINKS (WORKSPACE);
+
+CREATE TABLE IF NOT EXISTS INVITE_LINK_REDEMPTIONS (
+    ID UUID PRIMARY KEY NOT NULL,
+    LINK_ID UUID NOT NULL,
+    USER_ID UUID NOT NULL,
+    MEMBER_ID UUID NOT NULL,
+    CREATED_AT TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT REDEMPTION_LINK_FK FOREIGN KEY (LINK_ID) REFERENCES INVITE_LINKS(ID) ON DELETE CASCADE,
+    CONSTRAINT REDEMPTION_USER_FK FOREIGN KEY (USER_ID) REFERENCES USERS(ID) ON DELETE CASCADE,
+    CONSTRAINT REDEMPTION_MEMBER_FK FOREIGN KEY (MEMBER_ID) REFERENCES MEMBERS(ID) ON DELETE CASCADE
+);
@@ -0,0 +1,10 @@
+-- Add migration script here
+-- every verified email gets a confirmation link sent to the address itself
+ALTER TABLE VERIFIED_EMAILS ADD COLUMN CONFIRM_TOKEN VARCHAR(64);
+ALTER TABLE VERIFIED_EMAILS ADD COLUMN TOKEN_EXPIRES_AT TIMESTAMP;
+ALTER TABLE VERIFIED_EMAILS ADD COLUMN SEND_COUNT INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE VERIFIED_EMAILS ADD COLUMN LAST_SENT_AT TIMESTAMP;
+ALTER TABLE VERIFIED_EMAILS ADD COLUMN SEND_ERROR VARCHAR(1000);
+ALTER TABLE VERIFIED_EMAILS ADD COLUMN VERIFIED_AT TIMESTAMP;
+
+CREATE UNIQUE INDEX IF NOT EXISTS VERIFIED_EMAILS_CONFIRM_TOKEN_UNIQUE ON VERIFIED_EMAILS (CONFIRM_TOKEN);
@@ -0,0 +1,15 @@
+-- Add migration script here
+-- domains are proven with a per domain token published over dns or http
+CREATE TYPE DOMAIN_PROOF AS ENUM ('DNS_TXT', 'WELL_KNOWN_FILE', 'META_TAG');
+
+ALTER TABLE VERIFIED_DOMAINS ADD COLUMN VERIFICATION_TOKEN VARCHAR(64);
+UPDATE VERIFIED_DOMAINS SET VERIFICATION_TOKEN = MD5(RANDOM()::TEXT || ID::TEXT)
+WHERE VERIFICATION_TOKEN IS NULL;
+ALTER TABLE VERIFIED_DOMAINS ALTER COLUMN VERIFICATION_TOKEN SET NOT NULL;
+
+ALTER TABLE VERIFIED_DOMAINS ADD COLUMN VERIFICATION_METHOD DOMAIN_PROOF;
+ALTER TABLE VERIFIED_DOMAINS ADD COLUMN VERIFIED_AT TIMESTAMP;
+ALTER TABLE VERIFIED_DOMAINS ADD COLUMN LAST_CHECKED_AT TIMESTAMP;
+-- consecutive re-checks that didn't find the proof
+ALTER TABLE VERIFIED_DOMAINS ADD COLUMN FAILED_CHECKS INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE VERIFIED_DOMAINS ADD COLUMN CHECK_ERROR VARCHAR(1000);
@@ -0,0 +1,33 @@
+-- Add migration script here
+UPDATE VERIFIED_EMAILS SET EMAIL = RTRIM(LOWER(TRIM(EMAIL)), '.');
+UPDATE VERIFIED_DOMAINS SET DOMAIN = RTRIM(LOWER(TRIM(DOMAIN)), '.');
+
+-- keep one row per workspace, preferring the verified then the oldest one
+DELETE FROM VERIFIED_EMAILS WHERE ID IN (
+    SELECT ID FROM (
+        SELECT VERIFIED_EMAILS.ID, ROW_NUMBER() OVER (
+            PARTITION BY MEMBERS.WORKSPACE, VERIFIED_EMAILS.EMAIL
+            ORDER BY VERIFIED_EMAILS.IS_VERIFIED DESC, VERIFIED_EMAILS.CREATED_AT
+        ) AS RANK
+        FROM VERIFIED_EMAILS INNER JOIN MEMBERS ON VERIFIED_EMAILS.ADDED_BY = MEMBERS.ID
+    ) RANKED WHERE RANK > 1
+);
+DELETE FROM VERIFIED_DOMAINS WHERE ID IN (
+    SELECT ID FROM (
+        SELECT VERIFIED_DOMAINS.ID, ROW_NUMBER() OVER (
+            PARTITION BY MEMBERS.WORKSPACE, VERIFIED_DOMAINS.DOMAIN
+            ORDER BY VERIFIED_DOMAINS.IS_VERIFIED DESC, VERIFIED_DOMAINS.CREATED_AT
+        ) AS RANK
+        FROM VERIFIED_DOMAINS INNER JOIN MEMBERS ON VERIFIED_DOMAINS.ADDED_BY = MEMBERS.ID
+    ) RANKED WHERE RANK > 1
+);
+
+CREATE INDEX IF NOT EXISTS VERIFIED_EMAILS_EMAIL_IDX ON VERIFIED_EMAILS (EMAIL);
+CREATE INDEX IF NOT EXISTS VERIFIED_DOMAINS_DOMAIN_IDX ON VERIFIED_DOMAINS (DOMAIN);
+
+-- confirmation emails sent to each address, kept when the address is removed
+CREATE TABLE IF NOT EXISTS CONFIRMATION_SENDS (
+    EMAIL VARCHAR(255) NOT NULL,
+    SENT_AT TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX IF NOT EXISTS CONFIRMATION_SENDS_EMAIL_IDX ON CONFIRMATION_SENDS (EMAIL, SENT_AT);
@@ -0,0 +1,45 @@
+-- Add migration script here
+-- verified assets belong to the workspace, ADDED_BY only records who added them
+ALTER TABLE VERIFIED_EMAILS ADD COLUMN IF NOT EXISTS WORKSPACE UUID;
+UPDATE VERIFIED_EMAILS SET WORKSPACE = MEMBERS.WORKSPACE
+FROM MEMBERS WHERE MEMBERS.ID = VERIFIED_EMAILS.ADDED_BY;
+ALTER TABLE VERIFIED_DOMAINS ADD COLUMN IF NOT EXISTS WORKSPACE UUID;
+UPDATE VERIFIED_DOMAINS SET WORKSPACE = MEMBERS.WORKSPACE
+FROM MEMBERS WHERE MEMBERS.ID = VERIFIED_DOMAINS.ADDED_BY;
+
+-- rows whose member is gone can't be traced to a workspace
+DO $$
+DECLARE
+    ORPHAN_EMAILS BIGINT;
+    ORPHAN_DOMAINS BIGINT;
+BEGIN
+    DELETE FROM VERIFIED_EMAILS WHERE WORKSPACE IS NULL;
+    GET DIAGNOSTICS ORPHAN_EMAILS = ROW_COUNT;
+    DELETE FROM VERIFIED_DOMAINS WHERE WORKSPACE IS NULL;
+    GET DIAGNOSTICS ORPHAN_DOMAINS = ROW_COUNT;
+    RAISE NOTICE 'deleted % verified emails and % verified domains without a member',
+        ORPHAN_EMAILS, ORPHAN_DOMAINS;
+END $$;
+
+-- one entry per normalized value in each workspace
+ALTER TABLE VERIFIED_EMAILS ALTER COLUMN WORKSPACE SET NOT NULL;
+ALTER TABLE VERIFIED_EMAILS ADD CONSTRAINT VERIFIED_EMAILS_WORKSPACE_EMAIL_UNIQUE
+    UNIQUE (WORKSPACE, EMAIL);
+ALTER TABLE VERIFIED_DOMAINS ALTER COLUMN WORKSPACE SET NOT NULL;
+ALTER TABLE VERIFIED_DOMAINS ADD CONSTRAINT VERIFIED_DOMAINS_WORKSPACE_DOMAIN_UNIQUE
+    UNIQUE (WORKSPACE, DOMAIN);
+
+ALTER TABLE VERIFIED_EMAILS ADD CONSTRAINT VERIFIED_EMAILS_WORKSPACE_FK
+    FOREIGN KEY (WORKSPACE) REFERENCES WORKSPACES(ID) ON DELETE CASCADE;
+ALTER TABLE VERIFIED_EMAILS ALTER COLUMN ADDED_BY DROP NOT NULL;
+ALTER TABLE VERIFIED_EMAILS DROP CONSTRAINT IF EXISTS ADDED_BY_FK;
+ALTER TABLE VERIFIED_EMAILS ADD CONSTRAINT ADDED_BY_FK
+    FOREIGN KEY (ADDED_BY) REFERENCES MEMBERS(ID) ON DELETE SET NULL;
+DROP INDEX IF EXISTS VERIFIED_EMAILS_EMAIL_IDX;
+
+ALTER TABLE VERIFIED_DOMAINS ADD CONSTRAINT VERIFIED_DOMAINS_WORKSPACE_FK
+    FOREIGN KEY (WORKSPACE) REFERENCES WORKSPACES(ID) ON DELETE CASCADE;
+ALTER TABLE VERIFIED_DOMAINS ALTER COLUMN ADDED_BY DROP NOT NULL;
+ALTER TABLE VERIFIED_DOMAINS DROP CONSTRAINT IF EXISTS ADDED_BY_FK;
+ALTER TABLE VERIFIED_DOMAINS ADD CONSTRAINT ADDED_BY_FK
+    FOREIGN KEY (ADDED_BY) REFERENCES MEMBERS(ID) ON DELETE SET NULL;
@@ -0,0 +1,18 @@
+-- Add migration script here
+-- breach lookups refused because the target isn't verified in the workspace
+DROP TYPE IF EXISTS LOOKUP_TARGET;
+CREATE TYPE LOOKUP_TARGET AS ENUM ('EMAIL', 'DOMAIN');
+
+CREATE TABLE IF NOT EXISTS LOOKUP_REFUSALS (
+    ID UUID PRIMARY KEY NOT NULL,
+    WORKSPACE UUID NOT NULL,
+    MEMBER UUID,
+    USER_ID UUID NOT NULL,
+    TARGET_TYPE LOOKUP_TARGET NOT NULL,
+    TARGET VARCHAR(255) NOT NULL,
+    PATH VARCHAR(512) NOT NULL,
+    CREATED_AT TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT REFUSAL_WORKSPACE_FK FOREIGN KEY (WORKSPACE) REFERENCES WORKSPACES(ID) ON DELETE CASCADE,
+    CONSTRAINT REFUSAL_MEMBER_FK FOREIGN KEY (MEMBER) REFERENCES MEMBERS(ID) ON DELETE SET NULL
+);
+CREATE INDEX IF NOT EXISTS LOOKUP_REFUSALS_WORKSPACE_IDX ON LOOKUP_REFUSALS (WORKSPACE, CREATED_AT);
@@ -0,0 +1,3 @@
+-- Add migration script here
+-- a verified domain can vouch for every host below it
+ALTER TABLE VERIFIED_DOMAINS ADD COLUMN IF NOT EXISTS INCLUDE_SUBDOMAINS BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,55 @@
+-- Add migration script here
+-- verified emails and domains are re-scanned for breaches in the background
+ALTER TABLE VERIFIED_EMAILS ADD COLUMN IF NOT EXISTS NEXT_SCAN_AT TIMESTAMP;
+ALTER TABLE VERIFIED_EMAILS ADD COLUMN IF NOT EXISTS LAST_SCANNED_AT TIMESTAMP;
+ALTER TABLE VERIFIED_DOMAINS ADD COLUMN IF NOT EXISTS NEXT_SCAN_AT TIMESTAMP;
+ALTER TABLE VERIFIED_DOMAINS ADD COLUMN IF NOT EXISTS LAST_SCANNED_AT TIMESTAMP;
+CREATE INDEX IF NOT EXISTS VERIFIED_EMAILS_NEXT_SCAN_IDX ON VERIFIED_EMAILS (NEXT_SCAN_AT);
+CREATE INDEX IF NOT EXISTS VERIFIED_DOMAINS_NEXT_SCAN_IDX ON VERIFIED_DOMAINS (NEXT_SCAN_AT);
+
+-- ASSET_ID is whichever of VERIFIED_EMAIL and VERIFIED_DOMAIN is set, the
+-- foreign keys drop the history together with the asset
+CREATE TABLE IF NOT EXISTS ASSET_SCANS (
+    ID UUID PRIMARY KEY NOT NULL,
+    WORKSPACE UUID NOT NULL,
+    ASSET_TYPE LOOKUP_TARGET NOT NULL,
+    ASSET_ID UUID NOT NULL,
+    VERIFIED_EMAIL UUID,
+    VERIFIED_DOMAIN UUID,
+    ASSET VARCHAR(255) NOT NULL,
+    SUCCEEDED BOOLEAN NOT NULL,
+    ERROR VARCHAR(1000),
+    BREACHES TEXT[] NOT NULL DEFAULT '{}',
+    NEW_BREACHES TEXT[] NOT NULL DEFAULT '{}',
+    CREATED_AT TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT SCAN_WORKSPACE_FK FOREIGN KEY (WORKSPACE) REFERENCES WORKSPACES(ID) ON DELETE CASCADE,
+    CONSTRAINT SCAN_EMAIL_FK FOREIGN KEY (VERIFIED_EMAIL) REFERENCES VERIFIED_EMAILS(ID) ON DELETE CASCADE,
+    CONSTRAINT SCAN_DOMAIN_FK FOREIGN KEY (VERIFIED_DOMAIN) REFERENCES VERIFIED_DOMAINS(ID) ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS ASSET_SCANS_ASSET_IDX ON ASSET_SCANS (ASSET_ID, CREATED_AT);
+
+-- every breach an asset has been seen in
+CREATE TABLE IF NOT EXISTS ASSET_BREACHES (
+    ASSET_ID UUID NOT NULL,
+    BREACH_NAME VARCHAR(255) NOT NULL,
+    WORKSPACE UUID NOT NULL,
+    ASSET_TYPE LOOKUP_TARGET NOT NULL,
+    VERIFIED_EMAIL UUID,
+    VERIFIED_DOMAIN UUID,
+    DATA_CLASSES TEXT[] NOT NULL DEFAULT '{}',
+    FIRST_SEEN_AT TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    LAST_SEEN_AT TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (ASSET_ID, BREACH_NAME),
+    CONSTRAINT BREACH_WORKSPACE_FK FOREIGN KEY (WORKSPACE) REFERENCES WORKSPACES(ID) ON DELETE CASCADE,
+    CONSTRAINT BREACH_EMAIL_FK FOREIGN KEY (VERIFIED_EMAIL) REFERENCES VERIFIED_EMAILS(ID) ON DELETE CASCADE,
+    CONSTRAINT BREACH_DOMAIN_FK FOREIGN KEY (VERIFIED_DOMAIN) REFERENCES VERIFIED_DOMAINS(ID) ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS ASSET_BREACHES_WORKSPACE_IDX ON ASSET_BREACHES (WORKSPACE);
+
+-- provider lookups left for the background scans, shared by every instance
+CREATE TABLE IF NOT EXISTS MONITORING_BUDGET (
+    ID INT PRIMARY KEY NOT NULL,
+    TOKENS DOUBLE PRECISION NOT NULL DEFAULT 0,
+    UPDATED_AT TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+INSERT INTO MONITORING_BUDGET (ID) VALUES (1) ON CONFLICT DO NOTHING;
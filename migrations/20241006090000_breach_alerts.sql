@@ -0,0 +1,35 @@
+-- Add migration script here
+-- one alert per breach a monitored asset newly appeared in
+DROP TYPE IF EXISTS ALERT_SEVERITY;
+CREATE TYPE ALERT_SEVERITY AS ENUM ('LOW', 'MEDIUM', 'HIGH', 'CRITICAL');
+DROP TYPE IF EXISTS ALERT_STATUS;
+CREATE TYPE ALERT_STATUS AS ENUM ('OPEN', 'ACKNOWLEDGED', 'RESOLVED');
+
+-- alerts outlive the asset they were raised for
+CREATE TABLE IF NOT EXISTS BREACH_ALERTS (
+    ID UUID PRIMARY KEY NOT NULL,
+    WORKSPACE UUID NOT NULL,
+    ASSET_TYPE LOOKUP_TARGET NOT NULL,
+    ASSET_ID UUID NOT NULL,
+    ASSET VARCHAR(255) NOT NULL,
+    BREACH_NAME VARCHAR(255) NOT NULL,
+    DATA_CLASSES TEXT[] NOT NULL DEFAULT '{}',
+    SEVERITY ALERT_SEVERITY NOT NULL,
+    STATUS ALERT_STATUS NOT NULL DEFAULT 'OPEN',
+    ACKNOWLEDGED_BY UUID,
+    ACKNOWLEDGED_AT TIMESTAMP,
+    RESOLVED_BY UUID,
+    RESOLVED_AT TIMESTAMP,
+    -- emails and webhooks go out from the stored alerts, a claim pushes
+    -- NOTIFY_AFTER out until NOTIFIED_AT is set
+    NOTIFY_AFTER TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    NOTIFIED_AT TIMESTAMP,
+    CREATED_AT TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UPDATED_AT TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT ALERT_WORKSPACE_FK FOREIGN KEY (WORKSPACE) REFERENCES WORKSPACES(ID) ON DELETE CASCADE,
+    CONSTRAINT ALERT_ACKNOWLEDGED_BY_FK FOREIGN KEY (ACKNOWLEDGED_BY) REFERENCES MEMBERS(ID) ON DELETE SET NULL,
+    CONSTRAINT ALERT_RESOLVED_BY_FK FOREIGN KEY (RESOLVED_BY) REFERENCES MEMBERS(ID) ON DELETE SET NULL,
+    CONSTRAINT ALERT_ASSET_BREACH_UNIQUE UNIQUE (ASSET_ID, BREACH_NAME)
+);
+CREATE INDEX IF NOT EXISTS BREACH_ALERTS_WORKSPACE_IDX ON BREACH_ALERTS (WORKSPACE, STATUS, CREATED_AT);
+CREATE INDEX IF NOT EXISTS BREACH_ALERTS_NOTIFY_IDX ON BREACH_ALERTS (NOTIFY_AFTER) WHERE NOTIFIED_AT IS NULL;
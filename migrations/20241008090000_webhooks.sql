@@ -0,0 +1,58 @@
+-- Add migration script here
+-- workspace configured endpoints that breach findings and member changes are pushed to
+DROP TYPE IF EXISTS WEBHOOK_EVENT;
+CREATE TYPE WEBHOOK_EVENT AS ENUM ('BREACH_DETECTED', 'DARK_SEARCH_HIT', 'MEMBER_JOINED');
+DROP TYPE IF EXISTS WEBHOOK_DELIVERY_STATUS;
+CREATE TYPE WEBHOOK_DELIVERY_STATUS AS ENUM ('PENDING', 'SUCCEEDED', 'FAILED');
+
+-- EVENTS holds WEBHOOK_EVENT names. PREVIOUS_SECRET keeps signing next to
+-- the new one for a while after a rotation.
+CREATE TABLE IF NOT EXISTS WEBHOOK_ENDPOINTS (
+    ID UUID PRIMARY KEY NOT NULL,
+    WORKSPACE UUID NOT NULL,
+    URL VARCHAR(2048) NOT NULL,
+    EVENTS TEXT[] NOT NULL DEFAULT '{}',
+    SECRET VARCHAR(128) NOT NULL,
+    PREVIOUS_SECRET VARCHAR(128),
+    PREVIOUS_SECRET_EXPIRES_AT TIMESTAMP,
+    ENABLED BOOLEAN NOT NULL DEFAULT TRUE,
+    CREATED_BY UUID,
+    CREATED_AT TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UPDATED_AT TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT WEBHOOK_WORKSPACE_FK FOREIGN KEY (WORKSPACE) REFERENCES WORKSPACES(ID) ON DELETE CASCADE,
+    CONSTRAINT WEBHOOK_CREATED_BY_FK FOREIGN KEY (CREATED_BY) REFERENCES MEMBERS(ID) ON DELETE SET NULL
+);
+CREATE INDEX IF NOT EXISTS WEBHOOK_ENDPOINTS_WORKSPACE_IDX ON WEBHOOK_ENDPOINTS (WORKSPACE);
+
+-- one row per event and endpoint, a replay is a new row pointing at the original
+CREATE TABLE IF NOT EXISTS WEBHOOK_DELIVERIES (
+    ID UUID PRIMARY KEY NOT NULL,
+    WORKSPACE UUID NOT NULL,
+    ENDPOINT UUID NOT NULL,
+    EVENT_ID UUID NOT NULL,
+    EVENT WEBHOOK_EVENT NOT NULL,
+    PAYLOAD TEXT NOT NULL,
+    STATUS WEBHOOK_DELIVERY_STATUS NOT NULL DEFAULT 'PENDING',
+    ATTEMPTS INTEGER NOT NULL DEFAULT 0,
+    NEXT_ATTEMPT_AT TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    LAST_ATTEMPT_AT TIMESTAMP,
+    RESPONSE_STATUS INTEGER,
+    ERROR VARCHAR(1000),
+    REPLAY_OF UUID,
+    CREATED_AT TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT DELIVERY_WORKSPACE_FK FOREIGN KEY (WORKSPACE) REFERENCES WORKSPACES(ID) ON DELETE CASCADE,
+    CONSTRAINT DELIVERY_ENDPOINT_FK FOREIGN KEY (ENDPOINT) REFERENCES WEBHOOK_ENDPOINTS(ID) ON DELETE CASCADE,
+    CONSTRAINT DELIVERY_REPLAY_OF_FK FOREIGN KEY (REPLAY_OF) REFERENCES WEBHOOK_DELIVERIES(ID) ON DELETE SET NULL
+);
+CREATE INDEX IF NOT EXISTS WEBHOOK_DELIVERIES_DUE_IDX ON WEBHOOK_DELIVERIES (STATUS, NEXT_ATTEMPT_AT);
+CREATE INDEX IF NOT EXISTS WEBHOOK_DELIVERIES_WORKSPACE_IDX ON WEBHOOK_DELIVERIES (WORKSPACE, CREATED_AT);
+
+-- dark search documents already seen for a target, so only new hits are pushed
+CREATE TABLE IF NOT EXISTS DARK_SEARCH_HITS (
+    WORKSPACE UUID NOT NULL,
+    TARGET VARCHAR(255) NOT NULL,
+    DOCUMENT_UUID VARCHAR(128) NOT NULL,
+    FIRST_SEEN_AT TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (WORKSPACE, TARGET, DOCUMENT_UUID),
+    CONSTRAINT HIT_WORKSPACE_FK FOREIGN KEY (WORKSPACE) REFERENCES WORKSPACES(ID) ON DELETE CASCADE
+);
@@ -0,0 +1,24 @@
+-- Add migration script here
+-- the lookup results of a monitored asset, one row per asset and day merging
+-- the results of that day
+CREATE TABLE IF NOT EXISTS BREACH_SNAPSHOTS (
+    ID UUID PRIMARY KEY NOT NULL DEFAULT uuid_generate_v4(),
+    WORKSPACE UUID NOT NULL,
+    ASSET_TYPE LOOKUP_TARGET NOT NULL,
+    ASSET_ID UUID NOT NULL,
+    VERIFIED_EMAIL UUID,
+    VERIFIED_DOMAIN UUID,
+    ASSET VARCHAR(255) NOT NULL,
+    SNAPSHOT_DATE DATE NOT NULL DEFAULT CURRENT_DATE,
+    BREACH_COUNT INT NOT NULL DEFAULT 0,
+    SENSITIVE_COUNT INT NOT NULL DEFAULT 0,
+    TOTAL_RECORDS BIGINT NOT NULL DEFAULT 0,
+    BREACHES TEXT[] NOT NULL DEFAULT '{}',
+    CREATED_AT TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UPDATED_AT TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (ASSET_ID, SNAPSHOT_DATE),
+    CONSTRAINT SNAPSHOT_WORKSPACE_FK FOREIGN KEY (WORKSPACE) REFERENCES WORKSPACES(ID) ON DELETE CASCADE,
+    CONSTRAINT SNAPSHOT_EMAIL_FK FOREIGN KEY (VERIFIED_EMAIL) REFERENCES VERIFIED_EMAILS(ID) ON DELETE CASCADE,
+    CONSTRAINT SNAPSHOT_DOMAIN_FK FOREIGN KEY (VERIFIED_DOMAIN) REFERENCES VERIFIED_DOMAINS(ID) ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS BREACH_SNAPSHOTS_WORKSPACE_IDX ON BREACH_SNAPSHOTS (WORKSPACE);
@@ -0,0 +1,44 @@
+use trust_dns_resolver::config::{ResolverConfig, ResolverOpts};
+use trust_dns_resolver::error::ResolveErrorKind;
+use trust_dns_resolver::TokioAsyncResolver;
+
+use crate::darkscout::adapters::DnsResolver;
+
+#[derive(Clone)]
+pub struct SystemDnsResolver {
+    resolver: TokioAsyncResolver,
+}
+
+impl SystemDnsResolver {
+    // uses the host's resolv.conf, falling back to public resolvers
+    pub fn new() -> Result<Self, String> {
+        let resolver = match TokioAsyncResolver::tokio_from_system_conf() {
+            Ok(r) => r,
+            Err(e) => {
+                tracing::debug!("Failed to read the system dns config {}", e);
+                TokioAsyncResolver::tokio(ResolverConfig::default(), ResolverOpts::default())
+                    .map_err(|e| e.to_string())?
+            }
+        };
+        Ok(SystemDnsResolver { resolver })
+    }
+}
+
+impl DnsResolver for SystemDnsResolver {
+    async fn txt_records(&self, name: &str) -> Result<Vec<String>, String> {
+        match self.resolver.txt_lookup(name).await {
+            Ok(lookup) => Ok(lookup
+                .iter()
+                .map(|txt| {
+                    txt.txt_data()
+                        .iter()
+                        .map(|part| String::from_utf8_lossy(part).into_owned())
+                        .collect::<String>()
+                })
+                .collect()),
+            // a domain without TXT records is a missing proof, not a failure
+            Err(e) if matches!(e.kind(), ResolveErrorKind::NoRecordsFound { .. }) => Ok(vec![]),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
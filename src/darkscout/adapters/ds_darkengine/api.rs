@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use crate::darkscout::adapters::{DSProvider, DarkSearchProvider};
+use crate::darkscout::adapters::{CacheTier, DSProvider, DarkSearchProvider, ProviderLookup};
 use axum::http::{HeaderMap, HeaderValue};
 use mongodb::bson::doc;
 // use http_cache_reqwest::{CACacheManager, Cache, CacheMode, HttpCache, HttpCacheOptions};
@@ -68,10 +68,13 @@ impl DarkSearchProvider for DarkSearchClient {
     async fn get_stats_by_email(
         &self,
         email: &str,
-    ) -> std::result::Result<DarkSearchResponse, reqwest::Error> {
+    ) -> std::result::Result<ProviderLookup<DarkSearchResponse>, reqwest::Error> {
         if let Some(data) = self.l1_cache.get(email).await {
             println!("L1 Cache hit Success");
-            return Ok(data);
+            return Ok(ProviderLookup {
+                data,
+                tier: CacheTier::L1,
+            });
         }
 
         // Check l2 Cache
@@ -82,7 +85,10 @@ impl DarkSearchProvider for DarkSearchClient {
                 self.l1_cache
                     .insert(email.to_string(), breach_info.clone())
                     .await;
-                return Ok(breach_info);
+                return Ok(ProviderLookup {
+                    data: breach_info,
+                    tier: CacheTier::L2,
+                });
             }
             Ok(None) => {}
             Err(_) => {}
@@ -114,7 +120,10 @@ impl DarkSearchProvider for DarkSearchClient {
                     }
                 });
 
-                return Ok(res);
+                return Ok(ProviderLookup {
+                    data: res,
+                    tier: CacheTier::UPSTREAM,
+                });
             }
             Err(e) => {
                 return Err(e);
@@ -125,10 +134,13 @@ impl DarkSearchProvider for DarkSearchClient {
     async fn get_stats_by_domain(
         &self,
         domain: &str,
-    ) -> std::result::Result<DarkSearchResponse, reqwest::Error> {
+    ) -> std::result::Result<ProviderLookup<DarkSearchResponse>, reqwest::Error> {
         if let Some(data) = self.l1_cache.get(domain).await {
             println!("L1 Cache hit Success");
-            return Ok(data);
+            return Ok(ProviderLookup {
+                data,
+                tier: CacheTier::L1,
+            });
         }
 
         // Check l2 Cache
@@ -139,7 +151,10 @@ impl DarkSearchProvider for DarkSearchClient {
                 self.l1_cache
                     .insert(domain.to_string(), breach_info.clone())
                     .await;
-                return Ok(breach_info);
+                return Ok(ProviderLookup {
+                    data: breach_info,
+                    tier: CacheTier::L2,
+                });
             }
             Ok(None) => {}
             Err(_) => {}
@@ -172,7 +187,10 @@ impl DarkSearchProvider for DarkSearchClient {
                     }
                 });
 
-                return Ok(res);
+                return Ok(ProviderLookup {
+                    data: res,
+                    tier: CacheTier::UPSTREAM,
+                });
             }
             Err(e) => {
                 return Err(e);
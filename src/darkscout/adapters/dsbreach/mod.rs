@@ -1,12 +1,14 @@
-use crate::darkscout::adapters::DSProvider;
-use axum::http::{HeaderMap, HeaderValue};
+use crate::darkscout::adapters::{CacheTier, DSProvider, ProviderLookup};
+use axum::http::header::RETRY_AFTER;
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
 use http_cache_reqwest::{CACacheManager, Cache, CacheMode, HttpCache, HttpCacheOptions};
 use moka::future::{self};
-use reqwest::Client;
+use reqwest::{Client, Url};
 use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
 use serde::{Deserialize, Serialize};
 pub mod api;
 use mongodb::bson::doc;
+use std::collections::HashMap;
 use std::time::Duration;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -38,6 +40,37 @@ pub struct CachedBreachedStats {
     pub added_at: chrono::DateTime<chrono::Utc>,
 }
 
+// Why an upstream refresh failed, monitoring backs off on RateLimited
+#[derive(Debug)]
+pub enum BreachFetchError {
+    // seconds from the Retry-After header
+    RateLimited(Option<u64>),
+    Failed(String),
+}
+
+impl std::fmt::Display for BreachFetchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BreachFetchError::RateLimited(_) => write!(f, "rate limited by the breach provider"),
+            BreachFetchError::Failed(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+const ACCOUNTS_URL: &'static str = "http://localhost:8001/searchapi/accounts";
+const DOMAINS_URL: &'static str = "http://localhost:8001/searchapi/domains";
+
+// The target goes in as one encoded path segment, so it can't walk to another
+// route or cut the query off
+fn upstream_url(base: &str, target: &str, query: Option<&str>) -> String {
+    let mut url = Url::parse(base).expect("upstream base url");
+    url.path_segments_mut()
+        .expect("upstream base url")
+        .push(target);
+    url.set_query(query);
+    url.to_string()
+}
+
 #[derive(Clone)]
 pub struct BreachClient {
     client: Client,
@@ -45,6 +78,31 @@ pub struct BreachClient {
     l2_cache: mongodb::Collection<CachedBreachedStats>,
 }
 
+impl BreachClient {
+    // None when upstream has nothing for the target, it answers 404 then
+    async fn fetch_upstream(&self, url: String) -> Result<Option<reqwest::Response>, BreachFetchError> {
+        let res = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| BreachFetchError::Failed(e.to_string()))?;
+        match res.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            StatusCode::TOO_MANY_REQUESTS => {
+                let retry_after = res
+                    .headers()
+                    .get(RETRY_AFTER)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| v.parse().ok());
+                Err(BreachFetchError::RateLimited(retry_after))
+            }
+            s if s.is_success() => Ok(Some(res)),
+            s => Err(BreachFetchError::Failed(format!("breach provider answered {}", s))),
+        }
+    }
+}
+
 impl DSProvider for BreachClient {
     async fn new(
         key: &'static str,
@@ -70,10 +128,13 @@ impl DSProvider for BreachClient {
     async fn get_stats_by_email(
         &self,
         email: &str,
-    ) -> std::result::Result<Vec<BreachName>, reqwest_middleware::Error> {
+    ) -> std::result::Result<ProviderLookup<Vec<BreachName>>, reqwest_middleware::Error> {
         if let Some(data) = self.l1_cache.get(email).await {
             println!("L1 Cache hit Success");
-            return Ok(data);
+            return Ok(ProviderLookup {
+                data,
+                tier: CacheTier::L1,
+            });
         }
 
         // Check L2 Cache
@@ -84,14 +145,16 @@ impl DSProvider for BreachClient {
                 self.l1_cache
                     .insert(email.to_string(), breach_info.clone())
                     .await;
-                return Ok(breach_info);
+                return Ok(ProviderLookup {
+                    data: breach_info,
+                    tier: CacheTier::L2,
+                });
             }
             Ok(None) => {}
             Err(_) => {}
         }
 
-        const BREACH_URL: &'static str = "http://localhost:8001/searchapi/accounts";
-        let url = format!("{}/{}?truncateResponse=false", BREACH_URL, email);
+        let url = upstream_url(ACCOUNTS_URL, email, Some("truncateResponse=false"));
 
         match self
             .client
@@ -120,7 +183,10 @@ impl DSProvider for BreachClient {
                     }
                 });
 
-                return Ok(res);
+                return Ok(ProviderLookup {
+                    data: res,
+                    tier: CacheTier::UPSTREAM,
+                });
             }
             Err(e) => {
                 return Err(reqwest_middleware::Error::Middleware(e.into()));
@@ -134,8 +200,7 @@ impl DSProvider for BreachClient {
     ) -> std::result::Result<(), reqwest_middleware::Error> {
         /**
         **/
-        const BREACH_URL: &'static str = "http://localhost:8001/searchapi/domains";
-        let url = format!("{}/{}", BREACH_URL, domain);
+        let url = upstream_url(DOMAINS_URL, domain, None);
         return match self.client.get(url).send().await?.text().await {
             Ok(res) => {
                 println!("{}", res);
@@ -144,4 +209,67 @@ impl DSProvider for BreachClient {
             Err(e) => Err(reqwest_middleware::Error::Middleware(e.into())),
         };
     }
-}
\ No newline at end of file
+
+    async fn refresh_email_breaches(&self, email: &str) -> std::result::Result<Vec<BreachName>, BreachFetchError> {
+        let url = upstream_url(ACCOUNTS_URL, email, Some("truncateResponse=false"));
+        let breaches = match self.fetch_upstream(url).await? {
+            Some(res) => res
+                .json::<Vec<BreachName>>()
+                .await
+                .map_err(|e| BreachFetchError::Failed(e.to_string()))?,
+            None => Vec::new(),
+        };
+        self.l1_cache.insert(String::from(email), breaches.clone()).await;
+        let cached = CachedBreachedStats {
+            _id: String::from(email),
+            breach_stats: breaches.clone(),
+            added_at: chrono::Utc::now(),
+        };
+        if let Err(e) = self
+            .l2_cache
+            .replace_one(doc! { "_id": email }, cached)
+            .upsert(true)
+            .await
+        {
+            tracing::debug!("Error refreshing the l2 cache: {}", e);
+        }
+        Ok(breaches)
+    }
+
+    async fn refresh_domain_breaches(&self, domain: &str) -> std::result::Result<Vec<String>, BreachFetchError> {
+        // alias on the domain -> names of the breaches it is in
+        let url = upstream_url(DOMAINS_URL, domain, None);
+        let aliases = match self.fetch_upstream(url).await? {
+            Some(res) => res
+                .json::<HashMap<String, Vec<String>>>()
+                .await
+                .map_err(|e| BreachFetchError::Failed(e.to_string()))?,
+            None => HashMap::new(),
+        };
+        let mut names: Vec<String> = aliases.into_values().flatten().collect();
+        names.sort();
+        names.dedup();
+        Ok(names)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upstream_url_encodes_the_target() {
+        assert_eq!(
+            upstream_url(ACCOUNTS_URL, "a@b.com", Some("truncateResponse=false")),
+            "http://localhost:8001/searchapi/accounts/a@b.com?truncateResponse=false"
+        );
+        assert_eq!(
+            upstream_url(ACCOUNTS_URL, "x/../../domains/victim.com#@mine.com", None),
+            "http://localhost:8001/searchapi/accounts/x%2F..%2F..%2Fdomains%2Fvictim.com%23@mine.com"
+        );
+        assert_eq!(
+            upstream_url(ACCOUNTS_URL, "x/../victim%40other.com#@mine.com", None),
+            "http://localhost:8001/searchapi/accounts/x%2F..%2Fvictim%2540other.com%23@mine.com"
+        );
+    }
+}
@@ -0,0 +1,59 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use reqwest::Url;
+
+use crate::darkscout::adapters::HttpFetcher;
+use crate::darkscout::utils::network::{check_url_host, PublicResolver};
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+// proofs are tiny, don't read whole websites into memory
+const MAX_BODY_BYTES: usize = 256 * 1024;
+
+#[derive(Clone)]
+pub struct ReqwestFetcher {
+    client: reqwest::Client,
+}
+
+impl ReqwestFetcher {
+    pub fn new() -> Result<Self, String> {
+        let client = reqwest::Client::builder()
+            .timeout(FETCH_TIMEOUT)
+            // the urls are built from user supplied domains, never follow a
+            // redirect or connect to anything but public addresses
+            .redirect(reqwest::redirect::Policy::none())
+            .https_only(true)
+            .no_proxy()
+            .dns_resolver(Arc::new(PublicResolver {
+                allow_loopback: false,
+            }))
+            .user_agent("DarkScout-Verification/1.0")
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(ReqwestFetcher { client })
+    }
+}
+
+impl HttpFetcher for ReqwestFetcher {
+    async fn fetch_text(&self, url: &str) -> Result<String, String> {
+        let url = Url::parse(url).map_err(|e| e.to_string())?;
+        check_url_host(&url, false)?;
+        let mut response = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?
+            .error_for_status()
+            .map_err(|e| e.to_string())?;
+        let mut body: Vec<u8> = Vec::new();
+        while let Some(chunk) = response.chunk().await.map_err(|e| e.to_string())? {
+            body.extend_from_slice(&chunk);
+            if body.len() > MAX_BODY_BYTES {
+                body.truncate(MAX_BODY_BYTES);
+                break;
+            }
+        }
+        Ok(String::from_utf8_lossy(&body).into_owned())
+    }
+}
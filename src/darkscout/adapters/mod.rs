@@ -1,10 +1,31 @@
 use std::result::Result;
 pub mod dsbreach;
+pub mod sms;
 pub mod smtp_mailer;
 pub mod ds_darkengine;
+pub mod dns;
+pub mod http_fetcher;
+pub mod webhooks;
 
-use crate::darkscout::adapters::dsbreach::BreachName;
+use crate::darkscout::adapters::dsbreach::{BreachFetchError, BreachName};
 use crate::darkscout::adapters::ds_darkengine::types::DarkSearchResponse;
+use serde::{Deserialize, Serialize};
+use sqlx::Type;
+
+// Where a provider lookup was answered from. Only UPSTREAM costs us money.
+#[derive(Serialize, Deserialize, Clone, Copy, Type, PartialEq, Debug)]
+#[sqlx(type_name = "CACHE_TIER")]
+pub enum CacheTier {
+    L1,
+    L2,
+    UPSTREAM,
+}
+
+#[derive(Clone)]
+pub struct ProviderLookup<T> {
+    pub data: T,
+    pub tier: CacheTier,
+}
 
 #[allow(async_fn_in_trait)]
 pub trait DSProvider {
@@ -14,8 +35,13 @@ pub trait DSProvider {
     async fn get_stats_by_email(
         &self,
         email: &str,
-    ) -> Result<Vec<BreachName>, reqwest_middleware::Error>;
+    ) -> Result<ProviderLookup<Vec<BreachName>>, reqwest_middleware::Error>;
     async fn get_stats_by_domain(&self, domain: &str) -> Result<(), reqwest_middleware::Error>;
+    // straight from upstream for background monitoring, the caches only get
+    // the fresh result
+    async fn refresh_email_breaches(&self, email: &str) -> Result<Vec<BreachName>, BreachFetchError>;
+    // names of the breaches any address on the domain appears in
+    async fn refresh_domain_breaches(&self, domain: &str) -> Result<Vec<String>, BreachFetchError>;
 }
 
 #[allow(async_fn_in_trait)]
@@ -23,9 +49,14 @@ pub trait DarkSearchProvider {
     fn new(key: &str, l2_cache: mongodb::Client) -> Self
     where
         Self: Sized;
-    async fn get_stats_by_email(&self, email: &str) -> Result<DarkSearchResponse, reqwest::Error>;
-    async fn get_stats_by_domain(&self, domain: &str)
-        -> Result<DarkSearchResponse, reqwest::Error>;
+    async fn get_stats_by_email(
+        &self,
+        email: &str,
+    ) -> Result<ProviderLookup<DarkSearchResponse>, reqwest::Error>;
+    async fn get_stats_by_domain(
+        &self,
+        domain: &str,
+    ) -> Result<ProviderLookup<DarkSearchResponse>, reqwest::Error>;
 }
 
 
@@ -34,3 +65,28 @@ pub trait SendGridProvider {
     fn new(key: &str)-> Self where Self: Sized;
     async fn send(&self, _: String) -> Result<(), reqwest::Error>;
 }
+
+#[allow(async_fn_in_trait)]
+pub trait SmsProvider {
+    // `to` is an E.164 number, the error keeps the provider's message
+    async fn send_sms(&self, to: &str, body: &str) -> Result<(), String>;
+}
+
+#[allow(async_fn_in_trait)]
+pub trait DnsResolver {
+    // every TXT record of `name`, the character strings of a record joined
+    async fn txt_records(&self, name: &str) -> Result<Vec<String>, String>;
+}
+
+#[allow(async_fn_in_trait)]
+pub trait HttpFetcher {
+    // body of a successful GET, anything else is an error
+    async fn fetch_text(&self, url: &str) -> Result<String, String>;
+}
+
+#[allow(async_fn_in_trait)]
+pub trait WebhookSender {
+    // status code of whatever the receiver answered, errors are for requests
+    // that got no answer at all
+    async fn post(&self, url: &str, headers: Vec<(&'static str, String)>, body: String) -> Result<u16, String>;
+}
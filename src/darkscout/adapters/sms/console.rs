@@ -0,0 +1,53 @@
+use tokio::io::AsyncWriteExt;
+
+use crate::darkscout::adapters::SmsProvider;
+
+// Development provider, messages are logged and optionally appended to a file
+#[derive(Clone)]
+pub struct ConsoleSms {
+    file: Option<String>,
+}
+
+impl ConsoleSms {
+    pub fn new(file: String) -> Self {
+        ConsoleSms {
+            file: Some(file).filter(|f| !f.is_empty()),
+        }
+    }
+}
+
+impl SmsProvider for ConsoleSms {
+    async fn send_sms(&self, to: &str, body: &str) -> Result<(), String> {
+        tracing::info!("SMS to {}: {}", to, body);
+        let Some(path) = &self.file else {
+            return Ok(());
+        };
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await
+            .map_err(|e| format!("Failed to open sms file: {}", e))?;
+        let line = format!("{}\t{}\t{}\n", chrono::Utc::now().to_rfc3339(), to, body);
+        file.write_all(line.as_bytes())
+            .await
+            .map_err(|e| format!("Failed to write sms file: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_console_sms_file() {
+        let path = std::env::temp_dir().join(format!("ds-sms-{}.log", uuid::Uuid::new_v4()));
+        let sms = ConsoleSms::new(path.to_string_lossy().to_string());
+        sms.send_sms("+15551234567", "Your code is 123456")
+            .await
+            .unwrap();
+        let written = tokio::fs::read_to_string(&path).await.unwrap();
+        assert!(written.contains("+15551234567\tYour code is 123456"));
+        tokio::fs::remove_file(&path).await.unwrap();
+    }
+}
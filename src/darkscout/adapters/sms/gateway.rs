@@ -0,0 +1,60 @@
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::darkscout::adapters::SmsProvider;
+
+#[derive(Serialize)]
+struct GatewayMessage<'a> {
+    from: &'a str,
+    to: &'a str,
+    body: &'a str,
+}
+
+const GATEWAY_TIMEOUT_SECS: u64 = 10;
+
+// Posts messages as json to an http sms gateway with a bearer api key
+#[derive(Clone)]
+pub struct HttpSmsGateway {
+    client: reqwest::Client,
+    url: String,
+    api_key: String,
+    sender: String,
+}
+
+impl HttpSmsGateway {
+    pub fn new(url: String, api_key: String, sender: String) -> Self {
+        HttpSmsGateway {
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(GATEWAY_TIMEOUT_SECS))
+                .build()
+                .expect("Couldn't create the sms gateway client"),
+            url,
+            api_key,
+            sender,
+        }
+    }
+}
+
+impl SmsProvider for HttpSmsGateway {
+    async fn send_sms(&self, to: &str, body: &str) -> Result<(), String> {
+        let response = self
+            .client
+            .post(&self.url)
+            .bearer_auth(&self.api_key)
+            .json(&GatewayMessage {
+                from: &self.sender,
+                to,
+                body,
+            })
+            .send()
+            .await
+            .map_err(|e| format!("Failed to reach sms gateway: {}", e))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(format!("Sms gateway returned {}: {}", status, text));
+        }
+        Ok(())
+    }
+}
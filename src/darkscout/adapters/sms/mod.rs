@@ -0,0 +1,36 @@
+pub mod console;
+pub mod gateway;
+
+use crate::darkscout::adapters::sms::console::ConsoleSms;
+use crate::darkscout::adapters::sms::gateway::HttpSmsGateway;
+use crate::darkscout::adapters::SmsProvider;
+use crate::darkscout::types::{SmsBackend, SmsSettings};
+
+// The provider picked in the [sms] settings
+#[derive(Clone)]
+pub enum SmsClient {
+    Console(ConsoleSms),
+    Gateway(HttpSmsGateway),
+}
+
+impl SmsClient {
+    pub fn from_settings(settings: &SmsSettings) -> Self {
+        match settings.backend {
+            SmsBackend::console => SmsClient::Console(ConsoleSms::new(settings.file.clone())),
+            SmsBackend::gateway => SmsClient::Gateway(HttpSmsGateway::new(
+                settings.gateway_url.clone(),
+                settings.api_key.clone(),
+                settings.sender.clone(),
+            )),
+        }
+    }
+}
+
+impl SmsProvider for SmsClient {
+    async fn send_sms(&self, to: &str, body: &str) -> Result<(), String> {
+        match self {
+            SmsClient::Console(c) => c.send_sms(to, body).await,
+            SmsClient::Gateway(g) => g.send_sms(to, body).await,
+        }
+    }
+}
@@ -3,6 +3,8 @@ use lettre::message::Mailbox;
 use lettre::transport::smtp::authentication::Credentials;
 use lettre::{Message, SmtpTransport, Transport};
 
+use crate::darkscout::types::workspace::WorkspaceBranding;
+
 pub fn create_smtp_client(smtp_username: String, smtp_password: String) -> SmtpTransport {
     let creds = Credentials::new(smtp_username, smtp_password);
     let mailer = SmtpTransport::relay("smtp.gmail.com")
@@ -40,17 +42,49 @@ pub fn build_message(
     };
 }
 
-type MailSendError = &'static str;
+// keeps the smtp error text so failed deliveries can be reported
+type MailSendError = String;
 
 async fn send_mail(m: &Message, s: &SmtpTransport) -> Result<String, MailSendError> {
     return match s.send(m) {
         Ok(_) => Ok("Sent succesfully".to_owned()),
         Err(e) => {
-            dbg!(e);
-            Err("Failed to send the message")
+            tracing::debug!("Failed to send the message {}", e);
+            Err(format!("Failed to send the message: {}", e))
         },
     };
 }
+pub fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// Wraps the html content with the workspace logo and name
+pub fn brand_email(content: String, branding: &WorkspaceBranding) -> String {
+    let name = escape_html(&branding.name);
+    let logo = match &branding.logo_url {
+        Some(url) => format!(
+            r#"<img src="{}" alt="{}" style="max-height:48px" />"#,
+            escape_html(url),
+            name
+        ),
+        None => String::new(),
+    };
+    format!(
+        r#"<html>
+    <body>
+        <div>{}<h2>{}</h2></div>
+        {}
+        <p style="color:#888888">Sent by DarkScout on behalf of {}</p>
+    </body>
+</html>"#,
+        logo, name, content, name
+    )
+}
+
 pub async fn send_smtp_email(sender: String, rx: String, body: String, sub: &str, s: &SmtpTransport) -> Result<String, MailSendError> {
     let sender = new_mailbox(sender)?;
     let rx = new_mailbox(rx)?;
@@ -93,6 +127,18 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_brand_email() {
+        let branding = WorkspaceBranding {
+            name: String::from("Acme <Security>"),
+            logo_url: Some(String::from("https://acme.com/logo.png")),
+        };
+        let html = brand_email(String::from("<h1>Hello</h1>"), &branding);
+        assert!(html.contains("Acme &lt;Security&gt;"));
+        assert!(html.contains(r#"src="https://acme.com/logo.png""#));
+        assert!(html.contains("<h1>Hello</h1>"));
+    }
+
     #[tokio::test]
     async fn test_send_email() {
         let sender = new_mailbox(String::from("iamtheparzival@gmail.com")).unwrap();
@@ -0,0 +1,72 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use reqwest::Url;
+
+use crate::darkscout::adapters::WebhookSender;
+use crate::darkscout::utils::network::{check_url_host, PublicResolver};
+
+const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone)]
+pub struct WebhookClient {
+    client: reqwest::Client,
+    allow_loopback: bool,
+}
+
+impl WebhookClient {
+    // `allow_loopback` is for local testing only, it also allows plain http
+    pub fn new(allow_loopback: bool) -> Result<Self, String> {
+        // a redirect could carry the signed payload somewhere else, and the
+        // urls come from workspace admins so only public addresses are called
+        let client = reqwest::Client::builder()
+            .timeout(SEND_TIMEOUT)
+            .redirect(reqwest::redirect::Policy::none())
+            .https_only(!allow_loopback)
+            .no_proxy()
+            .dns_resolver(Arc::new(PublicResolver { allow_loopback }))
+            .user_agent("DarkScout-Webhooks/1.0")
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(WebhookClient {
+            client,
+            allow_loopback,
+        })
+    }
+}
+
+// what the delivery log keeps of a failed request, without the details of
+// whatever answered
+fn delivery_error(e: reqwest::Error) -> String {
+    tracing::debug!("Webhook delivery failed: {}", e);
+    let reason = if e.is_timeout() {
+        "timed out"
+    } else if e.is_connect() {
+        "couldn't connect"
+    } else {
+        "request failed"
+    };
+    String::from(reason)
+}
+
+impl WebhookSender for WebhookClient {
+    async fn post(
+        &self,
+        url: &str,
+        headers: Vec<(&'static str, String)>,
+        body: String,
+    ) -> Result<u16, String> {
+        let url = Url::parse(url).map_err(|_| String::from("invalid url"))?;
+        check_url_host(&url, self.allow_loopback)?;
+        let mut request = self
+            .client
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body);
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+        let response = request.send().await.map_err(delivery_error)?;
+        Ok(response.status().as_u16())
+    }
+}
@@ -0,0 +1,164 @@
+use crate::darkscout::types::alerts::{AlertRecipient, AlertStatus, BreachAlert};
+use crate::darkscout::types::store::{AlertsRepo, AlertsStore};
+use sqlx::{Error, PgConnection};
+use uuid::Uuid;
+
+// Stores the alerts as part of the caller's transaction and returns the ones
+// actually created, a breach is only alerted once per asset
+pub async fn insert_breach_alerts(
+    connection: &mut PgConnection,
+    alerts: Vec<BreachAlert>,
+) -> Result<Vec<BreachAlert>, Error> {
+    let mut created = Vec::with_capacity(alerts.len());
+    for alert in alerts {
+        let row: Option<BreachAlert> = sqlx::query_as(
+            r#"
+        INSERT INTO BREACH_ALERTS
+        (ID, WORKSPACE, ASSET_TYPE, ASSET_ID, ASSET, BREACH_NAME, DATA_CLASSES, SEVERITY)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        ON CONFLICT (ASSET_ID, BREACH_NAME) DO NOTHING
+        RETURNING *
+        "#,
+        )
+        .bind(alert.id)
+        .bind(alert.workspace)
+        .bind(alert.asset_type)
+        .bind(alert.asset_id)
+        .bind(&alert.asset)
+        .bind(&alert.breach_name)
+        .bind(&alert.data_classes)
+        .bind(alert.severity)
+        .fetch_optional(&mut *connection)
+        .await?;
+        created.extend(row);
+    }
+    Ok(created)
+}
+
+impl AlertsStore for AlertsRepo {
+    async fn claim_unnotified_alerts(
+        &self,
+        limit: i64,
+        lease_minutes: i32,
+    ) -> Result<Vec<BreachAlert>, Error> {
+        sqlx::query_as(
+            r#"
+        UPDATE BREACH_ALERTS SET NOTIFY_AFTER = CURRENT_TIMESTAMP + make_interval(mins => $2)
+        WHERE ID IN (
+            SELECT ID FROM BREACH_ALERTS
+            WHERE NOTIFIED_AT IS NULL AND NOTIFY_AFTER <= CURRENT_TIMESTAMP
+            ORDER BY CREATED_AT
+            LIMIT $1
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING *
+        "#,
+        )
+        .bind(limit)
+        .bind(lease_minutes)
+        .fetch_all(&self.db)
+        .await
+    }
+
+    async fn mark_alerts_notified(&self, ids: Vec<Uuid>) -> Result<(), Error> {
+        sqlx::query(
+            r#"
+        UPDATE BREACH_ALERTS SET NOTIFIED_AT = CURRENT_TIMESTAMP WHERE ID = ANY($1)
+        "#,
+        )
+        .bind(ids)
+        .execute(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    async fn list_breach_alerts(
+        &self,
+        workspace_id: Uuid,
+        status: Option<AlertStatus>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<BreachAlert>, i64), Error> {
+        let filter = r#"
+        FROM BREACH_ALERTS
+        WHERE BREACH_ALERTS.WORKSPACE = $1
+        AND ($2::ALERT_STATUS IS NULL OR BREACH_ALERTS.STATUS = $2)
+        "#;
+        let (total,): (i64,) = sqlx::query_as(&format!("SELECT COUNT(*) {}", filter))
+            .bind(workspace_id)
+            .bind(status)
+            .fetch_one(&self.db)
+            .await?;
+        let alerts: Vec<BreachAlert> = sqlx::query_as(&format!(
+            r#"
+        SELECT *
+        {}
+        ORDER BY BREACH_ALERTS.CREATED_AT DESC
+        LIMIT $3 OFFSET $4
+        "#,
+            filter
+        ))
+        .bind(workspace_id)
+        .bind(status)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.db)
+        .await?;
+        Ok((alerts, total))
+    }
+
+    async fn get_breach_alert(&self, workspace_id: Uuid, id: Uuid) -> Result<BreachAlert, Error> {
+        sqlx::query_as(
+            r#"
+        SELECT * FROM BREACH_ALERTS WHERE ID = $1 AND WORKSPACE = $2
+        "#,
+        )
+        .bind(id)
+        .bind(workspace_id)
+        .fetch_one(&self.db)
+        .await
+    }
+
+    async fn update_alert_status(
+        &self,
+        workspace_id: Uuid,
+        id: Uuid,
+        from: AlertStatus,
+        to: AlertStatus,
+        member_id: Uuid,
+    ) -> Result<BreachAlert, Error> {
+        // resolving an alert nobody acknowledged doesn't fill in ACKNOWLEDGED_BY
+        let alert: Option<BreachAlert> = sqlx::query_as(
+            r#"
+        UPDATE BREACH_ALERTS SET STATUS = $3, UPDATED_AT = CURRENT_TIMESTAMP,
+        ACKNOWLEDGED_BY = CASE WHEN $3 = 'ACKNOWLEDGED' THEN $5 ELSE ACKNOWLEDGED_BY END,
+        ACKNOWLEDGED_AT = CASE WHEN $3 = 'ACKNOWLEDGED' THEN CURRENT_TIMESTAMP ELSE ACKNOWLEDGED_AT END,
+        RESOLVED_BY = CASE WHEN $3 = 'RESOLVED' THEN $5 ELSE RESOLVED_BY END,
+        RESOLVED_AT = CASE WHEN $3 = 'RESOLVED' THEN CURRENT_TIMESTAMP ELSE RESOLVED_AT END
+        WHERE ID = $1 AND WORKSPACE = $2 AND STATUS = $4
+        RETURNING *
+        "#,
+        )
+        .bind(id)
+        .bind(workspace_id)
+        .bind(to)
+        .bind(from)
+        .bind(member_id)
+        .fetch_optional(&self.db)
+        .await?;
+        alert.ok_or(Error::RowNotFound)
+    }
+
+    async fn get_alert_recipients(&self, workspace_id: Uuid) -> Result<Vec<AlertRecipient>, Error> {
+        sqlx::query_as(
+            r#"
+        SELECT MEMBERS.ID AS MEMBER_ID, USERS.EMAIL
+        FROM MEMBERS INNER JOIN USERS ON USERS.ID = MEMBERS.USER_ID
+        WHERE MEMBERS.WORKSPACE = $1 AND MEMBERS.STATUS = 'VERIFIED'
+        "#,
+        )
+        .bind(workspace_id)
+        .fetch_all(&self.db)
+        .await
+    }
+}
@@ -1,4 +1,8 @@
-use crate::darkscout::types::invitations::{InvitationRecord, InvitationStatus, VerificationData};
+use crate::darkscout::types::invitations::{
+    InvitationRecord, InvitationStatus, InvitationSummary, InviteLink, NewInviteLink,
+    TransportType, VerificationData, MAX_CODE_ATTEMPTS, MAX_INVITATION_RESENDS,
+    PHONE_CODE_TTL_MINUTES, RESEND_INTERVAL_MINUTES,
+};
 use crate::darkscout::types::{
     store::{InvitationsStore},
 };
@@ -6,13 +10,18 @@ use chrono::{Local};
 use tracing::debug;
 use uuid::Uuid;
 use crate::darkscout::types::invitations::Invitation;
+use crate::darkscout::types::member::{JoinOutcome, MemberRecord};
+use crate::darkscout::database::members::insert_member_with_seat;
 use crate::darkscout::types::store::InvitationRepo;
+use crate::darkscout::types::workspace::MemberWorkspaceDetails;
 
 impl InvitationsStore for InvitationRepo {
     async fn create_new(&self, record: Invitation) -> Result<Invitation, sqlx::error::Error> {
         let record_ = record.clone();
         return match sqlx::query(r#"insert into invitations
-        (id, subject, msg, email, details, created_by, sent_to, workspace) values ($1, $2, $3, $4, $5, $6, $7, $8)"#)
+        (id, subject, msg, email, details, created_by, sent_to, workspace, transport, status, inv_type, role, phone, expires_at)
+        values ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13,
+        CURRENT_TIMESTAMP + MAKE_INTERVAL(MINS => $14))"#)
             .bind(record.id)
             .bind(record.subject)
             .bind(record.msg)
@@ -21,6 +30,16 @@ impl InvitationsStore for InvitationRepo {
             .bind(record.created_by)
             .bind(record.sent_to)
             .bind(record.workspace)
+            .bind(record.transport)
+            .bind(record.status)
+            .bind(record.inv_type)
+            .bind(record.role)
+            .bind(record.phone)
+            .bind(if record_.transport == TransportType::PHONE_CODE {
+                Some(PHONE_CODE_TTL_MINUTES)
+            } else {
+                None
+            })
             .execute(&self.db).await {
             Ok(_) => Ok(record_),
             Err(err) => {
@@ -47,7 +66,13 @@ impl InvitationsStore for InvitationRepo {
         id: &Uuid,
     ) -> Result<VerificationData, sqlx::error::Error> {
         return match sqlx::query_as::<_, VerificationData>(
-            r#"SELECT * FROM INVITATIONS WHERE SENT_TO = $1 AND STATUS != 'DELETED'"#,
+            r#"
+            SELECT INVITATIONS.* FROM INVITATIONS
+            INNER JOIN USERS ON USERS.ID = INVITATIONS.SENT_TO
+            WHERE INVITATIONS.SENT_TO = $1 AND INVITATIONS.STATUS != 'DELETED'
+            AND INVITATIONS.INV_TYPE = 'VERIFY_TOKEN' AND USERS.STATUS = 'REGISTERED'
+            ORDER BY INVITATIONS.CREATED_AT DESC LIMIT 1
+            "#,
         )
         .bind(id)
         .fetch_one(&self.db)
@@ -64,7 +89,7 @@ impl InvitationsStore for InvitationRepo {
     ) -> Result<(), sqlx::error::Error> {
         let ts = Local::now().naive_utc();
         return match sqlx::query(
-            r#"UPDATE INVITATIONS SET STATUS = $1, UPDATED_AT = $2 WHERE CREATED_BY = $3"#,
+            r#"UPDATE INVITATIONS SET STATUS = $1, UPDATED_AT = $2 WHERE ID = $3"#,
         )
         .bind(status as InvitationStatus)
         .bind(ts)
@@ -72,11 +97,316 @@ impl InvitationsStore for InvitationRepo {
         .execute(&self.db)
         .await
         {
-            Ok(result) => {
-                dbg!(result);
-                Ok(())
-            }
+            Ok(result) if result.rows_affected() == 1 => Ok(()),
+            Ok(_) => Err(sqlx::error::Error::RowNotFound),
             Err(e) => Err(e),
         };
     }
+
+    async fn get_invitation(&self, id: Uuid) -> Result<Invitation, sqlx::error::Error> {
+        let invitation: Invitation = sqlx::query_as(r#"SELECT * FROM INVITATIONS WHERE ID = $1"#)
+            .bind(id)
+            .fetch_one(&self.db)
+            .await?;
+        Ok(invitation)
+    }
+
+    async fn has_pending_invitation(
+        &self,
+        workspace_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<bool, sqlx::error::Error> {
+        let (pending,): (bool,) = sqlx::query_as(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM INVITATIONS
+                WHERE WORKSPACE = $1 AND SENT_TO = $2 AND INV_TYPE = 'MEMBER_INVITATION'
+                AND STATUS IN ('QUEUED', 'INVITED')
+            )
+            "#,
+        )
+        .bind(workspace_id)
+        .bind(user_id)
+        .fetch_one(&self.db)
+        .await?;
+        Ok(pending)
+    }
+
+    async fn accept_invitation(
+        &self,
+        invitation_id: Uuid,
+        member: MemberRecord,
+        details: MemberWorkspaceDetails,
+        member_limit: Option<i64>,
+    ) -> Result<JoinOutcome, sqlx::error::Error> {
+        let mut transaction = self.db.begin().await?;
+        // only one accept can win, a used or revoked invitation matches no row
+        let result = sqlx::query(
+            r#"
+            UPDATE INVITATIONS SET STATUS = 'ACCEPTED', ACCEPTED_AT = CURRENT_TIMESTAMP,
+            UPDATED_AT = CURRENT_TIMESTAMP
+            WHERE ID = $1 AND STATUS IN ('QUEUED', 'INVITED')
+            "#,
+        )
+        .bind(invitation_id)
+        .execute(&mut *transaction)
+        .await?;
+        if result.rows_affected() != 1 {
+            return Err(sqlx::error::Error::RowNotFound);
+        }
+        // the invitation stays pending when the member can't be added
+        let outcome =
+            insert_member_with_seat(&mut transaction, &member, &details, member_limit).await?;
+        if outcome == JoinOutcome::JOINED {
+            transaction.commit().await?;
+        }
+        Ok(outcome)
+    }
+
+    async fn list_workspace_invitations(
+        &self,
+        workspace_id: Uuid,
+    ) -> Result<Vec<InvitationSummary>, sqlx::error::Error> {
+        let invitations: Vec<InvitationSummary> = sqlx::query_as(
+            r#"
+            SELECT INVITATIONS.ID, INVITATIONS.EMAIL, INVITATIONS.ROLE, INVITATIONS.STATUS,
+                INVITATIONS.ERROR, INVITATIONS.CREATED_BY, USERS.EMAIL AS SENDER_EMAIL,
+                INVITATIONS.RESEND_COUNT, INVITATIONS.LAST_SENT_AT, INVITATIONS.CREATED_AT
+            FROM INVITATIONS
+            INNER JOIN USERS ON USERS.ID = INVITATIONS.CREATED_BY
+            WHERE INVITATIONS.WORKSPACE = $1 AND INVITATIONS.INV_TYPE = 'MEMBER_INVITATION'
+            ORDER BY INVITATIONS.CREATED_AT DESC
+            "#,
+        )
+        .bind(workspace_id)
+        .fetch_all(&self.db)
+        .await?;
+        Ok(invitations)
+    }
+
+    async fn mark_invitation_sent(&self, id: Uuid) -> Result<(), sqlx::error::Error> {
+        sqlx::query(
+            r#"
+            UPDATE INVITATIONS SET STATUS = 'INVITED', ERROR = NULL,
+            LAST_SENT_AT = CURRENT_TIMESTAMP, UPDATED_AT = CURRENT_TIMESTAMP
+            WHERE ID = $1 AND STATUS IN ('QUEUED', 'FAILED', 'INVITED')
+            "#,
+        )
+        .bind(id)
+        .execute(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    async fn mark_invitation_failed(&self, id: Uuid, error: String) -> Result<(), sqlx::error::Error> {
+        sqlx::query(
+            r#"
+            UPDATE INVITATIONS SET STATUS = 'FAILED', ERROR = LEFT($2, 1000),
+            UPDATED_AT = CURRENT_TIMESTAMP
+            WHERE ID = $1 AND STATUS IN ('QUEUED', 'FAILED', 'INVITED')
+            "#,
+        )
+        .bind(id)
+        .bind(error)
+        .execute(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    async fn claim_invitation_resend(
+        &self,
+        workspace_id: Uuid,
+        id: Uuid,
+        msg: String,
+    ) -> Result<Option<Invitation>, sqlx::error::Error> {
+        // the row is only returned when the resend limits allow another email
+        let invitation: Option<Invitation> = sqlx::query_as(
+            r#"
+            UPDATE INVITATIONS SET MSG = $3, STATUS = 'QUEUED', RESEND_COUNT = RESEND_COUNT + 1,
+            LAST_SENT_AT = CURRENT_TIMESTAMP, UPDATED_AT = CURRENT_TIMESTAMP
+            WHERE ID = $1 AND WORKSPACE = $2 AND INV_TYPE = 'MEMBER_INVITATION'
+            AND STATUS IN ('QUEUED', 'INVITED', 'FAILED')
+            AND RESEND_COUNT < $4
+            AND (LAST_SENT_AT IS NULL OR LAST_SENT_AT < CURRENT_TIMESTAMP - make_interval(mins => $5))
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(workspace_id)
+        .bind(msg)
+        .bind(MAX_INVITATION_RESENDS)
+        .bind(RESEND_INTERVAL_MINUTES)
+        .fetch_optional(&self.db)
+        .await?;
+        Ok(invitation)
+    }
+
+    async fn revoke_invitation(&self, workspace_id: Uuid, id: Uuid) -> Result<(), sqlx::error::Error> {
+        let result = sqlx::query(
+            r#"
+            UPDATE INVITATIONS SET STATUS = 'DELETED', DELETED_AT = CURRENT_TIMESTAMP,
+            UPDATED_AT = CURRENT_TIMESTAMP
+            WHERE ID = $1 AND WORKSPACE = $2 AND INV_TYPE = 'MEMBER_INVITATION'
+            AND STATUS IN ('QUEUED', 'INVITED', 'FAILED')
+            "#,
+        )
+        .bind(id)
+        .bind(workspace_id)
+        .execute(&self.db)
+        .await?;
+        if result.rows_affected() != 1 {
+            return Err(sqlx::error::Error::RowNotFound);
+        }
+        Ok(())
+    }
+
+    async fn get_phone_verification(
+        &self,
+        user_id: Uuid,
+        phone: &str,
+    ) -> Result<VerificationData, sqlx::error::Error> {
+        let data: VerificationData = sqlx::query_as(
+            r#"
+            SELECT * FROM INVITATIONS
+            WHERE SENT_TO = $1 AND PHONE = $2 AND STATUS != 'DELETED'
+            AND INV_TYPE = 'VERIFY_TOKEN' AND TRANSPORT = 'PHONE_CODE'
+            ORDER BY CREATED_AT DESC LIMIT 1
+            "#,
+        )
+        .bind(user_id)
+        .bind(phone)
+        .fetch_one(&self.db)
+        .await?;
+        Ok(data)
+    }
+
+    async fn check_verification_code(
+        &self,
+        id: Uuid,
+        code: &str,
+    ) -> Result<Option<bool>, sqlx::error::Error> {
+        let matched: Option<(bool,)> = sqlx::query_as(
+            r#"
+            UPDATE INVITATIONS SET ATTEMPTS = ATTEMPTS + 1
+            WHERE ID = $1 AND STATUS != 'DELETED' AND ATTEMPTS < $3
+            AND (EXPIRES_AT IS NULL OR EXPIRES_AT > CURRENT_TIMESTAMP)
+            RETURNING DETAILS = $2
+            "#,
+        )
+        .bind(id)
+        .bind(code)
+        .bind(MAX_CODE_ATTEMPTS)
+        .fetch_optional(&self.db)
+        .await?;
+        Ok(matched.map(|(m,)| m))
+    }
+
+    async fn create_invite_link(&self, link: NewInviteLink) -> Result<InviteLink, sqlx::error::Error> {
+        let link: InviteLink = sqlx::query_as(
+            r#"
+            INSERT INTO INVITE_LINKS
+            (ID, WORKSPACE, TOKEN, ROLE, DOMAIN, MAX_USES, EXPIRES_AT, CREATED_BY)
+            VALUES ($1, $2, $3, $4, $5, $6, CURRENT_TIMESTAMP + MAKE_INTERVAL(HOURS => $7), $8)
+            RETURNING *
+            "#,
+        )
+        .bind(link.id)
+        .bind(link.workspace)
+        .bind(link.token)
+        .bind(link.role)
+        .bind(link.domain)
+        .bind(link.max_uses)
+        .bind(link.ttl_hours)
+        .bind(link.created_by)
+        .fetch_one(&self.db)
+        .await?;
+        Ok(link)
+    }
+
+    async fn list_invite_links(&self, workspace_id: Uuid) -> Result<Vec<InviteLink>, sqlx::error::Error> {
+        sqlx::query_as(
+            r#"
+            SELECT * FROM INVITE_LINKS WHERE WORKSPACE = $1
+            ORDER BY CREATED_AT DESC
+            "#,
+        )
+        .bind(workspace_id)
+        .fetch_all(&self.db)
+        .await
+    }
+
+    async fn revoke_invite_link(&self, workspace_id: Uuid, link_id: Uuid) -> Result<(), sqlx::error::Error> {
+        let result = sqlx::query(
+            r#"
+            UPDATE INVITE_LINKS SET REVOKED_AT = CURRENT_TIMESTAMP
+            WHERE ID = $1 AND WORKSPACE = $2 AND REVOKED_AT IS NULL
+            "#,
+        )
+        .bind(link_id)
+        .bind(workspace_id)
+        .execute(&self.db)
+        .await?;
+        if result.rows_affected() != 1 {
+            return Err(sqlx::error::Error::RowNotFound);
+        }
+        Ok(())
+    }
+
+    async fn get_invite_link(&self, token: &str) -> Result<InviteLink, sqlx::error::Error> {
+        sqlx::query_as(r#"SELECT * FROM INVITE_LINKS WHERE TOKEN = $1"#)
+            .bind(token)
+            .fetch_one(&self.db)
+            .await
+    }
+
+    async fn redeem_invite_link(
+        &self,
+        link_id: Uuid,
+        member: MemberRecord,
+        details: MemberWorkspaceDetails,
+        member_limit: Option<i64>,
+    ) -> Result<JoinOutcome, sqlx::error::Error> {
+        let mut transaction = self.db.begin().await?;
+        // concurrent redemptions wait on the link row, so none can go over
+        // the maximum
+        let live: Option<(Uuid,)> = sqlx::query_as(
+            r#"
+            SELECT ID FROM INVITE_LINKS
+            WHERE ID = $1 AND REVOKED_AT IS NULL AND EXPIRES_AT > CURRENT_TIMESTAMP
+            AND USE_COUNT < MAX_USES
+            FOR UPDATE
+            "#,
+        )
+        .bind(link_id)
+        .fetch_optional(&mut *transaction)
+        .await?;
+        if live.is_none() {
+            return Err(sqlx::error::Error::RowNotFound);
+        }
+        sqlx::query(r#"UPDATE INVITE_LINKS SET USE_COUNT = USE_COUNT + 1 WHERE ID = $1"#)
+            .bind(link_id)
+            .execute(&mut *transaction)
+            .await?;
+        // membership and seats are checked again under the lock, the use is
+        // given back when the user can't join
+        let outcome =
+            insert_member_with_seat(&mut transaction, &member, &details, member_limit).await?;
+        if outcome != JoinOutcome::JOINED {
+            return Ok(outcome);
+        }
+        sqlx::query(
+            r#"
+            INSERT INTO INVITE_LINK_REDEMPTIONS (ID, LINK_ID, USER_ID, MEMBER_ID)
+            VALUES ($1, $2, $3, $4)
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(link_id)
+        .bind(member.user_id)
+        .bind(member.id)
+        .execute(&mut *transaction)
+        .await?;
+        transaction.commit().await?;
+        Ok(JoinOutcome::JOINED)
+    }
 }
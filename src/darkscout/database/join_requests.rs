@@ -0,0 +1,221 @@
+use crate::darkscout::types::join_requests::{JoinPolicy, JoinRequest, JoinRequestSummary};
+use crate::darkscout::types::member::MemberRecord;
+use crate::darkscout::types::store::{JoinRequestRepo, JoinRequestsStore};
+use crate::darkscout::types::workspace::MemberWorkspaceDetails;
+use sqlx::Error;
+use uuid::Uuid;
+
+impl JoinRequestsStore for JoinRequestRepo {
+    async fn get_join_policy(&self, workspace_id: Uuid) -> Result<JoinPolicy, Error> {
+        let (policy,): (JoinPolicy,) =
+            sqlx::query_as(r#"SELECT JOIN_POLICY FROM WORKSPACES WHERE ID = $1"#)
+                .bind(workspace_id)
+                .fetch_one(&self.db)
+                .await?;
+        Ok(policy)
+    }
+
+    async fn set_join_policy(&self, workspace_id: Uuid, policy: JoinPolicy) -> Result<(), Error> {
+        let result = sqlx::query(
+            r#"
+        UPDATE WORKSPACES SET JOIN_POLICY = $1, UPDATED_AT = CURRENT_TIMESTAMP
+        WHERE ID = $2
+        "#,
+        )
+        .bind(policy)
+        .bind(workspace_id)
+        .execute(&self.db)
+        .await?;
+        if result.rows_affected() != 1 {
+            return Err(Error::RowNotFound);
+        }
+        Ok(())
+    }
+
+    // the closest covering domain wins, then the oldest verification when
+    // several workspaces claim it
+    async fn find_domain_workspace(
+        &self,
+        domain: &str,
+        parents: &[String],
+    ) -> Result<Option<(Uuid, JoinPolicy)>, Error> {
+        sqlx::query_as(
+            r#"
+        SELECT WORKSPACES.ID, WORKSPACES.JOIN_POLICY FROM VERIFIED_DOMAINS
+        INNER JOIN WORKSPACES ON WORKSPACES.ID = VERIFIED_DOMAINS.WORKSPACE
+        WHERE VERIFIED_DOMAINS.IS_VERIFIED
+        AND (VERIFIED_DOMAINS.DOMAIN = $1
+            OR (VERIFIED_DOMAINS.INCLUDE_SUBDOMAINS AND VERIFIED_DOMAINS.DOMAIN = ANY($2)))
+        AND WORKSPACES.JOIN_POLICY != 'OFF' AND WORKSPACES.STATUS = 'VERIFIED'
+        AND NOT WORKSPACES.DELETED
+        ORDER BY LENGTH(VERIFIED_DOMAINS.DOMAIN) DESC, VERIFIED_DOMAINS.CREATED_AT LIMIT 1
+        "#,
+        )
+        .bind(domain)
+        .bind(parents)
+        .fetch_optional(&self.db)
+        .await
+    }
+
+    async fn create_join_request(&self, request: JoinRequest) -> Result<(), Error> {
+        sqlx::query(
+            r#"
+        INSERT INTO JOIN_REQUESTS (ID, WORKSPACE, USER_ID, DOMAIN, STATUS)
+        VALUES ($1, $2, $3, $4, $5)
+        "#,
+        )
+        .bind(request.id)
+        .bind(request.workspace)
+        .bind(request.user_id)
+        .bind(request.domain)
+        .bind(request.status)
+        .execute(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    // Moves the request of a user who just verified their email in front of
+    // the admins. Returns false when the user has no request there.
+    async fn confirm_join_request(&self, workspace_id: Uuid, user_id: Uuid) -> Result<bool, Error> {
+        let result = sqlx::query(
+            r#"
+        UPDATE JOIN_REQUESTS SET STATUS = 'PENDING', UPDATED_AT = CURRENT_TIMESTAMP
+        WHERE WORKSPACE = $1 AND USER_ID = $2 AND STATUS = 'UNVERIFIED'
+        "#,
+        )
+        .bind(workspace_id)
+        .bind(user_id)
+        .execute(&self.db)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn list_join_requests(
+        &self,
+        workspace_id: Uuid,
+    ) -> Result<Vec<JoinRequestSummary>, Error> {
+        sqlx::query_as(
+            r#"
+        SELECT JOIN_REQUESTS.ID, JOIN_REQUESTS.USER_ID, USERS.EMAIL, USERS.FIRST_NAME,
+            USERS.LAST_NAME, JOIN_REQUESTS.DOMAIN, JOIN_REQUESTS.STATUS, JOIN_REQUESTS.CREATED_AT
+        FROM JOIN_REQUESTS
+        INNER JOIN USERS ON USERS.ID = JOIN_REQUESTS.USER_ID
+        WHERE JOIN_REQUESTS.WORKSPACE = $1 AND JOIN_REQUESTS.STATUS = 'PENDING'
+        ORDER BY JOIN_REQUESTS.CREATED_AT
+        "#,
+        )
+        .bind(workspace_id)
+        .fetch_all(&self.db)
+        .await
+    }
+
+    async fn get_join_request(
+        &self,
+        workspace_id: Uuid,
+        request_id: Uuid,
+    ) -> Result<JoinRequest, Error> {
+        sqlx::query_as(
+            r#"
+        SELECT ID, WORKSPACE, USER_ID, DOMAIN, STATUS FROM JOIN_REQUESTS
+        WHERE ID = $1 AND WORKSPACE = $2
+        "#,
+        )
+        .bind(request_id)
+        .bind(workspace_id)
+        .fetch_one(&self.db)
+        .await
+    }
+
+    async fn get_latest_join_request(&self, user_id: Uuid) -> Result<JoinRequest, Error> {
+        sqlx::query_as(
+            r#"
+        SELECT ID, WORKSPACE, USER_ID, DOMAIN, STATUS FROM JOIN_REQUESTS
+        WHERE USER_ID = $1
+        ORDER BY CREATED_AT DESC LIMIT 1
+        "#,
+        )
+        .bind(user_id)
+        .fetch_one(&self.db)
+        .await
+    }
+
+    async fn approve_join_request(
+        &self,
+        request_id: Uuid,
+        decided_by: Uuid,
+        member: MemberRecord,
+        details: MemberWorkspaceDetails,
+    ) -> Result<(), Error> {
+        let mut transaction = self.db.begin().await?;
+        // only one decision can win, a decided request matches no row
+        let result = sqlx::query(
+            r#"
+        UPDATE JOIN_REQUESTS SET STATUS = 'APPROVED', DECIDED_BY = $1,
+        DECIDED_AT = CURRENT_TIMESTAMP, UPDATED_AT = CURRENT_TIMESTAMP
+        WHERE ID = $2 AND STATUS = 'PENDING'
+        "#,
+        )
+        .bind(decided_by)
+        .bind(request_id)
+        .execute(&mut *transaction)
+        .await?;
+        if result.rows_affected() != 1 {
+            return Err(Error::RowNotFound);
+        }
+        sqlx::query(
+            r#"
+        INSERT INTO MEMBERS
+        (ID, JOINED_AT, PROFILE_PICTURE, STATUS, ROLE, WORKSPACE, USER_ID, INVITED_BY, ROLE_GRANTED_BY)
+        VALUES ($1, $2, COALESCE($3, ''), $4, $5, $6, $7, $8, $9)
+        "#,
+        )
+        .bind(&member.id)
+        .bind(&member.joined_at)
+        .bind(&member.profile_picture)
+        .bind(&member.status)
+        .bind(&member.role)
+        .bind(&member.workspace)
+        .bind(&member.user_id)
+        .bind(&member.invited_by)
+        .bind(&member.role_granted_by)
+        .execute(&mut *transaction)
+        .await?;
+        sqlx::query(
+            r#"
+        INSERT INTO MEMBER_WORKSPACE_DETAILS
+        (ID, WORKSPACE_ORDER, IS_DEFAULT, IS_FAV, MEMBER_ID, WORKSPACE_ID, USER_ID)
+        VALUES ($1, $2,
+            NOT EXISTS (SELECT 1 FROM MEMBER_WORKSPACE_DETAILS WHERE USER_ID = $6 AND IS_DEFAULT),
+            $3, $4, $5, $6)
+        "#,
+        )
+        .bind(&details.id)
+        .bind(&details.workspace_order)
+        .bind(&details.is_fav)
+        .bind(&details.member_id)
+        .bind(&details.workspace_id)
+        .bind(&details.user_id)
+        .execute(&mut *transaction)
+        .await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn reject_join_request(&self, request_id: Uuid, decided_by: Uuid) -> Result<(), Error> {
+        let result = sqlx::query(
+            r#"
+        UPDATE JOIN_REQUESTS SET STATUS = 'REJECTED', DECIDED_BY = $1,
+        DECIDED_AT = CURRENT_TIMESTAMP, UPDATED_AT = CURRENT_TIMESTAMP
+        WHERE ID = $2 AND STATUS = 'PENDING'
+        "#,
+        )
+        .bind(decided_by)
+        .bind(request_id)
+        .execute(&self.db)
+        .await?;
+        if result.rows_affected() != 1 {
+            return Err(Error::RowNotFound);
+        }
+        Ok(())
+    }
+}
@@ -1,14 +1,85 @@
 use sqlx::error::Error;
 use sqlx::postgres::PgQueryResult;
+use sqlx::PgConnection;
 use uuid::Uuid;
 
-use crate::darkscout::types::member::MemberData;
+use crate::darkscout::types::member::{
+    JoinOutcome, MemberData, MemberListItem, MemberRole, MemberStatus,
+};
+use crate::darkscout::types::plans::has_room;
 use crate::darkscout::types::store::MemberRepo;
+use crate::darkscout::types::workspace::MemberWorkspaceDetails;
 use crate::darkscout::types::{
     member::MemberRecord,
     store::{MemberStore, PgStore},
 };
 
+// Adds the member as part of the caller's transaction. The workspace row is
+// locked first so concurrent joins see each other's seats and memberships.
+pub async fn insert_member_with_seat(
+    connection: &mut PgConnection,
+    member: &MemberRecord,
+    details: &MemberWorkspaceDetails,
+    member_limit: Option<i64>,
+) -> Result<JoinOutcome, Error> {
+    sqlx::query("SELECT ID FROM WORKSPACES WHERE ID = $1 FOR NO KEY UPDATE")
+        .bind(member.workspace)
+        .fetch_one(&mut *connection)
+        .await?;
+    let (is_member, seats): (bool, i64) = sqlx::query_as(
+        r#"
+        SELECT COALESCE(BOOL_OR(USER_ID = $2), FALSE), COUNT(*)
+        FROM MEMBERS WHERE WORKSPACE = $1 AND STATUS != 'DELETED'
+        "#,
+    )
+    .bind(member.workspace)
+    .bind(member.user_id)
+    .fetch_one(&mut *connection)
+    .await?;
+    if is_member {
+        return Ok(JoinOutcome::ALREADY_MEMBER);
+    }
+    if !has_room(member_limit, seats, 1) {
+        return Ok(JoinOutcome::NO_SEAT);
+    }
+    sqlx::query(
+        r#"
+        INSERT INTO MEMBERS
+        (ID, JOINED_AT, PROFILE_PICTURE, STATUS, ROLE, WORKSPACE, USER_ID, INVITED_BY, ROLE_GRANTED_BY)
+        VALUES ($1, $2, COALESCE($3, ''), $4, $5, $6, $7, $8, $9)
+        "#,
+    )
+    .bind(&member.id)
+    .bind(&member.joined_at)
+    .bind(&member.profile_picture)
+    .bind(&member.status)
+    .bind(&member.role)
+    .bind(&member.workspace)
+    .bind(&member.user_id)
+    .bind(&member.invited_by)
+    .bind(&member.role_granted_by)
+    .execute(&mut *connection)
+    .await?;
+    sqlx::query(
+        r#"
+        INSERT INTO MEMBER_WORKSPACE_DETAILS
+        (ID, WORKSPACE_ORDER, IS_DEFAULT, IS_FAV, MEMBER_ID, WORKSPACE_ID, USER_ID)
+        VALUES ($1, $2,
+            NOT EXISTS (SELECT 1 FROM MEMBER_WORKSPACE_DETAILS WHERE USER_ID = $6 AND IS_DEFAULT),
+            $3, $4, $5, $6)
+        "#,
+    )
+    .bind(&details.id)
+    .bind(&details.workspace_order)
+    .bind(&details.is_fav)
+    .bind(&details.member_id)
+    .bind(&details.workspace_id)
+    .bind(&details.user_id)
+    .execute(&mut *connection)
+    .await?;
+    Ok(JoinOutcome::JOINED)
+}
+
 impl MemberStore for MemberRepo {
     async fn create_new_member(&self, record: MemberRecord) -> Result<(), Error> {
         return match sqlx::query(
@@ -39,14 +110,30 @@ impl MemberStore for MemberRepo {
         };
     }
 
-    async fn get_member_profile(&self, user_id: Uuid) -> Result<MemberData, Error> {
-        let member: MemberData = sqlx::query_as(r#"SELECT * FROM MEMBERS WHERE USER_ID=$1"#)
-            .bind(user_id)
-            .fetch_one(&self.db)
-            .await?;
+    async fn get_member_profile(
+        &self,
+        user_id: Uuid,
+        workspace_id: Uuid,
+    ) -> Result<MemberData, Error> {
+        let member: MemberData =
+            sqlx::query_as(r#"SELECT * FROM MEMBERS WHERE USER_ID = $1 AND WORKSPACE = $2 AND STATUS != 'DELETED'"#)
+                .bind(user_id)
+                .bind(workspace_id)
+                .fetch_one(&self.db)
+                .await?;
         Ok(member)
     }
 
+    async fn get_user_memberships(&self, user_id: Uuid) -> Result<Vec<MemberData>, Error> {
+        let members: Vec<MemberData> = sqlx::query_as(
+            r#"SELECT * FROM MEMBERS WHERE USER_ID = $1 AND STATUS != 'DELETED' ORDER BY CREATED_AT"#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.db)
+        .await?;
+        Ok(members)
+    }
+
     async fn update_member_profile_picture(
         &self,
         member_id: Uuid,
@@ -62,7 +149,175 @@ impl MemberStore for MemberRepo {
             Err(e) => Err(e),
         }
     }
-    async fn get_member_settings(&self, member_id: Uuid) -> Result<(), Error> {
+
+    async fn count_workspace_members(&self, workspace_id: Uuid) -> Result<i64, Error> {
+        let (count,): (i64,) = sqlx::query_as(
+            r#"SELECT COUNT(*) FROM MEMBERS WHERE WORKSPACE = $1 AND STATUS != 'DELETED'"#,
+        )
+        .bind(workspace_id)
+        .fetch_one(&self.db)
+        .await?;
+        Ok(count)
+    }
+
+    async fn get_workspace_member(
+        &self,
+        workspace_id: Uuid,
+        member_id: Uuid,
+    ) -> Result<MemberData, Error> {
+        let member: MemberData = sqlx::query_as(
+            r#"SELECT * FROM MEMBERS WHERE ID = $1 AND WORKSPACE = $2 AND STATUS != 'DELETED'"#,
+        )
+        .bind(member_id)
+        .bind(workspace_id)
+        .fetch_one(&self.db)
+        .await?;
+        Ok(member)
+    }
+
+    async fn get_member_access(&self, member_id: Uuid) -> Result<(MemberStatus, MemberRole), Error> {
+        let access: (MemberStatus, MemberRole) =
+            sqlx::query_as(r#"SELECT STATUS, ROLE FROM MEMBERS WHERE ID = $1"#)
+                .bind(member_id)
+                .fetch_one(&self.db)
+                .await?;
+        Ok(access)
+    }
+
+    async fn list_workspace_members(
+        &self,
+        workspace_id: Uuid,
+        search: Option<String>,
+        status: Option<MemberStatus>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<MemberListItem>, i64), Error> {
+        // removed members are only listed when asked for explicitly
+        let filter = r#"
+            FROM MEMBERS INNER JOIN USERS ON USERS.ID = MEMBERS.USER_ID
+            WHERE MEMBERS.WORKSPACE = $1
+            AND (($2::MEMBER_STATUS IS NULL AND MEMBERS.STATUS != 'DELETED') OR MEMBERS.STATUS = $2)
+            AND ($3::TEXT IS NULL
+                OR USERS.EMAIL ILIKE $3
+                OR USERS.FIRST_NAME ILIKE $3
+                OR USERS.LAST_NAME ILIKE $3)
+        "#;
+        let pattern = search.map(|s| {
+            let escaped = s.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+            format!("%{}%", escaped)
+        });
+        let (total,): (i64,) = sqlx::query_as(&format!("SELECT COUNT(*) {}", filter))
+            .bind(workspace_id)
+            .bind(&status)
+            .bind(&pattern)
+            .fetch_one(&self.db)
+            .await?;
+        let members: Vec<MemberListItem> = sqlx::query_as(&format!(
+            r#"
+            SELECT MEMBERS.ID, MEMBERS.USER_ID, USERS.EMAIL, USERS.FIRST_NAME, USERS.LAST_NAME,
+                MEMBERS.PROFILE_PICTURE, MEMBERS.ROLE, MEMBERS.STATUS, MEMBERS.INVITED_BY,
+                MEMBERS.ROLE_GRANTED_BY, MEMBERS.JOINED_AT
+            {}
+            ORDER BY USERS.EMAIL
+            LIMIT $4 OFFSET $5
+            "#,
+            filter
+        ))
+        .bind(workspace_id)
+        .bind(&status)
+        .bind(&pattern)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.db)
+        .await?;
+        Ok((members, total))
+    }
+
+    async fn update_member_role(
+        &self,
+        workspace_id: Uuid,
+        member_id: Uuid,
+        role: MemberRole,
+        granted_by: Uuid,
+    ) -> Result<(), Error> {
+        let result = sqlx::query(
+            r#"
+            UPDATE MEMBERS SET ROLE = $1, ROLE_GRANTED_BY = $2, UPDATED_AT = CURRENT_TIMESTAMP
+            WHERE ID = $3 AND WORKSPACE = $4 AND STATUS != 'DELETED'
+            "#,
+        )
+        .bind(role)
+        .bind(granted_by)
+        .bind(member_id)
+        .bind(workspace_id)
+        .execute(&self.db)
+        .await?;
+        if result.rows_affected() != 1 {
+            return Err(Error::RowNotFound);
+        }
+        Ok(())
+    }
+
+    async fn update_member_status(
+        &self,
+        workspace_id: Uuid,
+        member_id: Uuid,
+        status: MemberStatus,
+    ) -> Result<(), Error> {
+        let result = sqlx::query(
+            r#"
+            UPDATE MEMBERS SET STATUS = $1, UPDATED_AT = CURRENT_TIMESTAMP
+            WHERE ID = $2 AND WORKSPACE = $3 AND STATUS != 'DELETED'
+            "#,
+        )
+        .bind(status)
+        .bind(member_id)
+        .bind(workspace_id)
+        .execute(&self.db)
+        .await?;
+        if result.rows_affected() != 1 {
+            return Err(Error::RowNotFound);
+        }
+        Ok(())
+    }
+
+    async fn remove_member(&self, workspace_id: Uuid, member_id: Uuid) -> Result<(), Error> {
+        let mut transaction = self.db.begin().await?;
+        let user: Option<(Uuid,)> = sqlx::query_as(
+            r#"
+            UPDATE MEMBERS SET STATUS = 'DELETED', UPDATED_AT = CURRENT_TIMESTAMP
+            WHERE ID = $1 AND WORKSPACE = $2 AND STATUS != 'DELETED'
+            RETURNING USER_ID
+            "#,
+        )
+        .bind(member_id)
+        .bind(workspace_id)
+        .fetch_optional(&mut *transaction)
+        .await?;
+        let Some((user_id,)) = user else {
+            return Err(Error::RowNotFound);
+        };
+        sqlx::query(r#"DELETE FROM MEMBER_WORKSPACE_DETAILS WHERE MEMBER_ID = $1"#)
+            .bind(member_id)
+            .execute(&mut *transaction)
+            .await?;
+        // the user keeps a default workspace if they have any left
+        sqlx::query(
+            r#"
+            UPDATE MEMBER_WORKSPACE_DETAILS SET IS_DEFAULT = TRUE
+            WHERE ID = (
+                SELECT ID FROM MEMBER_WORKSPACE_DETAILS WHERE USER_ID = $1
+                ORDER BY WORKSPACE_ORDER, ID LIMIT 1
+            )
+            AND NOT EXISTS (
+                SELECT 1 FROM MEMBER_WORKSPACE_DETAILS WHERE USER_ID = $1 AND IS_DEFAULT
+            )
+            "#,
+        )
+        .bind(user_id)
+        .execute(&mut *transaction)
+        .await?;
+        transaction.commit().await?;
         Ok(())
     }
 }
@@ -5,10 +5,16 @@ pub mod workspaces;
 pub mod auth;
 pub mod verified_emails;
 pub mod verified_domains;
+pub mod usage;
+pub mod settings;
+pub mod join_requests;
+pub mod alerts;
+pub mod webhooks;
+pub mod monitoring;
 
 use std::sync::Arc;
 use sqlx::postgres::PgPoolOptions;
-use crate::darkscout::types::store::{AuthRepo, InvitationRepo, MemberRepo, UserRepo, WorkspaceRepo, VerifiedEmailsRepo, VerifiedDomainRepo};
+use crate::darkscout::types::store::{AuthRepo, InvitationRepo, MemberRepo, UserRepo, WorkspaceRepo, VerifiedEmailsRepo, VerifiedDomainRepo, UsageRepo, SettingsRepo, JoinRequestRepo, MonitoringRepo, AlertsRepo, WebhooksRepo};
 
 use super::types::{
     store::{NewDb, PgStore},
@@ -38,7 +44,13 @@ impl NewDb for PgStore {
             member: Arc::new(MemberRepo { db:  pool.clone()}),
             auth: Arc::new(AuthRepo { db: pool.clone()}),
             verified_emails: Arc::new(VerifiedEmailsRepo { db: pool.clone()}),
-            verified_domains: Arc::new(VerifiedDomainRepo { db: pool.clone()})
+            verified_domains: Arc::new(VerifiedDomainRepo { db: pool.clone()}),
+            usage: Arc::new(UsageRepo { db: pool.clone() }),
+            settings: Arc::new(SettingsRepo { db: pool.clone() }),
+            join_requests: Arc::new(JoinRequestRepo { db: pool.clone() }),
+            monitoring: Arc::new(MonitoringRepo { db: pool.clone() }),
+            alerts: Arc::new(AlertsRepo { db: pool.clone() }),
+            webhooks: Arc::new(WebhooksRepo { db: pool.clone() }),
         }
     }
 }
@@ -0,0 +1,418 @@
+use crate::darkscout::database::alerts::insert_breach_alerts;
+use crate::darkscout::types::alerts::BreachAlert;
+use crate::darkscout::types::monitoring::{
+    new_breaches, BreachSnapshot, BreachTimelineEntry, MonitoredAsset, ScannedBreach,
+    SnapshotTotals,
+};
+use crate::darkscout::types::store::{MonitoringRepo, MonitoringStore};
+use crate::darkscout::types::usage::LookupTargetType;
+use sqlx::Error;
+use uuid::Uuid;
+
+fn asset_table(asset_type: LookupTargetType) -> &'static str {
+    match asset_type {
+        LookupTargetType::EMAIL => "VERIFIED_EMAILS",
+        LookupTargetType::DOMAIN => "VERIFIED_DOMAINS",
+    }
+}
+
+// the asset id goes in the column matching its type, the other stays NULL
+fn asset_columns(asset: &MonitoredAsset) -> (Option<Uuid>, Option<Uuid>) {
+    typed_asset_columns(asset.asset_type, asset.id)
+}
+
+fn typed_asset_columns(asset_type: LookupTargetType, id: Uuid) -> (Option<Uuid>, Option<Uuid>) {
+    match asset_type {
+        LookupTargetType::EMAIL => (Some(id), None),
+        LookupTargetType::DOMAIN => (None, Some(id)),
+    }
+}
+
+impl MonitoringStore for MonitoringRepo {
+    async fn claim_due_assets(
+        &self,
+        plans: Vec<String>,
+        limit: i64,
+        lease_minutes: i32,
+    ) -> Result<Vec<MonitoredAsset>, Error> {
+        // rows claimed by another instance are skipped, not waited on
+        let mut transaction = self.db.begin().await?;
+        let assets = sqlx::query_as::<_, MonitoredAsset>(
+            r#"
+        WITH EMAILS AS (
+            SELECT VERIFIED_EMAILS.ID, VERIFIED_EMAILS.WORKSPACE,
+            'EMAIL'::LOOKUP_TARGET AS ASSET_TYPE, VERIFIED_EMAILS.EMAIL AS ASSET,
+            WORKSPACES.W_TYPE AS PLAN, VERIFIED_EMAILS.LAST_SCANNED_AT IS NOT NULL AS HAS_BASELINE,
+            VERIFIED_EMAILS.NEXT_SCAN_AT
+            FROM VERIFIED_EMAILS
+            INNER JOIN WORKSPACES ON WORKSPACES.ID = VERIFIED_EMAILS.WORKSPACE
+            WHERE VERIFIED_EMAILS.IS_VERIFIED = TRUE
+            AND (VERIFIED_EMAILS.NEXT_SCAN_AT IS NULL OR VERIFIED_EMAILS.NEXT_SCAN_AT <= CURRENT_TIMESTAMP)
+            AND WORKSPACES.DELETED = FALSE AND WORKSPACES.STATUS NOT IN ('DISABLED', 'SUSPENDED')
+            AND WORKSPACES.W_TYPE::TEXT = ANY($1)
+            ORDER BY VERIFIED_EMAILS.NEXT_SCAN_AT ASC NULLS FIRST
+            LIMIT $2
+            FOR UPDATE OF VERIFIED_EMAILS SKIP LOCKED
+        ), DOMAINS AS (
+            SELECT VERIFIED_DOMAINS.ID, VERIFIED_DOMAINS.WORKSPACE,
+            'DOMAIN'::LOOKUP_TARGET AS ASSET_TYPE, VERIFIED_DOMAINS.DOMAIN AS ASSET,
+            WORKSPACES.W_TYPE AS PLAN, VERIFIED_DOMAINS.LAST_SCANNED_AT IS NOT NULL AS HAS_BASELINE,
+            VERIFIED_DOMAINS.NEXT_SCAN_AT
+            FROM VERIFIED_DOMAINS
+            INNER JOIN WORKSPACES ON WORKSPACES.ID = VERIFIED_DOMAINS.WORKSPACE
+            WHERE VERIFIED_DOMAINS.IS_VERIFIED = TRUE
+            AND (VERIFIED_DOMAINS.NEXT_SCAN_AT IS NULL OR VERIFIED_DOMAINS.NEXT_SCAN_AT <= CURRENT_TIMESTAMP)
+            AND WORKSPACES.DELETED = FALSE AND WORKSPACES.STATUS NOT IN ('DISABLED', 'SUSPENDED')
+            AND WORKSPACES.W_TYPE::TEXT = ANY($1)
+            ORDER BY VERIFIED_DOMAINS.NEXT_SCAN_AT ASC NULLS FIRST
+            LIMIT $2
+            FOR UPDATE OF VERIFIED_DOMAINS SKIP LOCKED
+        )
+        SELECT ID, WORKSPACE, ASSET_TYPE, ASSET, PLAN, HAS_BASELINE FROM (
+            SELECT * FROM EMAILS UNION ALL SELECT * FROM DOMAINS
+        ) DUE
+        ORDER BY NEXT_SCAN_AT ASC NULLS FIRST
+        LIMIT $2
+        "#,
+        )
+        .bind(plans)
+        .bind(limit)
+        .fetch_all(&mut *transaction)
+        .await?;
+        for asset_type in [LookupTargetType::EMAIL, LookupTargetType::DOMAIN] {
+            let ids: Vec<Uuid> = assets
+                .iter()
+                .filter(|a| a.asset_type == asset_type)
+                .map(|a| a.id)
+                .collect();
+            if ids.is_empty() {
+                continue;
+            }
+            sqlx::query(&format!(
+                "UPDATE {} SET NEXT_SCAN_AT = CURRENT_TIMESTAMP + make_interval(mins => $1) WHERE ID = ANY($2)",
+                asset_table(asset_type)
+            ))
+            .bind(lease_minutes)
+            .bind(ids)
+            .execute(&mut *transaction)
+            .await?;
+        }
+        transaction.commit().await?;
+        Ok(assets)
+    }
+
+    async fn record_asset_scan(
+        &self,
+        asset: &MonitoredAsset,
+        breaches: Vec<ScannedBreach>,
+        next_scan_minutes: i32,
+    ) -> Result<Vec<BreachAlert>, Error> {
+        let (email_id, domain_id) = asset_columns(asset);
+        let mut transaction = self.db.begin().await?;
+        let known: Vec<String> = sqlx::query_scalar(
+            r#"
+        SELECT BREACH_NAME FROM ASSET_BREACHES WHERE ASSET_ID = $1
+        "#,
+        )
+        .bind(asset.id)
+        .fetch_all(&mut *transaction)
+        .await?;
+        let found = if asset.has_baseline {
+            new_breaches(&known, &breaches)
+        } else {
+            Vec::new()
+        };
+        for breach in breaches.iter() {
+            sqlx::query(
+                r#"
+            INSERT INTO ASSET_BREACHES
+            (ASSET_ID, BREACH_NAME, WORKSPACE, ASSET_TYPE, VERIFIED_EMAIL, VERIFIED_DOMAIN, DATA_CLASSES)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (ASSET_ID, BREACH_NAME)
+            DO UPDATE SET LAST_SEEN_AT = CURRENT_TIMESTAMP, DATA_CLASSES = EXCLUDED.DATA_CLASSES
+            "#,
+            )
+            .bind(asset.id)
+            .bind(&breach.name)
+            .bind(asset.workspace)
+            .bind(asset.asset_type)
+            .bind(email_id)
+            .bind(domain_id)
+            .bind(&breach.data_classes)
+            .execute(&mut *transaction)
+            .await?;
+        }
+        let names: Vec<String> = breaches.into_iter().map(|b| b.name).collect();
+        let new_names: Vec<String> = found.iter().map(|b| b.name.clone()).collect();
+        sqlx::query(
+            r#"
+        INSERT INTO ASSET_SCANS
+        (ID, WORKSPACE, ASSET_TYPE, ASSET_ID, VERIFIED_EMAIL, VERIFIED_DOMAIN, ASSET, SUCCEEDED, BREACHES, NEW_BREACHES)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, TRUE, $8, $9)
+        "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(asset.workspace)
+        .bind(asset.asset_type)
+        .bind(asset.id)
+        .bind(email_id)
+        .bind(domain_id)
+        .bind(&asset.asset)
+        .bind(names)
+        .bind(new_names)
+        .execute(&mut *transaction)
+        .await?;
+        sqlx::query(&format!(
+            "UPDATE {} SET LAST_SCANNED_AT = CURRENT_TIMESTAMP,
+            NEXT_SCAN_AT = CURRENT_TIMESTAMP + make_interval(mins => $1) WHERE ID = $2",
+            asset_table(asset.asset_type)
+        ))
+        .bind(next_scan_minutes)
+        .bind(asset.id)
+        .execute(&mut *transaction)
+        .await?;
+        // the alerts are committed with the breaches, a failed notification
+        // is retried from them
+        let alerts = found
+            .into_iter()
+            .map(|b| BreachAlert::for_breach(asset, b))
+            .collect();
+        let created = insert_breach_alerts(&mut transaction, alerts).await?;
+        transaction.commit().await?;
+        Ok(created)
+    }
+
+    async fn record_failed_asset_scan(
+        &self,
+        asset: &MonitoredAsset,
+        error: String,
+        next_scan_minutes: i32,
+    ) -> Result<(), Error> {
+        let (email_id, domain_id) = asset_columns(asset);
+        let mut transaction = self.db.begin().await?;
+        sqlx::query(
+            r#"
+        INSERT INTO ASSET_SCANS
+        (ID, WORKSPACE, ASSET_TYPE, ASSET_ID, VERIFIED_EMAIL, VERIFIED_DOMAIN, ASSET, SUCCEEDED, ERROR)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, FALSE, $8)
+        "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(asset.workspace)
+        .bind(asset.asset_type)
+        .bind(asset.id)
+        .bind(email_id)
+        .bind(domain_id)
+        .bind(&asset.asset)
+        .bind(error.chars().take(1000).collect::<String>())
+        .execute(&mut *transaction)
+        .await?;
+        sqlx::query(&format!(
+            "UPDATE {} SET NEXT_SCAN_AT = CURRENT_TIMESTAMP + make_interval(mins => $1) WHERE ID = $2",
+            asset_table(asset.asset_type)
+        ))
+        .bind(next_scan_minutes)
+        .bind(asset.id)
+        .execute(&mut *transaction)
+        .await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn take_scan_token(&self, lookups_per_minute: i32) -> Result<bool, Error> {
+        // refills at the configured rate and holds a single token so the
+        // lookups stay evenly spaced whichever instance makes them
+        let taken: Option<f64> = sqlx::query_scalar(
+            r#"
+        UPDATE MONITORING_BUDGET SET
+        TOKENS = LEAST(1, TOKENS + EXTRACT(EPOCH FROM CLOCK_TIMESTAMP() - UPDATED_AT)::FLOAT8 * $1 / 60) - 1,
+        UPDATED_AT = CLOCK_TIMESTAMP()
+        WHERE ID = 1
+        AND LEAST(1, TOKENS + EXTRACT(EPOCH FROM CLOCK_TIMESTAMP() - UPDATED_AT)::FLOAT8 * $1 / 60) >= 1
+        RETURNING TOKENS
+        "#,
+        )
+        .bind(lookups_per_minute as f64)
+        .fetch_optional(&self.db)
+        .await?;
+        Ok(taken.is_some())
+    }
+
+    async fn pause_scans(&self, seconds: i64) -> Result<(), Error> {
+        // no token refills until the pause is over
+        sqlx::query(
+            r#"
+        UPDATE MONITORING_BUDGET SET TOKENS = 0,
+        UPDATED_AT = GREATEST(UPDATED_AT, CLOCK_TIMESTAMP() + make_interval(secs => $1))
+        WHERE ID = 1
+        "#,
+        )
+        .bind(seconds as f64)
+        .execute(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    async fn release_assets(&self, assets: &[MonitoredAsset]) -> Result<(), Error> {
+        for asset_type in [LookupTargetType::EMAIL, LookupTargetType::DOMAIN] {
+            let ids: Vec<Uuid> = assets
+                .iter()
+                .filter(|a| a.asset_type == asset_type)
+                .map(|a| a.id)
+                .collect();
+            if ids.is_empty() {
+                continue;
+            }
+            sqlx::query(&format!(
+                "UPDATE {} SET NEXT_SCAN_AT = CURRENT_TIMESTAMP WHERE ID = ANY($1)",
+                asset_table(asset_type)
+            ))
+            .bind(ids)
+            .execute(&self.db)
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn record_dark_search_hits(
+        &self,
+        workspace_id: Uuid,
+        target: &str,
+        document_ids: Vec<String>,
+    ) -> Result<Vec<String>, Error> {
+        sqlx::query_scalar(
+            r#"
+        INSERT INTO DARK_SEARCH_HITS (WORKSPACE, TARGET, DOCUMENT_UUID)
+        SELECT $1, $2, UNNEST($3::TEXT[])
+        ON CONFLICT (WORKSPACE, TARGET, DOCUMENT_UUID) DO NOTHING
+        RETURNING DOCUMENT_UUID
+        "#,
+        )
+        .bind(workspace_id)
+        .bind(target)
+        .bind(document_ids)
+        .fetch_all(&self.db)
+        .await
+    }
+
+    async fn find_monitored_asset(
+        &self,
+        workspace_id: Uuid,
+        asset_type: LookupTargetType,
+        asset: &str,
+    ) -> Result<Uuid, Error> {
+        let column = match asset_type {
+            LookupTargetType::EMAIL => "EMAIL",
+            LookupTargetType::DOMAIN => "DOMAIN",
+        };
+        sqlx::query_scalar(&format!(
+            "SELECT ID FROM {} WHERE WORKSPACE = $1 AND {} = $2 AND IS_VERIFIED = TRUE",
+            asset_table(asset_type),
+            column
+        ))
+        .bind(workspace_id)
+        .bind(asset)
+        .fetch_one(&self.db)
+        .await
+    }
+
+    async fn save_breach_snapshot(&self, snapshot: &BreachSnapshot) -> Result<(), Error> {
+        // a second result the same day is merged in, a domain scan knows no
+        // record counts and mustn't wipe the ones of an earlier lookup
+        let (email_id, domain_id) = typed_asset_columns(snapshot.asset_type, snapshot.asset_id);
+        sqlx::query(
+            r#"
+        INSERT INTO BREACH_SNAPSHOTS
+        (WORKSPACE, ASSET_TYPE, ASSET_ID, VERIFIED_EMAIL, VERIFIED_DOMAIN, ASSET,
+        BREACH_COUNT, SENSITIVE_COUNT, TOTAL_RECORDS, BREACHES)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+        ON CONFLICT (ASSET_ID, SNAPSHOT_DATE) DO UPDATE SET
+        BREACH_COUNT = (
+            SELECT COUNT(DISTINCT NAME)
+            FROM UNNEST(BREACH_SNAPSHOTS.BREACHES || EXCLUDED.BREACHES) AS NAME
+        ),
+        SENSITIVE_COUNT = GREATEST(BREACH_SNAPSHOTS.SENSITIVE_COUNT, EXCLUDED.SENSITIVE_COUNT),
+        TOTAL_RECORDS = GREATEST(BREACH_SNAPSHOTS.TOTAL_RECORDS, EXCLUDED.TOTAL_RECORDS),
+        BREACHES = ARRAY(
+            SELECT DISTINCT UNNEST(BREACH_SNAPSHOTS.BREACHES || EXCLUDED.BREACHES) ORDER BY 1
+        ),
+        UPDATED_AT = CURRENT_TIMESTAMP
+        "#,
+        )
+        .bind(snapshot.workspace)
+        .bind(snapshot.asset_type)
+        .bind(snapshot.asset_id)
+        .bind(email_id)
+        .bind(domain_id)
+        .bind(&snapshot.asset)
+        .bind(snapshot.breach_count)
+        .bind(snapshot.sensitive_count)
+        .bind(snapshot.total_records)
+        .bind(&snapshot.breaches)
+        .execute(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    async fn get_snapshot_totals(
+        &self,
+        workspace_id: Uuid,
+        asset_id: Uuid,
+        days_back: i32,
+    ) -> Result<SnapshotTotals, Error> {
+        sqlx::query_as::<_, SnapshotTotals>(
+            r#"
+        SELECT SNAPSHOT.SNAPSHOT_DATE, SNAPSHOT.BREACH_COUNT, SNAPSHOT.SENSITIVE_COUNT,
+        SNAPSHOT.TOTAL_RECORDS, (
+            SELECT COUNT(DISTINCT BREACH) FROM BREACH_SNAPSHOTS, UNNEST(BREACHES) AS BREACH
+            WHERE ASSET_ID = SNAPSHOT.ASSET_ID AND SNAPSHOT_DATE <= SNAPSHOT.SNAPSHOT_DATE
+        ) AS TOTAL_BREACHES
+        FROM BREACH_SNAPSHOTS SNAPSHOT
+        WHERE SNAPSHOT.WORKSPACE = $1 AND SNAPSHOT.ASSET_ID = $2
+        AND SNAPSHOT.SNAPSHOT_DATE <= CURRENT_DATE - $3
+        ORDER BY SNAPSHOT.SNAPSHOT_DATE DESC
+        LIMIT 1
+        "#,
+        )
+        .bind(workspace_id)
+        .bind(asset_id)
+        .bind(days_back)
+        .fetch_one(&self.db)
+        .await
+    }
+
+    async fn get_breach_timeline(
+        &self,
+        workspace_id: Uuid,
+        asset_id: Uuid,
+    ) -> Result<Vec<BreachTimelineEntry>, Error> {
+        // the scans recorded before snapshots existed only live in ASSET_BREACHES
+        sqlx::query_as::<_, BreachTimelineEntry>(
+            r#"
+        WITH SEEN AS (
+            SELECT BREACH AS BREACH_NAME, SNAPSHOT_DATE AS SEEN_FROM, SNAPSHOT_DATE AS SEEN_TO
+            FROM BREACH_SNAPSHOTS, UNNEST(BREACHES) AS BREACH
+            WHERE WORKSPACE = $1 AND ASSET_ID = $2
+            UNION ALL
+            SELECT BREACH_NAME, FIRST_SEEN_AT::DATE, LAST_SEEN_AT::DATE
+            FROM ASSET_BREACHES
+            WHERE WORKSPACE = $1 AND ASSET_ID = $2
+        ), LATEST AS (
+            SELECT GREATEST(
+                (SELECT MAX(SNAPSHOT_DATE) FROM BREACH_SNAPSHOTS WHERE WORKSPACE = $1 AND ASSET_ID = $2),
+                (SELECT MAX(SEEN_TO) FROM SEEN)
+            ) AS DAY
+        )
+        SELECT BREACH_NAME, MIN(SEEN_FROM) AS FIRST_SEEN, MAX(SEEN_TO) AS LAST_SEEN,
+        MAX(SEEN_TO) >= (SELECT DAY FROM LATEST) AS STILL_LISTED
+        FROM SEEN
+        GROUP BY BREACH_NAME
+        ORDER BY FIRST_SEEN DESC, BREACH_NAME
+        "#,
+        )
+        .bind(workspace_id)
+        .bind(asset_id)
+        .fetch_all(&self.db)
+        .await
+    }
+}
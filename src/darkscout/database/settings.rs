@@ -0,0 +1,64 @@
+use crate::darkscout::types::store::{SettingsRepo, SettingsStore};
+use crate::darkscout::types::workspace::WorkspaceSettings;
+use sqlx::Error;
+use uuid::Uuid;
+
+impl SettingsStore for SettingsRepo {
+    async fn get_settings(
+        &self,
+        workspace_id: Uuid,
+        member_id: Option<Uuid>,
+    ) -> Result<Vec<WorkspaceSettings>, Error> {
+        let settings: Vec<WorkspaceSettings> = sqlx::query_as(
+            r#"
+            SELECT * FROM WORKSPACE_SETTINGS
+            WHERE WORKSPACE_ID = $1 AND (MEMBER_ID IS NULL OR MEMBER_ID = $2)
+            "#,
+        )
+        .bind(workspace_id)
+        .bind(member_id)
+        .fetch_all(&self.db)
+        .await?;
+        Ok(settings)
+    }
+
+    async fn upsert_settings(
+        &self,
+        workspace_id: Uuid,
+        member_id: Option<Uuid>,
+        settings: Vec<(String, String)>,
+    ) -> Result<(), Error> {
+        // the two partial unique indexes need their own conflict targets
+        let upsert_query = match member_id {
+            None => {
+                r#"
+                INSERT INTO WORKSPACE_SETTINGS (ID, MEMBER_ID, WORKSPACE_ID, SETTINGS_NAME, SETTINGS_VALUE)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT (WORKSPACE_ID, SETTINGS_NAME) WHERE MEMBER_ID IS NULL
+                DO UPDATE SET SETTINGS_VALUE = EXCLUDED.SETTINGS_VALUE, UPDATED_AT = CURRENT_TIMESTAMP
+                "#
+            }
+            Some(_) => {
+                r#"
+                INSERT INTO WORKSPACE_SETTINGS (ID, MEMBER_ID, WORKSPACE_ID, SETTINGS_NAME, SETTINGS_VALUE)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT (WORKSPACE_ID, MEMBER_ID, SETTINGS_NAME) WHERE MEMBER_ID IS NOT NULL
+                DO UPDATE SET SETTINGS_VALUE = EXCLUDED.SETTINGS_VALUE, UPDATED_AT = CURRENT_TIMESTAMP
+                "#
+            }
+        };
+        let mut transaction = self.db.begin().await?;
+        for (name, value) in settings {
+            sqlx::query(upsert_query)
+                .bind(Uuid::new_v4())
+                .bind(member_id)
+                .bind(workspace_id)
+                .bind(name)
+                .bind(value)
+                .execute(&mut *transaction)
+                .await?;
+        }
+        transaction.commit().await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,150 @@
+use crate::darkscout::types::plans::{has_room, Quota};
+use crate::darkscout::types::store::{UsageRepo, UsageStore};
+use crate::darkscout::types::usage::{LookupRefusal, UsageEvent, UsageRollup};
+use chrono::NaiveDate;
+use sqlx::Error;
+use uuid::Uuid;
+
+impl UsageStore for UsageRepo {
+    async fn consume_daily_usage(
+        &self,
+        workspace_id: Uuid,
+        quota: Quota,
+        day: NaiveDate,
+        limit: Option<i64>,
+    ) -> Result<bool, Error> {
+        if !has_room(limit, 0, 1) {
+            return Ok(false);
+        }
+        // the conditional upsert keeps concurrent requests from going over the limit
+        let used: Option<(i64,)> = sqlx::query_as(
+            r#"
+            INSERT INTO WORKSPACE_DAILY_USAGE (WORKSPACE, USAGE_DATE, QUOTA, USED)
+            VALUES ($1, $4, $2, 1)
+            ON CONFLICT (WORKSPACE, USAGE_DATE, QUOTA)
+            DO UPDATE SET USED = WORKSPACE_DAILY_USAGE.USED + 1
+            WHERE $3::BIGINT IS NULL OR WORKSPACE_DAILY_USAGE.USED < $3::BIGINT
+            RETURNING USED
+            "#,
+        )
+        .bind(workspace_id)
+        .bind(quota)
+        .bind(limit)
+        .bind(day)
+        .fetch_optional(&self.db)
+        .await?;
+        Ok(used.is_some())
+    }
+
+    async fn get_daily_usage(
+        &self,
+        workspace_id: Uuid,
+        quota: Quota,
+        day: NaiveDate,
+    ) -> Result<i64, Error> {
+        let used: Option<(i64,)> = sqlx::query_as(
+            r#"
+            SELECT USED FROM WORKSPACE_DAILY_USAGE
+            WHERE WORKSPACE = $1 AND USAGE_DATE = $3 AND QUOTA = $2
+            "#,
+        )
+        .bind(workspace_id)
+        .bind(quota)
+        .bind(day)
+        .fetch_optional(&self.db)
+        .await?;
+        Ok(used.map(|(u,)| u).unwrap_or(0))
+    }
+
+    async fn record_usage_event(&self, event: UsageEvent) -> Result<(), Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO USAGE_EVENTS
+            (ID, WORKSPACE, MEMBER, USER_ID, QUERY, PROVIDER, TIER, LATENCY_MS, SUCCEEDED, CREATED_AT)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            "#,
+        )
+        .bind(event.id)
+        .bind(event.workspace)
+        .bind(event.member)
+        .bind(event.user_id)
+        .bind(&event.query)
+        .bind(event.provider)
+        .bind(event.tier)
+        .bind(event.latency_ms)
+        .bind(event.succeeded)
+        .bind(event.created_at)
+        .execute(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    async fn record_lookup_refusal(&self, refusal: LookupRefusal) -> Result<(), Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO LOOKUP_REFUSALS
+            (ID, WORKSPACE, MEMBER, USER_ID, TARGET_TYPE, TARGET, PATH, CREATED_AT)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            "#,
+        )
+        .bind(refusal.id)
+        .bind(refusal.workspace)
+        .bind(refusal.member)
+        .bind(refusal.user_id)
+        .bind(refusal.target_type)
+        .bind(refusal.target)
+        .bind(refusal.path)
+        .bind(refusal.created_at)
+        .execute(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    async fn rollup_usage_events(&self, day: NaiveDate) -> Result<(), Error> {
+        // days are UTC days like the quotas, whatever the session time zone
+        let mut transaction = self.db.begin().await?;
+        sqlx::query("DELETE FROM USAGE_DAILY_ROLLUPS WHERE USAGE_DATE = $1")
+            .bind(day)
+            .execute(&mut *transaction)
+            .await?;
+        sqlx::query(
+            r#"
+            INSERT INTO USAGE_DAILY_ROLLUPS
+            (USAGE_DATE, WORKSPACE, USER_ID, PROVIDER, TIER, LOOKUPS, FAILED_LOOKUPS, TOTAL_LATENCY_MS)
+            SELECT $1::DATE, WORKSPACE, USER_ID, PROVIDER, TIER,
+                COUNT(*), COUNT(*) FILTER (WHERE NOT SUCCEEDED), COALESCE(SUM(LATENCY_MS), 0)
+            FROM USAGE_EVENTS
+            WHERE CREATED_AT >= $1::DATE::TIMESTAMP AT TIME ZONE 'UTC'
+            AND CREATED_AT < ($1::DATE + 1)::TIMESTAMP AT TIME ZONE 'UTC'
+            GROUP BY WORKSPACE, USER_ID, PROVIDER, TIER
+            "#,
+        )
+        .bind(day)
+        .execute(&mut *transaction)
+        .await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn get_usage_rollups(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+        workspace_id: Option<Uuid>,
+    ) -> Result<Vec<UsageRollup>, Error> {
+        let rollups: Vec<UsageRollup> = sqlx::query_as(
+            r#"
+            SELECT * FROM USAGE_DAILY_ROLLUPS
+            WHERE USAGE_DATE >= $1 AND USAGE_DATE <= $2
+            AND ($3::UUID IS NULL OR WORKSPACE = $3)
+            ORDER BY USAGE_DATE, WORKSPACE, USER_ID, PROVIDER, TIER
+            "#,
+        )
+        .bind(from)
+        .bind(to)
+        .bind(workspace_id)
+        .fetch_all(&self.db)
+        .await?;
+        Ok(rollups)
+    }
+}
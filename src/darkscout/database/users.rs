@@ -8,14 +8,15 @@ impl UserStore for UserRepo {
     async fn create_user(&self, user: &UserRecord) -> Result<Uuid, Error> {
         #[derive(FromRow)]struct MyID{id: Uuid}
         return match sqlx::query_as::<_, MyID>(
-            r#"INSERT INTO USERS (ID, FIRST_NAME, LAST_NAME, USERNAME, EMAIL, IP_STR, PASSWORD) VALUES ($1, $2, $3, $4, $5, $6, $7) returning id"#).
+            r#"INSERT INTO USERS (ID, FIRST_NAME, LAST_NAME, USERNAME, EMAIL, IP_STR, PASSWORD, PHONE) VALUES ($1, $2, $3, $4, $5, $6, $7, $8) returning id"#).
             bind(&user.id).
             bind(&user.first_name).
             bind(&user.last_name).
             bind(&user.username).
             bind(&user.email).
             bind(&user.ip_str).
-            bind(&user.password)
+            bind(&user.password).
+            bind(&user.phone)
             .fetch_one(&self.db)
             .await {
             Ok(id) => Ok(id.id),
@@ -39,7 +40,7 @@ impl UserStore for UserRepo {
     }
 
     async fn get_by_id(&self, id: Uuid) -> Result<UserRecord, Error> {
-        return match sqlx::query_as::<_, UserRecord>(r#""#)
+        return match sqlx::query_as::<_, UserRecord>(r#"SELECT * FROM USERS WHERE ID = $1"#)
             .bind(id)
             .fetch_one(&self.db)
             .await
@@ -101,4 +102,68 @@ impl UserStore for UserRepo {
             Err(e) => Err(e),
         };
     }
+
+    async fn update_user_phone(&self, user_id: Uuid, phone: String) -> Result<(), Error> {
+        sqlx::query(
+            r#"
+        UPDATE USERS SET PHONE = $1, PHONE_VERIFIED = FALSE, UPDATED_AT = CURRENT_TIMESTAMP
+        WHERE ID = $2
+        "#,
+        )
+        .bind(phone)
+        .bind(user_id)
+        .execute(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    async fn verify_user_phone(&self, user_id: Uuid, phone: &str) -> Result<(), Error> {
+        let result = sqlx::query(
+            r#"
+        UPDATE USERS SET PHONE_VERIFIED = TRUE, UPDATED_AT = CURRENT_TIMESTAMP
+        WHERE ID = $1 AND PHONE = $2
+        "#,
+        )
+        .bind(user_id)
+        .bind(phone)
+        .execute(&self.db)
+        .await?;
+        if result.rows_affected() != 1 {
+            return Err(Error::RowNotFound);
+        }
+        Ok(())
+    }
+
+    async fn is_user_verified(&self, user_id: Uuid) -> Result<bool, Error> {
+        let (verified,): (bool,) =
+            sqlx::query_as(r#"SELECT STATUS = 'VERIFIED' FROM USERS WHERE ID = $1"#)
+                .bind(user_id)
+                .fetch_one(&self.db)
+                .await?;
+        Ok(verified)
+    }
+
+    // only accounts that never had a password can be onboarded
+    async fn complete_onboarding(
+        &self,
+        user_id: Uuid,
+        password_hash: String,
+        first_name: String,
+        last_name: String,
+    ) -> Result<UserRecord, Error> {
+        sqlx::query_as::<_, UserRecord>(
+            r#"
+        UPDATE USERS SET PASSWORD = $1, FIRST_NAME = $2, LAST_NAME = $3, STATUS = 'VERIFIED',
+        UPDATED_AT = CURRENT_TIMESTAMP
+        WHERE ID = $4 AND PASSWORD IS NULL
+        RETURNING *
+        "#,
+        )
+        .bind(password_hash)
+        .bind(first_name)
+        .bind(last_name)
+        .bind(user_id)
+        .fetch_one(&self.db)
+        .await
+    }
 }
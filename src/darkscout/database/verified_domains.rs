@@ -1,36 +1,67 @@
 use crate::darkscout::types::store::{VerifiedDomainRepo, VerifiedDomainsStore};
-use crate::darkscout::types::verified_domains::VerifiedDomain;
+use crate::darkscout::types::verified_domains::{
+    DomainProofMethod, VerifiedDomain, VerifiedDomainRecord, DOMAIN_RECHECK_HOURS,
+};
+use uuid::Uuid;
 
 impl VerifiedDomainsStore for VerifiedDomainRepo {
+    // returns the records that were added, a domain the workspace already
+    // has is skipped
     async fn add_verified_domains(
         &self,
+        workspace_id: Uuid,
         records: Vec<VerifiedDomain>,
-    ) -> Result<(), sqlx::error::Error> {
+    ) -> Result<Vec<VerifiedDomain>, sqlx::error::Error> {
         let mut transaction = self.db.begin().await?;
-        let insert_query = "INSERT INTO VERIFIED_DOMAINS (ID, DOMAIN, ADDED_BY) VALUES ($1, $2, $3)";
+        let insert_query = r#"
+        INSERT INTO VERIFIED_DOMAINS (ID, DOMAIN, ADDED_BY, VERIFICATION_TOKEN, WORKSPACE, INCLUDE_SUBDOMAINS)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (WORKSPACE, DOMAIN) DO NOTHING
+        "#;
+        let mut added = Vec::new();
         for record in records {
-            sqlx::query(insert_query)
+            let result = sqlx::query(insert_query)
                 .bind(&record.id)
                 .bind(&record.domain)
                 .bind(&record.added_by)
+                .bind(&record.verification_token)
+                .bind(workspace_id)
+                .bind(record.include_subdomains)
                 .execute(&mut *transaction)
                 .await?;
+            if result.rows_affected() > 0 {
+                added.push(record);
             }
+        }
         transaction.commit().await?;
-        Ok(())
+        Ok(added)
     }
 
-    async fn update_verified_domain(&self, domain: &str, is_verified: bool) -> Result<(), sqlx::error::Error> {
+    // resets the failed re-checks, a revoked domain keeps the error of the last one
+    async fn update_verified_domain(
+        &self,
+        workspace_id: Uuid,
+        id: Uuid,
+        is_verified: bool,
+        method: Option<DomainProofMethod>,
+    ) -> Result<(), sqlx::error::Error> {
         match sqlx::query(
             r#"
         UPDATE VERIFIED_DOMAINS SET
         IS_VERIFIED = $1,
+        VERIFICATION_METHOD = $2,
+        VERIFIED_AT = CASE WHEN $1 THEN COALESCE(VERIFIED_AT, CURRENT_TIMESTAMP) END,
+        LAST_CHECKED_AT = CURRENT_TIMESTAMP,
+        FAILED_CHECKS = 0,
+        CHECK_ERROR = CASE WHEN $1 THEN NULL ELSE CHECK_ERROR END,
         UPDATED_AT = CURRENT_TIMESTAMP
-        WHERE DOMAIN = $2
+        WHERE ID = $3 AND WORKSPACE = $4
         "#,
         )
         .bind(is_verified)
-        .bind(domain)
+        .bind(method)
+        .bind(id)
+        .bind(workspace_id)
         .execute(&self.db)
         .await
         {
@@ -39,4 +70,219 @@ impl VerifiedDomainsStore for VerifiedDomainRepo {
         }
     }
 
+    async fn record_failed_domain_check(
+        &self,
+        workspace_id: Uuid,
+        id: Uuid,
+        error: String,
+    ) -> Result<i32, sqlx::error::Error> {
+        let (failed,): (i32,) = sqlx::query_as(
+            r#"
+        UPDATE VERIFIED_DOMAINS SET FAILED_CHECKS = FAILED_CHECKS + 1, CHECK_ERROR = $2,
+        LAST_CHECKED_AT = CURRENT_TIMESTAMP, UPDATED_AT = CURRENT_TIMESTAMP
+        WHERE ID = $1 AND WORKSPACE = $3
+        RETURNING FAILED_CHECKS
+        "#,
+        )
+        .bind(id)
+        .bind(error)
+        .bind(workspace_id)
+        .fetch_one(&self.db)
+        .await?;
+        Ok(failed)
+    }
+
+    async fn get_workspace_domain(
+        &self,
+        workspace_id: Uuid,
+        id: Uuid,
+    ) -> Result<VerifiedDomainRecord, sqlx::error::Error> {
+        sqlx::query_as(
+            r#"
+        SELECT VERIFIED_DOMAINS.ID, VERIFIED_DOMAINS.WORKSPACE, VERIFIED_DOMAINS.DOMAIN,
+            VERIFIED_DOMAINS.VERIFICATION_TOKEN,
+            VERIFIED_DOMAINS.IS_VERIFIED, VERIFIED_DOMAINS.INCLUDE_SUBDOMAINS,
+            VERIFIED_DOMAINS.VERIFICATION_METHOD,
+            VERIFIED_DOMAINS.VERIFIED_AT, VERIFIED_DOMAINS.LAST_CHECKED_AT,
+            VERIFIED_DOMAINS.FAILED_CHECKS, VERIFIED_DOMAINS.CHECK_ERROR
+        FROM VERIFIED_DOMAINS
+        WHERE VERIFIED_DOMAINS.ID = $1 AND VERIFIED_DOMAINS.WORKSPACE = $2
+        "#,
+        )
+        .bind(id)
+        .bind(workspace_id)
+        .fetch_one(&self.db)
+        .await
+    }
+
+    // verified domains whose proof wasn't looked at for a day, oldest first
+    async fn get_domains_due_for_recheck(
+        &self,
+        limit: i64,
+    ) -> Result<Vec<VerifiedDomainRecord>, sqlx::error::Error> {
+        sqlx::query_as(
+            r#"
+        SELECT ID, WORKSPACE, DOMAIN, VERIFICATION_TOKEN, IS_VERIFIED, INCLUDE_SUBDOMAINS,
+            VERIFICATION_METHOD, VERIFIED_AT,
+            LAST_CHECKED_AT, FAILED_CHECKS, CHECK_ERROR
+        FROM VERIFIED_DOMAINS
+        WHERE IS_VERIFIED AND (LAST_CHECKED_AT IS NULL
+            OR LAST_CHECKED_AT < CURRENT_TIMESTAMP - make_interval(hours => $1))
+        ORDER BY LAST_CHECKED_AT NULLS FIRST
+        LIMIT $2
+        "#,
+        )
+        .bind(DOMAIN_RECHECK_HOURS)
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await
+    }
+
+    async fn count_workspace_domains(&self, workspace_id: Uuid) -> Result<i64, sqlx::error::Error> {
+        let (count,): (i64,) = sqlx::query_as(
+            r#"
+        SELECT COUNT(*) FROM VERIFIED_DOMAINS
+        WHERE VERIFIED_DOMAINS.WORKSPACE = $1
+        "#,
+        )
+        .bind(workspace_id)
+        .fetch_one(&self.db)
+        .await?;
+        Ok(count)
+    }
+
+    async fn get_verified_domain_names(
+        &self,
+        workspace_id: Uuid,
+    ) -> Result<Vec<String>, sqlx::error::Error> {
+        let domains: Vec<(String,)> = sqlx::query_as(
+            r#"
+        SELECT VERIFIED_DOMAINS.DOMAIN FROM VERIFIED_DOMAINS
+        WHERE VERIFIED_DOMAINS.WORKSPACE = $1 AND VERIFIED_DOMAINS.IS_VERIFIED
+        ORDER BY VERIFIED_DOMAINS.DOMAIN
+        "#,
+        )
+        .bind(workspace_id)
+        .fetch_all(&self.db)
+        .await?;
+        Ok(domains.into_iter().map(|(d,)| d).collect())
+    }
+
+    async fn get_workspace_domain_names(
+        &self,
+        workspace_id: Uuid,
+    ) -> Result<Vec<String>, sqlx::error::Error> {
+        let domains: Vec<(String,)> = sqlx::query_as(
+            r#"
+        SELECT VERIFIED_DOMAINS.DOMAIN FROM VERIFIED_DOMAINS
+        WHERE VERIFIED_DOMAINS.WORKSPACE = $1
+        "#,
+        )
+        .bind(workspace_id)
+        .fetch_all(&self.db)
+        .await?;
+        Ok(domains.into_iter().map(|(d,)| d).collect())
+    }
+
+    async fn find_covering_domain(
+        &self,
+        workspace_id: Uuid,
+        host: &str,
+        parents: &[String],
+    ) -> Result<Option<String>, sqlx::error::Error> {
+        let domain: Option<(String,)> = sqlx::query_as(
+            r#"
+        SELECT DOMAIN FROM VERIFIED_DOMAINS
+        WHERE WORKSPACE = $1 AND IS_VERIFIED
+        AND (DOMAIN = $2 OR (INCLUDE_SUBDOMAINS AND DOMAIN = ANY($3)))
+        ORDER BY LENGTH(DOMAIN) DESC
+        LIMIT 1
+        "#,
+        )
+        .bind(workspace_id)
+        .bind(host)
+        .bind(parents)
+        .fetch_optional(&self.db)
+        .await?;
+        Ok(domain.map(|(d,)| d))
+    }
+
+    async fn set_domain_subdomains(
+        &self,
+        workspace_id: Uuid,
+        id: Uuid,
+        include_subdomains: bool,
+    ) -> Result<(), sqlx::error::Error> {
+        let result = sqlx::query(
+            r#"
+        UPDATE VERIFIED_DOMAINS SET INCLUDE_SUBDOMAINS = $1, UPDATED_AT = CURRENT_TIMESTAMP
+        WHERE ID = $2 AND WORKSPACE = $3
+        "#,
+        )
+        .bind(include_subdomains)
+        .bind(id)
+        .bind(workspace_id)
+        .execute(&self.db)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(sqlx::error::Error::RowNotFound);
+        }
+        Ok(())
+    }
+
+    async fn list_workspace_domains(
+        &self,
+        workspace_id: Uuid,
+        verified: Option<bool>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<VerifiedDomainRecord>, i64), sqlx::error::Error> {
+        let filter = r#"
+        FROM VERIFIED_DOMAINS
+        WHERE VERIFIED_DOMAINS.WORKSPACE = $1
+        AND ($2::BOOLEAN IS NULL OR VERIFIED_DOMAINS.IS_VERIFIED = $2)
+        "#;
+        let (total,): (i64,) = sqlx::query_as(&format!("SELECT COUNT(*) {}", filter))
+            .bind(workspace_id)
+            .bind(verified)
+            .fetch_one(&self.db)
+            .await?;
+        let domains: Vec<VerifiedDomainRecord> = sqlx::query_as(&format!(
+            r#"
+        SELECT VERIFIED_DOMAINS.ID, VERIFIED_DOMAINS.WORKSPACE, VERIFIED_DOMAINS.DOMAIN,
+            VERIFIED_DOMAINS.VERIFICATION_TOKEN,
+            VERIFIED_DOMAINS.IS_VERIFIED, VERIFIED_DOMAINS.INCLUDE_SUBDOMAINS,
+            VERIFIED_DOMAINS.VERIFICATION_METHOD,
+            VERIFIED_DOMAINS.VERIFIED_AT, VERIFIED_DOMAINS.LAST_CHECKED_AT,
+            VERIFIED_DOMAINS.FAILED_CHECKS, VERIFIED_DOMAINS.CHECK_ERROR
+        {}
+        ORDER BY VERIFIED_DOMAINS.DOMAIN
+        LIMIT $3 OFFSET $4
+        "#,
+            filter
+        ))
+        .bind(workspace_id)
+        .bind(verified)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.db)
+        .await?;
+        Ok((domains, total))
+    }
+
+    async fn delete_workspace_domain(&self, workspace_id: Uuid, id: Uuid) -> Result<(), sqlx::error::Error> {
+        let result = sqlx::query(
+            r#"
+        DELETE FROM VERIFIED_DOMAINS WHERE ID = $1 AND WORKSPACE = $2
+        "#,
+        )
+        .bind(id)
+        .bind(workspace_id)
+        .execute(&self.db)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(sqlx::error::Error::RowNotFound);
+        }
+        Ok(())
+    }
 }
@@ -1,41 +1,319 @@
 use crate::darkscout::types::store::{VerifiedEmailsRepo, VerifiedEmailsStore};
-use crate::darkscout::types::verified_emails::VerifiedEmail;
+use crate::darkscout::types::verified_emails::{
+    EmailConfirmation, VerifiedEmail, VerifiedEmailSummary, CONFIRMATION_RESEND_MINUTES,
+    CONFIRMATION_SEND_WINDOW_HOURS, MAX_CONFIRMATION_SENDS, VERIFIED_EMAIL_TTL_HOURS,
+};
+use uuid::Uuid;
+
+const EMAIL_SUMMARY_COLUMNS: &str = r#"
+    VERIFIED_EMAILS.ID, VERIFIED_EMAILS.EMAIL, VERIFIED_EMAILS.IS_VERIFIED,
+    VERIFIED_EMAILS.SEND_COUNT, VERIFIED_EMAILS.LAST_SENT_AT,
+    VERIFIED_EMAILS.TOKEN_EXPIRES_AT, VERIFIED_EMAILS.SEND_ERROR,
+    VERIFIED_EMAILS.VERIFIED_AT,
+    COALESCE(VERIFIED_EMAILS.TOKEN_EXPIRES_AT <= CURRENT_TIMESTAMP, TRUE) AS EXPIRED
+"#;
 
 impl VerifiedEmailsStore for VerifiedEmailsRepo {
+    // returns the records that were added, an address the workspace already
+    // has is skipped
     async fn add_verified_emails(
         &self,
+        workspace_id: Uuid,
         records: Vec<VerifiedEmail>,
-    ) -> Result<(), sqlx::error::Error> {
+    ) -> Result<Vec<VerifiedEmail>, sqlx::error::Error> {
         let mut transaction = self.db.begin().await?;
-        let insert_query = "INSERT INTO VERIFIED_EMAILS (ID, EMAIL, ADDED_BY) VALUES ($1, $2, $3)";
+        let insert_query = r#"
+        INSERT INTO VERIFIED_EMAILS (ID, EMAIL, ADDED_BY, CONFIRM_TOKEN, TOKEN_EXPIRES_AT, SEND_COUNT, LAST_SENT_AT, WORKSPACE)
+        VALUES ($1, $2, $3, $4, CURRENT_TIMESTAMP + make_interval(hours => $5), 1, CURRENT_TIMESTAMP, $6)
+        ON CONFLICT (WORKSPACE, EMAIL) DO NOTHING
+        "#;
+        let mut added = Vec::new();
         for record in records {
-            sqlx::query(insert_query)
+            let result = sqlx::query(insert_query)
                 .bind(&record.id)
                 .bind(&record.email)
                 .bind(&record.added_by)
+                .bind(&record.confirm_token)
+                .bind(VERIFIED_EMAIL_TTL_HOURS)
+                .bind(workspace_id)
                 .execute(&mut *transaction)
                 .await?;
+            if result.rows_affected() > 0 {
+                added.push(record);
             }
+        }
         transaction.commit().await?;
-        Ok(())
+        Ok(added)
     }
 
-    async fn update_verified_email(&self, email: &str, is_verified: bool) -> Result<(), sqlx::error::Error> {
-        match sqlx::query(
+    async fn update_verified_email(
+        &self,
+        workspace_id: Uuid,
+        id: Uuid,
+        is_verified: bool,
+    ) -> Result<(), sqlx::error::Error> {
+        // a confirmed address has no use for its link anymore. Rows are
+        // matched by id within the workspace, the same address added to
+        // another workspace keeps its own state.
+        let result = sqlx::query(
             r#"
         UPDATE VERIFIED_EMAILS SET
         IS_VERIFIED = $1,
+        VERIFIED_AT = CASE WHEN $1 THEN CURRENT_TIMESTAMP END,
+        CONFIRM_TOKEN = CASE WHEN $1 THEN NULL ELSE CONFIRM_TOKEN END,
         UPDATED_AT = CURRENT_TIMESTAMP
-        WHERE EMAIL = $2
+        WHERE ID = $2 AND WORKSPACE = $3
         "#,
         )
         .bind(is_verified)
+        .bind(id)
+        .bind(workspace_id)
+        .execute(&self.db)
+        .await?;
+        if result.rows_affected() != 1 {
+            return Err(sqlx::error::Error::RowNotFound);
+        }
+        Ok(())
+    }
+
+    async fn count_workspace_emails(&self, workspace_id: Uuid) -> Result<i64, sqlx::error::Error> {
+        let (count,): (i64,) = sqlx::query_as(
+            r#"
+        SELECT COUNT(*) FROM VERIFIED_EMAILS
+        WHERE VERIFIED_EMAILS.WORKSPACE = $1
+        "#,
+        )
+        .bind(workspace_id)
+        .fetch_one(&self.db)
+        .await?;
+        Ok(count)
+    }
+
+    async fn get_workspace_email_names(
+        &self,
+        workspace_id: Uuid,
+    ) -> Result<Vec<String>, sqlx::error::Error> {
+        let emails: Vec<(String,)> = sqlx::query_as(
+            r#"
+        SELECT VERIFIED_EMAILS.EMAIL FROM VERIFIED_EMAILS
+        WHERE VERIFIED_EMAILS.WORKSPACE = $1
+        "#,
+        )
+        .bind(workspace_id)
+        .fetch_all(&self.db)
+        .await?;
+        Ok(emails.into_iter().map(|(e,)| e).collect())
+    }
+
+    async fn is_email_verified(&self, workspace_id: Uuid, email: &str) -> Result<bool, sqlx::error::Error> {
+        let (verified,): (bool,) = sqlx::query_as(
+            r#"
+        SELECT EXISTS (
+            SELECT 1 FROM VERIFIED_EMAILS
+            WHERE WORKSPACE = $1 AND EMAIL = $2 AND IS_VERIFIED
+        )
+        "#,
+        )
+        .bind(workspace_id)
         .bind(email)
+        .fetch_one(&self.db)
+        .await?;
+        Ok(verified)
+    }
+
+    async fn list_workspace_emails(
+        &self,
+        workspace_id: Uuid,
+        verified: Option<bool>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<VerifiedEmailSummary>, i64), sqlx::error::Error> {
+        let filter = r#"
+        FROM VERIFIED_EMAILS
+        WHERE VERIFIED_EMAILS.WORKSPACE = $1
+        AND ($2::BOOLEAN IS NULL OR VERIFIED_EMAILS.IS_VERIFIED = $2)
+        "#;
+        let (total,): (i64,) = sqlx::query_as(&format!("SELECT COUNT(*) {}", filter))
+            .bind(workspace_id)
+            .bind(verified)
+            .fetch_one(&self.db)
+            .await?;
+        let emails: Vec<VerifiedEmailSummary> = sqlx::query_as(&format!(
+            r#"
+        SELECT {}
+        {}
+        ORDER BY VERIFIED_EMAILS.EMAIL
+        LIMIT $3 OFFSET $4
+        "#,
+            EMAIL_SUMMARY_COLUMNS, filter
+        ))
+        .bind(workspace_id)
+        .bind(verified)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.db)
+        .await?;
+        Ok((emails.into_iter().map(|e| e.with_status()).collect(), total))
+    }
+
+    async fn get_workspace_email(
+        &self,
+        workspace_id: Uuid,
+        id: Uuid,
+    ) -> Result<VerifiedEmailSummary, sqlx::error::Error> {
+        let email: VerifiedEmailSummary = sqlx::query_as(&format!(
+            r#"
+        SELECT {}
+        FROM VERIFIED_EMAILS
+        WHERE VERIFIED_EMAILS.ID = $1 AND VERIFIED_EMAILS.WORKSPACE = $2
+        "#,
+            EMAIL_SUMMARY_COLUMNS
+        ))
+        .bind(id)
+        .bind(workspace_id)
+        .fetch_one(&self.db)
+        .await?;
+        Ok(email.with_status())
+    }
+
+    async fn delete_workspace_email(&self, workspace_id: Uuid, id: Uuid) -> Result<(), sqlx::error::Error> {
+        let result = sqlx::query(
+            r#"
+        DELETE FROM VERIFIED_EMAILS WHERE ID = $1 AND WORKSPACE = $2
+        "#,
+        )
+        .bind(id)
+        .bind(workspace_id)
         .execute(&self.db)
-        .await
-        {
-            Ok(_) => Ok(()),
-            Err(e) => Err(e),
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(sqlx::error::Error::RowNotFound);
         }
+        Ok(())
+    }
+
+    async fn restart_email_confirmation(
+        &self,
+        workspace_id: Uuid,
+        id: Uuid,
+        token: String,
+    ) -> Result<Option<VerifiedEmail>, sqlx::error::Error> {
+        // the address goes back to unconfirmed, the send count and the resend
+        // interval still apply
+        sqlx::query_as(
+            r#"
+        UPDATE VERIFIED_EMAILS SET IS_VERIFIED = FALSE, VERIFIED_AT = NULL, CONFIRM_TOKEN = $3,
+        TOKEN_EXPIRES_AT = CURRENT_TIMESTAMP + make_interval(hours => $4),
+        SEND_COUNT = SEND_COUNT + 1, LAST_SENT_AT = CURRENT_TIMESTAMP, SEND_ERROR = NULL,
+        UPDATED_AT = CURRENT_TIMESTAMP
+        WHERE VERIFIED_EMAILS.ID = $1 AND VERIFIED_EMAILS.WORKSPACE = $2
+        AND VERIFIED_EMAILS.SEND_COUNT < $6
+        AND (VERIFIED_EMAILS.LAST_SENT_AT IS NULL
+            OR VERIFIED_EMAILS.LAST_SENT_AT < CURRENT_TIMESTAMP - make_interval(mins => $5))
+        RETURNING VERIFIED_EMAILS.ID, VERIFIED_EMAILS.EMAIL, VERIFIED_EMAILS.ADDED_BY,
+            VERIFIED_EMAILS.CONFIRM_TOKEN
+        "#,
+        )
+        .bind(id)
+        .bind(workspace_id)
+        .bind(token)
+        .bind(VERIFIED_EMAIL_TTL_HOURS)
+        .bind(CONFIRMATION_RESEND_MINUTES)
+        .bind(MAX_CONFIRMATION_SENDS)
+        .fetch_optional(&self.db)
+        .await
+    }
+
+    async fn get_email_confirmation(&self, token: &str) -> Result<EmailConfirmation, sqlx::error::Error> {
+        sqlx::query_as(
+            r#"
+        SELECT ID, WORKSPACE, EMAIL, IS_VERIFIED,
+            COALESCE(TOKEN_EXPIRES_AT <= CURRENT_TIMESTAMP, TRUE) AS EXPIRED
+        FROM VERIFIED_EMAILS WHERE CONFIRM_TOKEN = $1
+        "#,
+        )
+        .bind(token)
+        .fetch_one(&self.db)
+        .await
+    }
+
+    // records a confirmation email for the address unless it already got
+    // MAX_CONFIRMATION_SENDS within the window, whichever row it was sent for
+    async fn claim_address_send(&self, email: &str) -> Result<bool, sqlx::error::Error> {
+        let result = sqlx::query(
+            r#"
+        INSERT INTO CONFIRMATION_SENDS (EMAIL)
+        SELECT $1 WHERE (
+            SELECT COUNT(*) FROM CONFIRMATION_SENDS
+            WHERE EMAIL = $1 AND SENT_AT > CURRENT_TIMESTAMP - make_interval(hours => $3)
+        ) < $2
+        "#,
+        )
+        .bind(email)
+        .bind(MAX_CONFIRMATION_SENDS as i64)
+        .bind(CONFIRMATION_SEND_WINDOW_HOURS)
+        .execute(&self.db)
+        .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn mark_confirmation_sent(&self, id: Uuid) -> Result<(), sqlx::error::Error> {
+        sqlx::query(
+            r#"
+        UPDATE VERIFIED_EMAILS SET SEND_ERROR = NULL, UPDATED_AT = CURRENT_TIMESTAMP
+        WHERE ID = $1
+        "#,
+        )
+        .bind(id)
+        .execute(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    async fn mark_confirmation_failed(&self, id: Uuid, error: String) -> Result<(), sqlx::error::Error> {
+        sqlx::query(
+            r#"
+        UPDATE VERIFIED_EMAILS SET SEND_ERROR = $2, UPDATED_AT = CURRENT_TIMESTAMP
+        WHERE ID = $1
+        "#,
+        )
+        .bind(id)
+        .bind(error)
+        .execute(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    async fn claim_confirmation_resend(
+        &self,
+        workspace_id: Uuid,
+        id: Uuid,
+        token: String,
+    ) -> Result<Option<VerifiedEmail>, sqlx::error::Error> {
+        // the row is only returned when the resend limits allow another email,
+        // the new link replaces the old one
+        sqlx::query_as(
+            r#"
+        UPDATE VERIFIED_EMAILS SET CONFIRM_TOKEN = $3,
+        TOKEN_EXPIRES_AT = CURRENT_TIMESTAMP + make_interval(hours => $4),
+        SEND_COUNT = SEND_COUNT + 1, LAST_SENT_AT = CURRENT_TIMESTAMP, SEND_ERROR = NULL,
+        UPDATED_AT = CURRENT_TIMESTAMP
+        WHERE VERIFIED_EMAILS.ID = $1 AND VERIFIED_EMAILS.WORKSPACE = $2
+        AND NOT VERIFIED_EMAILS.IS_VERIFIED
+        AND VERIFIED_EMAILS.SEND_COUNT < $5
+        AND (VERIFIED_EMAILS.LAST_SENT_AT IS NULL
+            OR VERIFIED_EMAILS.LAST_SENT_AT < CURRENT_TIMESTAMP - make_interval(mins => $6))
+        RETURNING VERIFIED_EMAILS.ID, VERIFIED_EMAILS.EMAIL, VERIFIED_EMAILS.ADDED_BY,
+            VERIFIED_EMAILS.CONFIRM_TOKEN
+        "#,
+        )
+        .bind(id)
+        .bind(workspace_id)
+        .bind(token)
+        .bind(VERIFIED_EMAIL_TTL_HOURS)
+        .bind(MAX_CONFIRMATION_SENDS)
+        .bind(CONFIRMATION_RESEND_MINUTES)
+        .fetch_optional(&self.db)
+        .await
     }
 }
@@ -0,0 +1,272 @@
+use crate::darkscout::types::store::{WebhooksRepo, WebhooksStore};
+use crate::darkscout::types::webhooks::{
+    DeliveryStatus, DueDelivery, WebhookDelivery, WebhookEndpoint, WebhookEvent,
+};
+use sqlx::Error;
+use uuid::Uuid;
+
+impl WebhooksStore for WebhooksRepo {
+    async fn create_webhook_endpoint(&self, endpoint: &WebhookEndpoint) -> Result<(), Error> {
+        sqlx::query(
+            r#"
+        INSERT INTO WEBHOOK_ENDPOINTS (ID, WORKSPACE, URL, EVENTS, SECRET, ENABLED, CREATED_BY)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#,
+        )
+        .bind(endpoint.id)
+        .bind(endpoint.workspace)
+        .bind(&endpoint.url)
+        .bind(&endpoint.events)
+        .bind(&endpoint.secret)
+        .bind(endpoint.enabled)
+        .bind(endpoint.created_by)
+        .execute(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    async fn list_webhook_endpoints(
+        &self,
+        workspace_id: Uuid,
+    ) -> Result<Vec<WebhookEndpoint>, Error> {
+        sqlx::query_as(
+            r#"
+        SELECT * FROM WEBHOOK_ENDPOINTS WHERE WORKSPACE = $1 ORDER BY CREATED_AT
+        "#,
+        )
+        .bind(workspace_id)
+        .fetch_all(&self.db)
+        .await
+    }
+
+    async fn get_webhook_endpoint(
+        &self,
+        workspace_id: Uuid,
+        id: Uuid,
+    ) -> Result<WebhookEndpoint, Error> {
+        sqlx::query_as(
+            r#"
+        SELECT * FROM WEBHOOK_ENDPOINTS WHERE ID = $1 AND WORKSPACE = $2
+        "#,
+        )
+        .bind(id)
+        .bind(workspace_id)
+        .fetch_one(&self.db)
+        .await
+    }
+
+    async fn update_webhook_endpoint(
+        &self,
+        workspace_id: Uuid,
+        id: Uuid,
+        url: Option<String>,
+        events: Option<Vec<String>>,
+        enabled: Option<bool>,
+    ) -> Result<WebhookEndpoint, Error> {
+        let endpoint: Option<WebhookEndpoint> = sqlx::query_as(
+            r#"
+        UPDATE WEBHOOK_ENDPOINTS SET URL = COALESCE($3, URL), EVENTS = COALESCE($4, EVENTS),
+        ENABLED = COALESCE($5, ENABLED), UPDATED_AT = CURRENT_TIMESTAMP
+        WHERE ID = $1 AND WORKSPACE = $2
+        RETURNING *
+        "#,
+        )
+        .bind(id)
+        .bind(workspace_id)
+        .bind(url)
+        .bind(events)
+        .bind(enabled)
+        .fetch_optional(&self.db)
+        .await?;
+        endpoint.ok_or(Error::RowNotFound)
+    }
+
+    async fn delete_webhook_endpoint(&self, workspace_id: Uuid, id: Uuid) -> Result<(), Error> {
+        let result = sqlx::query(
+            r#"
+        DELETE FROM WEBHOOK_ENDPOINTS WHERE ID = $1 AND WORKSPACE = $2
+        "#,
+        )
+        .bind(id)
+        .bind(workspace_id)
+        .execute(&self.db)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(Error::RowNotFound);
+        }
+        Ok(())
+    }
+
+    async fn rotate_webhook_secret(
+        &self,
+        workspace_id: Uuid,
+        id: Uuid,
+        secret: String,
+        grace_hours: i32,
+    ) -> Result<WebhookEndpoint, Error> {
+        let endpoint: Option<WebhookEndpoint> = sqlx::query_as(
+            r#"
+        UPDATE WEBHOOK_ENDPOINTS SET PREVIOUS_SECRET = SECRET, SECRET = $3,
+        PREVIOUS_SECRET_EXPIRES_AT = CURRENT_TIMESTAMP + make_interval(hours => $4),
+        UPDATED_AT = CURRENT_TIMESTAMP
+        WHERE ID = $1 AND WORKSPACE = $2
+        RETURNING *
+        "#,
+        )
+        .bind(id)
+        .bind(workspace_id)
+        .bind(secret)
+        .bind(grace_hours)
+        .fetch_optional(&self.db)
+        .await?;
+        endpoint.ok_or(Error::RowNotFound)
+    }
+
+    async fn enqueue_webhook_event(
+        &self,
+        workspace_id: Uuid,
+        event: WebhookEvent,
+        event_id: Uuid,
+        payload: String,
+    ) -> Result<u64, Error> {
+        let result = sqlx::query(
+            r#"
+        INSERT INTO WEBHOOK_DELIVERIES (ID, WORKSPACE, ENDPOINT, EVENT_ID, EVENT, PAYLOAD)
+        SELECT uuid_generate_v4(), WORKSPACE, ID, $2, $3, $4
+        FROM WEBHOOK_ENDPOINTS
+        WHERE WORKSPACE = $1 AND ENABLED = TRUE AND $5 = ANY(EVENTS)
+        "#,
+        )
+        .bind(workspace_id)
+        .bind(event_id)
+        .bind(event)
+        .bind(payload)
+        .bind(event.name())
+        .execute(&self.db)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn claim_due_deliveries(
+        &self,
+        limit: i64,
+        lease_minutes: i32,
+    ) -> Result<Vec<DueDelivery>, Error> {
+        sqlx::query_as(
+            r#"
+        WITH DUE AS (
+            SELECT WEBHOOK_DELIVERIES.ID FROM WEBHOOK_DELIVERIES
+            INNER JOIN WEBHOOK_ENDPOINTS ON WEBHOOK_ENDPOINTS.ID = WEBHOOK_DELIVERIES.ENDPOINT
+            WHERE WEBHOOK_DELIVERIES.STATUS = 'PENDING'
+            AND WEBHOOK_DELIVERIES.NEXT_ATTEMPT_AT <= CURRENT_TIMESTAMP
+            AND WEBHOOK_ENDPOINTS.ENABLED = TRUE
+            ORDER BY WEBHOOK_DELIVERIES.NEXT_ATTEMPT_AT
+            LIMIT $1
+            FOR UPDATE OF WEBHOOK_DELIVERIES SKIP LOCKED
+        )
+        UPDATE WEBHOOK_DELIVERIES
+        SET ATTEMPTS = WEBHOOK_DELIVERIES.ATTEMPTS + 1,
+        NEXT_ATTEMPT_AT = CURRENT_TIMESTAMP + make_interval(mins => $2)
+        FROM DUE, WEBHOOK_ENDPOINTS
+        WHERE WEBHOOK_DELIVERIES.ID = DUE.ID AND WEBHOOK_ENDPOINTS.ID = WEBHOOK_DELIVERIES.ENDPOINT
+        RETURNING WEBHOOK_DELIVERIES.ID, WEBHOOK_DELIVERIES.EVENT_ID, WEBHOOK_DELIVERIES.EVENT,
+        WEBHOOK_DELIVERIES.PAYLOAD, WEBHOOK_DELIVERIES.ATTEMPTS, WEBHOOK_ENDPOINTS.URL,
+        WEBHOOK_ENDPOINTS.SECRET,
+        CASE WHEN WEBHOOK_ENDPOINTS.PREVIOUS_SECRET_EXPIRES_AT > CURRENT_TIMESTAMP
+            THEN WEBHOOK_ENDPOINTS.PREVIOUS_SECRET END AS PREVIOUS_SECRET
+        "#,
+        )
+        .bind(limit)
+        .bind(lease_minutes)
+        .fetch_all(&self.db)
+        .await
+    }
+
+    async fn record_delivery_result(
+        &self,
+        id: Uuid,
+        status: DeliveryStatus,
+        response_status: Option<i32>,
+        error: Option<String>,
+        retry_minutes: i32,
+    ) -> Result<(), Error> {
+        sqlx::query(
+            r#"
+        UPDATE WEBHOOK_DELIVERIES SET STATUS = $2, RESPONSE_STATUS = $3, ERROR = $4,
+        LAST_ATTEMPT_AT = CURRENT_TIMESTAMP,
+        NEXT_ATTEMPT_AT = CURRENT_TIMESTAMP + make_interval(mins => $5)
+        WHERE ID = $1
+        "#,
+        )
+        .bind(id)
+        .bind(status)
+        .bind(response_status)
+        .bind(error.map(|e| e.chars().take(1000).collect::<String>()))
+        .bind(retry_minutes)
+        .execute(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    async fn list_webhook_deliveries(
+        &self,
+        workspace_id: Uuid,
+        endpoint: Option<Uuid>,
+        status: Option<DeliveryStatus>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<WebhookDelivery>, i64), Error> {
+        let filter = r#"
+        FROM WEBHOOK_DELIVERIES
+        WHERE WEBHOOK_DELIVERIES.WORKSPACE = $1
+        AND ($2::UUID IS NULL OR WEBHOOK_DELIVERIES.ENDPOINT = $2)
+        AND ($3::WEBHOOK_DELIVERY_STATUS IS NULL OR WEBHOOK_DELIVERIES.STATUS = $3)
+        "#;
+        let (total,): (i64,) = sqlx::query_as(&format!("SELECT COUNT(*) {}", filter))
+            .bind(workspace_id)
+            .bind(endpoint)
+            .bind(status)
+            .fetch_one(&self.db)
+            .await?;
+        let deliveries: Vec<WebhookDelivery> = sqlx::query_as(&format!(
+            r#"
+        SELECT *
+        {}
+        ORDER BY WEBHOOK_DELIVERIES.CREATED_AT DESC
+        LIMIT $4 OFFSET $5
+        "#,
+            filter
+        ))
+        .bind(workspace_id)
+        .bind(endpoint)
+        .bind(status)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.db)
+        .await?;
+        Ok((deliveries, total))
+    }
+
+    async fn replay_webhook_delivery(
+        &self,
+        workspace_id: Uuid,
+        id: Uuid,
+    ) -> Result<WebhookDelivery, Error> {
+        // the event id stays the same so receivers can tell it's a replay
+        let delivery: Option<WebhookDelivery> = sqlx::query_as(
+            r#"
+        INSERT INTO WEBHOOK_DELIVERIES (ID, WORKSPACE, ENDPOINT, EVENT_ID, EVENT, PAYLOAD, REPLAY_OF)
+        SELECT $1, WORKSPACE, ENDPOINT, EVENT_ID, EVENT, PAYLOAD, ID
+        FROM WEBHOOK_DELIVERIES
+        WHERE ID = $2 AND WORKSPACE = $3
+        RETURNING *
+        "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(id)
+        .bind(workspace_id)
+        .fetch_optional(&self.db)
+        .await?;
+        delivery.ok_or(Error::RowNotFound)
+    }
+}
@@ -1,4 +1,6 @@
-use crate::darkscout::types::workspace::{MemberWorkspace, MemberWorkspaceDetails};
+use crate::darkscout::types::workspace::{
+    MemberWorkspace, MemberWorkspaceDetails, WorkspaceOrderForm, WorkspaceStatus, WorkspaceType,
+};
 use crate::darkscout::types::{
     store::{WorkspaceStore},
     workspace::Workspace,
@@ -10,11 +12,19 @@ use crate::darkscout::types::store::WorkspaceRepo;
 impl WorkspaceStore for WorkspaceRepo {
     async fn create_workspace(&self, record: Workspace) -> Result<Workspace, sqlx::error::Error> {
         return match sqlx::query(
-            r#"INSERT INTO WORKSPACES (ID, TITLE, CREATED_BY) VALUES ($1, $2, $3)"#,
+            r#"
+            INSERT INTO WORKSPACES
+            (ID, TITLE, DETAILS, CREATED_BY, DISPLAY_PICTURE, COUNTRY_CODE, COUNTRY)
+            VALUES ($1, COALESCE($2, ''), COALESCE($3, ''), $4, $5, $6, $7)
+            "#,
         )
         .bind(&record.id)
         .bind(&record.title)
+        .bind(&record.details)
         .bind(&record.created_by)
+        .bind(&record.display_picture)
+        .bind(&record.country_code)
+        .bind(&record.country)
         .execute(&self.db)
         .await
         {
@@ -26,23 +36,37 @@ impl WorkspaceStore for WorkspaceRepo {
         &self,
         record: MemberWorkspaceDetails,
     ) -> Result<(), sqlx::error::Error> {
-        return match sqlx::query(
+        let mut transaction = self.db.begin().await?;
+        if record.is_default {
+            sqlx::query(
+                r#"UPDATE MEMBER_WORKSPACE_DETAILS SET IS_DEFAULT = FALSE WHERE USER_ID = $1 AND IS_DEFAULT"#,
+            )
+            .bind(&record.user_id)
+            .execute(&mut *transaction)
+            .await?;
+        }
+        // the first workspace of a user always becomes the default one
+        sqlx::query(
             r#"
-            insert into member_workspace_details
-            (id, workspace_order, is_default, member_id) values
-            ($1, $2, $3, $4)
+            INSERT INTO MEMBER_WORKSPACE_DETAILS
+            (ID, WORKSPACE_ORDER, IS_DEFAULT, IS_FAV, MEMBER_ID, WORKSPACE_ID, USER_ID)
+            VALUES ($1, $2,
+                $3 OR NOT EXISTS (SELECT 1 FROM MEMBER_WORKSPACE_DETAILS WHERE USER_ID = $7 AND IS_DEFAULT),
+                $4, $5, $6, $7)
+            ON CONFLICT (MEMBER_ID, WORKSPACE_ID) DO NOTHING
             "#,
         )
         .bind(&record.id)
         .bind(&record.workspace_order)
         .bind(&record.is_default)
+        .bind(&record.is_fav)
         .bind(&record.member_id)
-        .execute(&self.db)
-        .await
-        {
-            Ok(_) => Ok(()),
-            Err(e) => Err(e),
-        };
+        .bind(&record.workspace_id)
+        .bind(&record.user_id)
+        .execute(&mut *transaction)
+        .await?;
+        transaction.commit().await?;
+        Ok(())
     }
 
     async fn get_admin_workspaces(
@@ -62,11 +86,16 @@ impl WorkspaceStore for WorkspaceRepo {
     ) -> Result<Vec<MemberWorkspace>, sqlx::error::Error> {
         let workspaces: Vec<MemberWorkspace> = sqlx::query_as(
             r#"
-        WITH WORKSPACE_IDS AS (SELECT WORKSPACE FROM MEMBERS WHERE USER_ID = $1)
-        SELECT *
-        FROM WORKSPACES LEFT JOIN MEMBER_WORKSPACE_DETAILS
-        ON WORKSPACES.ID = MEMBER_WORKSPACE_DETAILS.ID
-        WHERE WORKSPACES.ID IN (SELECT WORKSPACE FROM WORKSPACE_IDS)
+        SELECT WORKSPACES.ID, WORKSPACES.TITLE, WORKSPACES.DETAILS, WORKSPACES.CREATED_BY,
+            COALESCE(MEMBER_WORKSPACE_DETAILS.WORKSPACE_ORDER, 0) AS WORKSPACE_ORDER,
+            COALESCE(MEMBER_WORKSPACE_DETAILS.IS_DEFAULT, FALSE) AS IS_DEFAULT,
+            COALESCE(MEMBER_WORKSPACE_DETAILS.IS_FAV, FALSE) AS IS_FAV,
+            MEMBERS.ID AS MEMBER_ID
+        FROM MEMBERS
+        INNER JOIN WORKSPACES ON WORKSPACES.ID = MEMBERS.WORKSPACE
+        LEFT JOIN MEMBER_WORKSPACE_DETAILS ON MEMBER_WORKSPACE_DETAILS.MEMBER_ID = MEMBERS.ID
+        WHERE MEMBERS.USER_ID = $1 AND MEMBERS.STATUS <> 'DELETED'
+        ORDER BY WORKSPACE_ORDER, WORKSPACES.TITLE
         "#,
         )
         .bind(user_id)
@@ -75,12 +104,99 @@ impl WorkspaceStore for WorkspaceRepo {
         Ok(workspaces)
     }
 
+    async fn update_member_workspace_details(
+        &self,
+        user_id: Uuid,
+        workspace_id: Uuid,
+        order: Option<i32>,
+        is_fav: Option<bool>,
+    ) -> Result<(), Error> {
+        let result = sqlx::query(
+            r#"
+            UPDATE MEMBER_WORKSPACE_DETAILS SET
+            WORKSPACE_ORDER = COALESCE($1, WORKSPACE_ORDER),
+            IS_FAV = COALESCE($2, IS_FAV)
+            WHERE USER_ID = $3 AND WORKSPACE_ID = $4
+            "#,
+        )
+        .bind(order)
+        .bind(is_fav)
+        .bind(user_id)
+        .bind(workspace_id)
+        .execute(&self.db)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(Error::RowNotFound);
+        }
+        Ok(())
+    }
+
+    async fn set_default_workspace(&self, user_id: Uuid, workspace_id: Uuid) -> Result<(), Error> {
+        let mut transaction = self.db.begin().await?;
+        sqlx::query(
+            r#"UPDATE MEMBER_WORKSPACE_DETAILS SET IS_DEFAULT = FALSE WHERE USER_ID = $1 AND IS_DEFAULT"#,
+        )
+        .bind(user_id)
+        .execute(&mut *transaction)
+        .await?;
+        let result = sqlx::query(
+            r#"
+            UPDATE MEMBER_WORKSPACE_DETAILS SET IS_DEFAULT = TRUE
+            WHERE USER_ID = $1 AND WORKSPACE_ID = $2
+            "#,
+        )
+        .bind(user_id)
+        .bind(workspace_id)
+        .execute(&mut *transaction)
+        .await?;
+        // dropping the transaction rolls back the cleared default
+        if result.rows_affected() != 1 {
+            return Err(Error::RowNotFound);
+        }
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn reorder_user_workspaces(
+        &self,
+        user_id: Uuid,
+        orders: Vec<WorkspaceOrderForm>,
+    ) -> Result<(), Error> {
+        let mut transaction = self.db.begin().await?;
+        for o in orders {
+            let result = sqlx::query(
+                r#"
+                UPDATE MEMBER_WORKSPACE_DETAILS SET WORKSPACE_ORDER = $1
+                WHERE USER_ID = $2 AND WORKSPACE_ID = $3
+                "#,
+            )
+            .bind(o.order)
+            .bind(user_id)
+            .bind(o.workspace_id)
+            .execute(&mut *transaction)
+            .await?;
+            if result.rows_affected() != 1 {
+                return Err(Error::RowNotFound);
+            }
+        }
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn get_workspace(&self, workspace_id: Uuid) -> Result<Workspace, Error> {
+        let workspace: Workspace = sqlx::query_as(r#"SELECT * FROM WORKSPACES WHERE ID = $1"#)
+            .bind(workspace_id)
+            .fetch_one(&self.db)
+            .await?;
+        Ok(workspace)
+    }
+
     async fn get_member_workspace(&self, member_id: Uuid) -> Result<Workspace, sqlx::error::Error> {
         return match sqlx::query_as::<_, Workspace>(
             r#"
-        SELECT * FROM WORKSPACES
-        LEFT JOIN MEMBER_WORKSPACE_DETAILS ON WORKSPACES.ID = MEMBER_WORKSPACE_DETAILS.ID
-        WHERE WORKSPACES.ID = MEMBER_WORKSPACE_DETAILS.ID"#,
+        SELECT WORKSPACES.* FROM WORKSPACES
+        INNER JOIN MEMBERS ON MEMBERS.WORKSPACE = WORKSPACES.ID
+        WHERE MEMBERS.ID = $1"#,
         )
         .bind(member_id)
         .fetch_one(&self.db)
@@ -92,10 +208,22 @@ impl WorkspaceStore for WorkspaceRepo {
     }
     async fn update_workspace(&self, record: Workspace) -> Result<(), sqlx::error::Error> {
         return match sqlx::query(
-            r#"update workspaces set title = $1, details = $2 where id = $3"#,
+            r#"
+        update workspaces set
+        title = $1,
+        details = $2,
+        display_picture = $3,
+        country_code = $4,
+        country = $5,
+        updated_at = current_timestamp
+        where id = $6
+        "#,
         )
             .bind(&record.title)
             .bind(&record.details)
+            .bind(&record.display_picture)
+            .bind(&record.country_code)
+            .bind(&record.country)
             .bind(&record.id)
             .execute(&self.db)
             .await
@@ -104,4 +232,61 @@ impl WorkspaceStore for WorkspaceRepo {
             Err(e) => Err(e),
         };
     }
+
+    async fn get_workspace_status(&self, workspace_id: Uuid) -> Result<WorkspaceStatus, Error> {
+        let (status,): (WorkspaceStatus,) =
+            sqlx::query_as(r#"SELECT STATUS FROM WORKSPACES WHERE ID = $1"#)
+                .bind(workspace_id)
+                .fetch_one(&self.db)
+                .await?;
+        Ok(status)
+    }
+
+    async fn get_workspace_type(&self, workspace_id: Uuid) -> Result<WorkspaceType, Error> {
+        let (w_type,): (WorkspaceType,) =
+            sqlx::query_as(r#"SELECT W_TYPE FROM WORKSPACES WHERE ID = $1"#)
+                .bind(workspace_id)
+                .fetch_one(&self.db)
+                .await?;
+        Ok(w_type)
+    }
+
+    async fn update_workspace_status(
+        &self,
+        workspace_id: Uuid,
+        status: WorkspaceStatus,
+        reason: String,
+        changed_by: Uuid,
+    ) -> Result<(), Error> {
+        let mut transaction = self.db.begin().await?;
+        let (old_status,): (WorkspaceStatus,) =
+            sqlx::query_as(r#"SELECT STATUS FROM WORKSPACES WHERE ID = $1 FOR UPDATE"#)
+                .bind(workspace_id)
+                .fetch_one(&mut *transaction)
+                .await?;
+        sqlx::query(
+            r#"UPDATE WORKSPACES SET STATUS = $1, UPDATED_AT = CURRENT_TIMESTAMP WHERE ID = $2"#,
+        )
+        .bind(status)
+        .bind(workspace_id)
+        .execute(&mut *transaction)
+        .await?;
+        sqlx::query(
+            r#"
+            INSERT INTO WORKSPACE_STATUS_CHANGES
+            (ID, WORKSPACE, OLD_STATUS, NEW_STATUS, REASON, CHANGED_BY)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(workspace_id)
+        .bind(old_status)
+        .bind(status)
+        .bind(reason)
+        .bind(changed_by)
+        .execute(&mut *transaction)
+        .await?;
+        transaction.commit().await?;
+        Ok(())
+    }
 }
@@ -0,0 +1,131 @@
+use crate::darkscout::types::errors::STATUS_REASON_REQUIRED;
+use crate::darkscout::types::store::{UsageStore, WorkspaceStore};
+use crate::darkscout::types::usage::{UsageReportQuery, UsageRollup};
+use crate::darkscout::types::user::UserData;
+use crate::darkscout::types::workspace::WorkspaceStatusForm;
+use crate::darkscout::types::AppState;
+use crate::darkscout::web::{json_error, json_resp};
+use crate::json_err;
+use axum::extract::{Path, Query, State};
+use axum::http::header::{CONTENT_DISPOSITION, CONTENT_TYPE};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::{Extension, Json};
+use chrono::{Days, NaiveDate, Utc};
+use uuid::Uuid;
+
+pub async fn update_workspace_status(
+    State(state): State<AppState>,
+    Extension(user_data): Extension<UserData>,
+    Path(workspace_id): Path<Uuid>,
+    Json(form): Json<WorkspaceStatusForm>,
+) -> impl IntoResponse {
+    let reason = form.reason.trim().to_string();
+    if reason.is_empty() {
+        return json_err!(StatusCode::BAD_REQUEST, STATUS_REASON_REQUIRED);
+    }
+    if let Err(e) = state
+        .db
+        .workspace
+        .update_workspace_status(workspace_id, form.status, reason, user_data.id)
+        .await
+    {
+        tracing::debug!("WorkspaceStatusUpdateFailed: {}", e);
+        return json_err!();
+    };
+    tracing::info!(
+        "Workspace {} status set to {:?} by {}",
+        workspace_id,
+        form.status,
+        user_data.email
+    );
+    json_resp(None, "Success")
+}
+
+// defaults to the last 30 days
+fn report_range(query: &UsageReportQuery) -> (NaiveDate, NaiveDate) {
+    let to = query.to.unwrap_or_else(|| Utc::now().date_naive());
+    let from = query
+        .from
+        .unwrap_or_else(|| to.checked_sub_days(Days::new(30)).unwrap_or(to));
+    (from, to)
+}
+
+pub async fn get_usage_report(
+    State(state): State<AppState>,
+    Query(query): Query<UsageReportQuery>,
+) -> impl IntoResponse {
+    let (from, to) = report_range(&query);
+    return match state
+        .db
+        .usage
+        .get_usage_rollups(from, to, query.workspace)
+        .await
+    {
+        Ok(rollups) => json_resp::<Vec<UsageRollup>>(None, rollups),
+        Err(e) => {
+            tracing::debug!("UsageReportFailed: {}", e);
+            json_err!()
+        }
+    };
+}
+
+fn usage_csv(rollups: Vec<UsageRollup>) -> Result<String, csv::Error> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    writer.write_record([
+        "usage_date",
+        "workspace",
+        "user_id",
+        "provider",
+        "tier",
+        "lookups",
+        "failed_lookups",
+        "total_latency_ms",
+    ])?;
+    for r in rollups {
+        writer.write_record([
+            r.usage_date.to_string(),
+            r.workspace.to_string(),
+            r.user_id.map(|u| u.to_string()).unwrap_or_default(),
+            format!("{:?}", r.provider),
+            format!("{:?}", r.tier),
+            r.lookups.to_string(),
+            r.failed_lookups.to_string(),
+            r.total_latency_ms.to_string(),
+        ])?;
+    }
+    let bytes = writer.into_inner().map_err(|e| e.into_error())?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+pub async fn export_usage_report(
+    State(state): State<AppState>,
+    Query(query): Query<UsageReportQuery>,
+) -> impl IntoResponse {
+    let (from, to) = report_range(&query);
+    let rollups = match state
+        .db
+        .usage
+        .get_usage_rollups(from, to, query.workspace)
+        .await
+    {
+        Ok(rollups) => rollups,
+        Err(e) => {
+            tracing::debug!("UsageReportFailed: {}", e);
+            return json_error::<()>(None, None).into_response();
+        }
+    };
+    let Ok(body) = usage_csv(rollups) else {
+        return json_error::<()>(None, None).into_response();
+    };
+    let disposition = format!("attachment; filename=\"usage-{}-{}.csv\"", from, to);
+    (
+        StatusCode::OK,
+        [
+            (CONTENT_TYPE, String::from("text/csv")),
+            (CONTENT_DISPOSITION, disposition),
+        ],
+        body,
+    )
+        .into_response()
+}
@@ -0,0 +1,113 @@
+use crate::darkscout::types::alerts::{AlertListQuery, AlertStatus, BreachAlert, BreachAlertPage};
+use crate::darkscout::types::errors::{self, ALERT_NOT_FOUND, ALERT_STATUS_CONFLICT};
+use crate::darkscout::types::store::AlertsStore;
+use crate::darkscout::types::user::MemberProfile;
+use crate::darkscout::types::workspace::MemberWorkspace;
+use crate::darkscout::types::AppState;
+use crate::darkscout::web::{json_error, json_resp};
+use crate::json_err;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Extension;
+use sqlx::Error;
+use uuid::Uuid;
+
+pub async fn list_breach_alerts(
+    State(state): State<AppState>,
+    Extension(workspace): Extension<MemberWorkspace>,
+    Query(query): Query<AlertListQuery>,
+) -> impl IntoResponse {
+    let (page, per_page) = query.pagination();
+    return match state
+        .db
+        .alerts
+        .list_breach_alerts(workspace.id, query.status, per_page, (page - 1) * per_page)
+        .await
+    {
+        Ok((alerts, total)) => json_resp::<BreachAlertPage>(
+            None,
+            BreachAlertPage {
+                alerts,
+                page,
+                per_page,
+                total,
+            },
+        ),
+        Err(e) => {
+            tracing::debug!("Failed to list breach alerts {}", e);
+            json_err!()
+        }
+    };
+}
+
+async fn move_alert(
+    state: &AppState,
+    member: &MemberProfile,
+    workspace: &MemberWorkspace,
+    alert_id: Uuid,
+    to: AlertStatus,
+) -> Result<BreachAlert, (StatusCode, &'static str)> {
+    let alert = match state
+        .db
+        .alerts
+        .get_breach_alert(workspace.id, alert_id)
+        .await
+    {
+        Ok(a) => a,
+        Err(Error::RowNotFound) => return Err((StatusCode::NOT_FOUND, ALERT_NOT_FOUND)),
+        Err(e) => {
+            tracing::debug!("Failed to load breach alert {}", e);
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, errors::INTERNAL_SERVER_ERROR));
+        }
+    };
+    if !alert.status.can_move_to(to) {
+        return Err((StatusCode::CONFLICT, ALERT_STATUS_CONFLICT));
+    }
+    // another member can move it first
+    match state
+        .db
+        .alerts
+        .update_alert_status(workspace.id, alert_id, alert.status, to, member.id)
+        .await
+    {
+        Ok(a) => Ok(a),
+        Err(Error::RowNotFound) => Err((StatusCode::CONFLICT, ALERT_STATUS_CONFLICT)),
+        Err(e) => {
+            tracing::debug!("Failed to update breach alert {}", e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, errors::INTERNAL_SERVER_ERROR))
+        }
+    }
+}
+
+pub async fn acknowledge_breach_alert(
+    State(state): State<AppState>,
+    Extension(member): Extension<MemberProfile>,
+    Extension(workspace): Extension<MemberWorkspace>,
+    Path(alert_id): Path<Uuid>,
+) -> impl IntoResponse {
+    return match move_alert(
+        &state,
+        &member,
+        &workspace,
+        alert_id,
+        AlertStatus::ACKNOWLEDGED,
+    )
+    .await
+    {
+        Ok(alert) => json_resp::<BreachAlert>(None, alert),
+        Err((code, msg)) => json_err!(code, msg),
+    };
+}
+
+pub async fn resolve_breach_alert(
+    State(state): State<AppState>,
+    Extension(member): Extension<MemberProfile>,
+    Extension(workspace): Extension<MemberWorkspace>,
+    Path(alert_id): Path<Uuid>,
+) -> impl IntoResponse {
+    return match move_alert(&state, &member, &workspace, alert_id, AlertStatus::RESOLVED).await {
+        Ok(alert) => json_resp::<BreachAlert>(None, alert),
+        Err((code, msg)) => json_err!(code, msg),
+    };
+}
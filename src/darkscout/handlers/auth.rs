@@ -4,7 +4,8 @@ use crate::darkscout::types::auth::{
 use crate::darkscout::types::store::{AuthStore, UserStore};
 use crate::darkscout::types::user::UserRecord;
 use crate::darkscout::types::AppState;
-use crate::darkscout::utils::auth::encrypt_password;
+use crate::darkscout::types::errors::WEAK_PASSWORD;
+use crate::darkscout::utils::auth::{encrypt_password, is_password_allowed};
 use axum::extract::State;
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
@@ -61,6 +62,9 @@ pub async fn reset_password_with_token(
     State(state): State<AppState>,
     Json(form): Json<ResetPasswordWithToken>,
 ) -> impl IntoResponse {
+    if !is_password_allowed(&form.new_password) {
+        return json_err!(StatusCode::BAD_REQUEST, WEAK_PASSWORD);
+    }
     let new_password_hash = match encrypt_password(form.new_password) {
         Ok(h) => h,
         Err(e) => {
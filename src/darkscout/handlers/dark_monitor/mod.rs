@@ -1,32 +1,58 @@
 use crate::darkscout::adapters::dsbreach::BreachName;
-use crate::darkscout::adapters::{DSProvider, DarkSearchProvider};
+use crate::darkscout::adapters::{CacheTier, DSProvider, DarkSearchProvider};
 use crate::darkscout::types::darkmonitor::{
     BreachInfo, DomainStats, EmailStats, EmailStatsConverter,
 };
+use crate::darkscout::types::plans::Quota;
+use crate::darkscout::types::usage::UsageProvider;
+use crate::darkscout::types::usage::{LookupTarget, LookupTargetType};
+use crate::darkscout::types::user::UserData;
+use crate::darkscout::types::workspace::MemberWorkspace;
 use crate::darkscout::types::{AppState, DSResponse};
 use crate::darkscout::utils::countries::get_country_code_from_name;
-use axum::extract::{Path, State};
+use crate::darkscout::utils::emails::workspace_branding;
+use crate::darkscout::utils::metering::LookupMeter;
+use crate::darkscout::utils::monitoring::{lookup_trend, record_lookup_snapshot};
+use crate::darkscout::utils::quotas::consume_daily_quota;
+use crate::darkscout::utils::webhooks::push_dark_search_hits;
+use axum::extract::State;
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
-use axum::Json;
+use axum::{Extension, Json};
+use uuid::Uuid;
 
 pub async fn get_stats_by_email(
     State(state): State<AppState>,
-    Path(email): Path<String>,
+    Extension(workspace): Extension<MemberWorkspace>,
+    Extension(user): Extension<UserData>,
+    Extension(target): Extension<LookupTarget>,
 ) -> impl IntoResponse {
+    let email = target.value;
+    if let Err(e) = consume_daily_quota(&state, workspace.id, Quota::DAILY_LOOKUPS).await {
+        return e.into_json();
+    }
+    let meter = LookupMeter::start(&workspace, &user, UsageProvider::DSBREACH, &email);
     match state.ds_provider.get_stats_by_email(email.as_str()).await {
         Ok(result) => {
+            meter.finish(&state, result.tier, true);
+            let breach_info = build_breach_info(result.data);
+            spawn_lookup_snapshot(
+                &state,
+                workspace.id,
+                LookupTargetType::EMAIL,
+                &email,
+                &breach_info,
+            );
             return (
                 StatusCode::OK,
                 Json(DSResponse {
-                    data: Some(EmailStats {
-                        breach_info: build_breach_info(result),
-                    }),
+                    data: Some(EmailStats { breach_info }),
                     err: None,
                 }),
             );
         }
         Err(err) => {
+            meter.finish(&state, CacheTier::UPSTREAM, false);
             tracing::debug!("{}", err);
             return (
                 StatusCode::NOT_FOUND,
@@ -41,23 +67,40 @@ pub async fn get_stats_by_email(
 
 pub async fn get_stats_by_domain(
     State(state): State<AppState>,
-    Path(domain): Path<String>,
+    Extension(workspace): Extension<MemberWorkspace>,
+    Extension(user): Extension<UserData>,
+    Extension(target): Extension<LookupTarget>,
 ) -> impl IntoResponse {
+    let domain = target.value;
+    if let Err(e) = consume_daily_quota(&state, workspace.id, Quota::DAILY_LOOKUPS).await {
+        return e.into_json();
+    }
+    let meter = LookupMeter::start(&workspace, &user, UsageProvider::DSBREACH, &domain);
     return match state
         .ds_provider
         .get_stats_by_email(format!("a@{}", domain).as_str())
         .await
     {
-        Ok(result) => (
-            StatusCode::OK,
-            Json(DSResponse {
-                data: Some(DomainStats {
-                    breach_info: build_breach_info(result),
+        Ok(result) => {
+            meter.finish(&state, result.tier, true);
+            let breach_info = build_breach_info(result.data);
+            spawn_lookup_snapshot(
+                &state,
+                workspace.id,
+                LookupTargetType::DOMAIN,
+                &domain,
+                &breach_info,
+            );
+            (
+                StatusCode::OK,
+                Json(DSResponse {
+                    data: Some(DomainStats { breach_info }),
+                    err: None,
                 }),
-                err: None,
-            }),
-        ),
+            )
+        }
         Err(err) => {
+            meter.finish(&state, CacheTier::UPSTREAM, false);
             tracing::debug!("Error: {}", err);
             (
                 StatusCode::NOT_FOUND,
@@ -72,6 +115,22 @@ pub async fn get_stats_by_domain(
     };
 }
 
+// lookups of a verified asset are kept as its snapshot of the day
+fn spawn_lookup_snapshot(
+    state: &AppState,
+    workspace_id: Uuid,
+    asset_type: LookupTargetType,
+    target: &str,
+    info: &[BreachInfo],
+) {
+    let state = state.clone();
+    let target = String::from(target);
+    let info = info.to_vec();
+    tokio::spawn(async move {
+        record_lookup_snapshot(&state, workspace_id, asset_type, &target, &info).await;
+    });
+}
+
 fn build_breach_info(info: Vec<BreachName>) -> Vec<BreachInfo> {
     return info
         .iter()
@@ -100,22 +159,32 @@ fn build_breach_info(info: Vec<BreachName>) -> Vec<BreachInfo> {
 
 pub async fn get_analytics_by_email(
     State(state): State<AppState>,
-    Path(email): Path<String>,
+    Extension(workspace): Extension<MemberWorkspace>,
+    Extension(user): Extension<UserData>,
+    Extension(target): Extension<LookupTarget>,
 ) -> impl IntoResponse {
+    let email = target.value;
+    if let Err(e) = consume_daily_quota(&state, workspace.id, Quota::DAILY_LOOKUPS).await {
+        return e.into_json();
+    }
+    let meter = LookupMeter::start(&workspace, &user, UsageProvider::DSBREACH, &email);
     return match state.ds_provider.get_stats_by_email(email.as_str()).await {
-        Ok(result) => (
-            StatusCode::OK,
-            Json(DSResponse {
-                data: Some(
-                    EmailStats {
-                        breach_info: build_breach_info(result),
-                    }
-                    .convert_to_email_analytics(),
-                ),
-                err: None,
-            }),
-        ),
+        Ok(result) => {
+            meter.finish(&state, result.tier, true);
+            let breach_info = build_breach_info(result.data);
+            let trend = lookup_trend(&state, workspace.id, &email, &breach_info).await;
+            let mut analytics = EmailStats { breach_info }.convert_to_email_analytics(&trend);
+            analytics.branding = workspace_branding(&state, workspace.id).await.ok();
+            (
+                StatusCode::OK,
+                Json(DSResponse {
+                    data: Some(analytics),
+                    err: None,
+                }),
+            )
+        }
         Err(err) => {
+            meter.finish(&state, CacheTier::UPSTREAM, false);
             tracing::debug!("{}", err);
             (
                 StatusCode::NOT_FOUND,
@@ -130,23 +199,33 @@ pub async fn get_analytics_by_email(
 
 pub async fn get_dark_search_by_email(
     State(state): State<AppState>,
-    Path(email): Path<String>,
+    Extension(workspace): Extension<MemberWorkspace>,
+    Extension(user): Extension<UserData>,
+    Extension(target): Extension<LookupTarget>,
 ) -> impl IntoResponse {
+    let email = target.value;
+    if let Err(e) = consume_daily_quota(&state, workspace.id, Quota::DARK_SEARCH_PAGES).await {
+        return e.into_json();
+    }
+    let meter = LookupMeter::start(&workspace, &user, UsageProvider::DS_DARKENGINE, &email);
     match state
         .ds_darkengine_provider
         .get_stats_by_email(email.as_str())
         .await
     {
         Ok(result) => {
+            meter.finish(&state, result.tier, true);
+            push_dark_search_hits(&state, workspace.id, &email, &result.data);
             return (
                 StatusCode::OK,
                 Json(DSResponse {
-                    data: Some(result),
+                    data: Some(result.data),
                     err: None,
                 }),
             );
         }
         Err(err) => {
+            meter.finish(&state, CacheTier::UPSTREAM, false);
             tracing::debug!("{}", err);
             return (
                 StatusCode::NOT_FOUND,
@@ -161,23 +240,33 @@ pub async fn get_dark_search_by_email(
 
 pub async fn get_dark_search_by_domain(
     State(state): State<AppState>,
-    Path(domain): Path<String>,
+    Extension(workspace): Extension<MemberWorkspace>,
+    Extension(user): Extension<UserData>,
+    Extension(target): Extension<LookupTarget>,
 ) -> impl IntoResponse {
+    let domain = target.value;
+    if let Err(e) = consume_daily_quota(&state, workspace.id, Quota::DARK_SEARCH_PAGES).await {
+        return e.into_json();
+    }
+    let meter = LookupMeter::start(&workspace, &user, UsageProvider::DS_DARKENGINE, &domain);
     match state
         .ds_darkengine_provider
         .get_stats_by_domain(domain.as_str())
         .await
     {
         Ok(result) => {
+            meter.finish(&state, result.tier, true);
+            push_dark_search_hits(&state, workspace.id, &domain, &result.data);
             return (
                 StatusCode::OK,
                 Json(DSResponse {
-                    data: Some(result),
+                    data: Some(result.data),
                     err: None,
                 }),
             );
         }
         Err(err) => {
+            meter.finish(&state, CacheTier::UPSTREAM, false);
             tracing::debug!("{}", err);
             return (
                 StatusCode::NOT_FOUND,
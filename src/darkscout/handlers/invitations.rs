@@ -0,0 +1,477 @@
+use crate::darkscout::types::webhooks::WebhookEvent;
+use crate::darkscout::utils::webhooks::{emit_webhook_event, member_joined_data};
+use crate::darkscout::types::errors::{
+    self, ALREADY_A_MEMBER, INVALID_INVITATION_ROLE, INVALID_INVITE_LINK, INVITATION_NOT_FOUND,
+    INVITATION_RESEND_LIMITED, INVITE_LINK_DOMAIN_MISMATCH, INVITE_LINK_DOMAIN_NOT_VERIFIED,
+    INVITE_LINK_NOT_FOUND,
+};
+use crate::darkscout::types::invitations::{
+    new_workspace_invitation, AcceptedInvitation, ImportRowStatus, Invitation, InvitationStatus,
+    InvitationSummary, InvitationType, InviteLink, InviteLinkForm, InviteLinkResponse,
+    MemberImportQuery, MemberImportReport, NewInviteLink, RedeemInviteLinkForm,
+    DEFAULT_INVITE_LINK_TTL_HOURS, INVITE_LINK_TOKEN_LEN, MAX_INVITE_LINK_TTL_HOURS,
+    MAX_INVITE_LINK_USES,
+};
+use crate::darkscout::types::join_requests::email_domain;
+use crate::darkscout::types::member::{JoinOutcome, MemberRecord, MemberRole, MemberStatus};
+use crate::darkscout::types::plans::Quota;
+use crate::darkscout::types::store::{InvitationsStore, MemberStore, UserStore};
+use crate::darkscout::types::user::{MemberProfile, UserData};
+use crate::darkscout::types::workspace::{InvitationForm, MemberWorkspace, MemberWorkspaceDetails};
+use crate::darkscout::types::AppState;
+use crate::darkscout::utils::emails::workspace_branding;
+use crate::darkscout::utils::invitations::{
+    check_invitee, create_invitation_token, create_member_invitation, deliver_in_batches,
+    deliver_invitation, invitation_accept_link, invite_link_url, InviteeCheck,
+};
+use crate::darkscout::utils::auth::generate_token;
+use crate::darkscout::utils::member_import::parse_member_csv;
+use crate::darkscout::utils::quotas::{check_capacity, plan_limits};
+use crate::darkscout::utils::verified_assets::{covering_verified_domain, normalize_domain};
+use crate::darkscout::web::{json_error, json_resp};
+use crate::json_err;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::{Extension, Json};
+use chrono::Local;
+use sqlx::Error;
+use uuid::Uuid;
+
+pub async fn list_invitations(
+    State(state): State<AppState>,
+    Extension(member): Extension<MemberProfile>,
+    Extension(workspace): Extension<MemberWorkspace>,
+) -> impl IntoResponse {
+    if !member.role.is_admin() {
+        return json_err!(StatusCode::UNAUTHORIZED, "Unauthorized!");
+    }
+    return match state
+        .db
+        .invitation
+        .list_workspace_invitations(workspace.id)
+        .await
+    {
+        Ok(invitations) => json_resp::<Vec<InvitationSummary>>(None, invitations),
+        Err(e) => {
+            tracing::debug!("Failed to list invitations {}", e);
+            json_err!()
+        }
+    };
+}
+
+pub async fn resend_invitation(
+    State(state): State<AppState>,
+    Extension(member): Extension<MemberProfile>,
+    Extension(workspace): Extension<MemberWorkspace>,
+    Path(invitation_id): Path<Uuid>,
+) -> impl IntoResponse {
+    if !member.role.is_admin() {
+        return json_err!(StatusCode::UNAUTHORIZED, "Unauthorized!");
+    }
+    let invitation = match state.db.invitation.get_invitation(invitation_id).await {
+        Ok(i) => i,
+        Err(Error::RowNotFound) => return json_err!(StatusCode::NOT_FOUND, INVITATION_NOT_FOUND),
+        Err(_) => return json_err!(),
+    };
+    let resendable = matches!(
+        invitation.status,
+        InvitationStatus::QUEUED | InvitationStatus::INVITED | InvitationStatus::FAILED
+    );
+    if invitation.workspace != workspace.id
+        || !matches!(invitation.inv_type, InvitationType::MEMBER_INVITATION)
+        || !resendable
+    {
+        return json_err!(StatusCode::NOT_FOUND, INVITATION_NOT_FOUND);
+    }
+
+    // the old link may be close to expiring, every resend gets a new one
+    let Ok(branding) = workspace_branding(&state, workspace.id).await else {
+        return json_err!();
+    };
+    let Ok(token) =
+        create_invitation_token(invitation.id, workspace.id, &state.settings.jwt.jwt_secret)
+    else {
+        return json_err!();
+    };
+    // greet the invitee by the name stored when they were first invited
+    let first_name = match state.db.user.get_by_id(invitation.sent_to).await {
+        Ok(user) => user.first_name,
+        Err(e) => {
+            tracing::debug!("Failed to load the invitee {}", e);
+            None
+        }
+    };
+    let refreshed = new_workspace_invitation(
+        invitation.id,
+        invitation.created_by,
+        first_name,
+        branding.name,
+        invitation.email,
+        workspace.id,
+        invitation.sent_to,
+        invitation.role,
+        invitation_accept_link(&state.settings.config.app_url, &token),
+        invitation.transport,
+        invitation.phone,
+    );
+    let claimed = match state
+        .db
+        .invitation
+        .claim_invitation_resend(workspace.id, invitation.id, refreshed.msg)
+        .await
+    {
+        Ok(Some(i)) => i,
+        Ok(None) => return json_err!(StatusCode::TOO_MANY_REQUESTS, INVITATION_RESEND_LIMITED),
+        Err(e) => {
+            tracing::debug!("Failed to resend invitation {}", e);
+            return json_err!();
+        }
+    };
+    tokio::spawn(async move { deliver_invitation(&state, claimed).await });
+    json_resp::<&str>(None, "Invitation queued")
+}
+
+pub async fn revoke_invitation(
+    State(state): State<AppState>,
+    Extension(member): Extension<MemberProfile>,
+    Extension(workspace): Extension<MemberWorkspace>,
+    Path(invitation_id): Path<Uuid>,
+) -> impl IntoResponse {
+    if !member.role.is_admin() {
+        return json_err!(StatusCode::UNAUTHORIZED, "Unauthorized!");
+    }
+    return match state
+        .db
+        .invitation
+        .revoke_invitation(workspace.id, invitation_id)
+        .await
+    {
+        Ok(_) => json_resp::<&str>(None, "Invitation revoked"),
+        Err(Error::RowNotFound) => json_err!(StatusCode::NOT_FOUND, INVITATION_NOT_FOUND),
+        Err(e) => {
+            tracing::debug!("Failed to revoke invitation {}", e);
+            json_err!()
+        }
+    };
+}
+
+pub async fn import_members(
+    State(state): State<AppState>,
+    Extension(member): Extension<MemberProfile>,
+    Extension(workspace): Extension<MemberWorkspace>,
+    Query(query): Query<MemberImportQuery>,
+    body: String,
+) -> impl IntoResponse {
+    if !member.role.is_admin() {
+        return json_err!(StatusCode::UNAUTHORIZED, "Unauthorized!");
+    }
+    let dry_run = query.dry_run.unwrap_or(false);
+    let mut rows = match parse_member_csv(&body) {
+        Ok(rows) => rows,
+        Err(msg) => return json_err!(StatusCode::BAD_REQUEST, msg),
+    };
+    for row in rows.iter_mut() {
+        if row.status != ImportRowStatus::NEW_INVITE {
+            continue;
+        }
+        row.status = match check_invitee(&state, workspace.id, &row.email).await {
+            Ok(InviteeCheck::New) => ImportRowStatus::NEW_INVITE,
+            Ok(InviteeCheck::AlreadyMember) => ImportRowStatus::ALREADY_MEMBER,
+            Ok(InviteeCheck::AlreadyInvited) => ImportRowStatus::ALREADY_INVITED,
+            Err(e) => {
+                tracing::debug!("Failed to check invitee {}", e);
+                ImportRowStatus::FAILED
+            }
+        };
+    }
+    let adding = rows
+        .iter()
+        .filter(|r| r.status == ImportRowStatus::NEW_INVITE)
+        .count();
+    if let Err(e) = check_capacity(&state, workspace.id, Quota::MEMBERS, adding as i64).await {
+        return e.into_json();
+    }
+    if dry_run {
+        return json_resp::<MemberImportReport>(
+            None,
+            MemberImportReport {
+                dry_run,
+                total: rows.len(),
+                invited: 0,
+                rows,
+            },
+        );
+    }
+
+    let Ok(branding) = workspace_branding(&state, workspace.id).await else {
+        return json_err!();
+    };
+    let mut invitations: Vec<Invitation> = Vec::with_capacity(adding);
+    for row in rows.iter_mut() {
+        if row.status != ImportRowStatus::NEW_INVITE {
+            continue;
+        }
+        let invitee = InvitationForm {
+            email: row.email.clone(),
+            role: None,
+            first_name: row.first_name.clone(),
+            last_name: row.last_name.clone(),
+            phone: None,
+            transport: None,
+        };
+        let role = row.role.clone().unwrap_or(MemberRole::MEMBER);
+        match create_member_invitation(
+            &state,
+            member.user_id,
+            workspace.id,
+            &branding.name,
+            &invitee,
+            role,
+        )
+        .await
+        {
+            Ok(invitation) => invitations.push(invitation),
+            Err(e) => {
+                tracing::debug!("{}", e);
+                row.status = ImportRowStatus::FAILED;
+            }
+        }
+    }
+    let invited = invitations.len();
+    tokio::spawn(deliver_in_batches(state.clone(), invitations));
+    json_resp::<MemberImportReport>(
+        None,
+        MemberImportReport {
+            dry_run,
+            total: rows.len(),
+            invited,
+            rows,
+        },
+    )
+}
+
+pub async fn create_invite_link(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserData>,
+    Extension(member): Extension<MemberProfile>,
+    Extension(workspace): Extension<MemberWorkspace>,
+    Json(form): Json<InviteLinkForm>,
+) -> impl IntoResponse {
+    if !member.role.is_admin() {
+        return json_err!(StatusCode::UNAUTHORIZED, "Unauthorized!");
+    }
+    let role = form.role.unwrap_or(MemberRole::MEMBER);
+    if role == MemberRole::OWNER {
+        return json_err!(StatusCode::BAD_REQUEST, INVALID_INVITATION_ROLE);
+    }
+    let ttl_hours = form
+        .expires_in_hours
+        .unwrap_or(DEFAULT_INVITE_LINK_TTL_HOURS);
+    if !(1..=MAX_INVITE_LINK_TTL_HOURS).contains(&ttl_hours)
+        || !(1..=MAX_INVITE_LINK_USES).contains(&form.max_uses)
+    {
+        return json_err!(StatusCode::BAD_REQUEST, INVALID_INVITE_LINK);
+    }
+    let domain = match form.domain.filter(|d| !d.trim().is_empty()) {
+        Some(d) => match normalize_domain(&d) {
+            Some(d) => Some(d),
+            None => return json_err!(StatusCode::BAD_REQUEST, INVITE_LINK_DOMAIN_NOT_VERIFIED),
+        },
+        None => None,
+    };
+    if let Some(domain) = &domain {
+        match covering_verified_domain(&state, workspace.id, domain).await {
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                return json_err!(StatusCode::BAD_REQUEST, INVITE_LINK_DOMAIN_NOT_VERIFIED)
+            }
+            Err(e) => {
+                tracing::debug!("Failed to get verified domains {}", e);
+                return json_err!();
+            }
+        }
+    }
+    let link = NewInviteLink {
+        id: Uuid::new_v4(),
+        workspace: workspace.id,
+        token: generate_token(INVITE_LINK_TOKEN_LEN),
+        role,
+        domain,
+        max_uses: form.max_uses,
+        ttl_hours,
+        created_by: user.id,
+    };
+    return match state.db.invitation.create_invite_link(link).await {
+        Ok(link) => json_resp::<InviteLinkResponse>(
+            None,
+            InviteLinkResponse {
+                url: invite_link_url(&state.settings.config.app_url, &link.token),
+                link,
+            },
+        ),
+        Err(e) => {
+            tracing::debug!("Failed to create invite link {}", e);
+            json_err!()
+        }
+    };
+}
+
+pub async fn list_invite_links(
+    State(state): State<AppState>,
+    Extension(member): Extension<MemberProfile>,
+    Extension(workspace): Extension<MemberWorkspace>,
+) -> impl IntoResponse {
+    if !member.role.is_admin() {
+        return json_err!(StatusCode::UNAUTHORIZED, "Unauthorized!");
+    }
+    return match state.db.invitation.list_invite_links(workspace.id).await {
+        Ok(links) => json_resp::<Vec<InviteLinkResponse>>(
+            None,
+            links
+                .into_iter()
+                .map(|link| InviteLinkResponse {
+                    url: invite_link_url(&state.settings.config.app_url, &link.token),
+                    link,
+                })
+                .collect(),
+        ),
+        Err(e) => {
+            tracing::debug!("Failed to list invite links {}", e);
+            json_err!()
+        }
+    };
+}
+
+pub async fn revoke_invite_link(
+    State(state): State<AppState>,
+    Extension(member): Extension<MemberProfile>,
+    Extension(workspace): Extension<MemberWorkspace>,
+    Path(link_id): Path<Uuid>,
+) -> impl IntoResponse {
+    if !member.role.is_admin() {
+        return json_err!(StatusCode::UNAUTHORIZED, "Unauthorized!");
+    }
+    return match state
+        .db
+        .invitation
+        .revoke_invite_link(workspace.id, link_id)
+        .await
+    {
+        Ok(_) => json_resp::<&str>(None, "Invite link revoked"),
+        Err(Error::RowNotFound) => json_err!(StatusCode::NOT_FOUND, INVITE_LINK_NOT_FOUND),
+        Err(e) => {
+            tracing::debug!("Failed to revoke invite link {}", e);
+            json_err!()
+        }
+    };
+}
+
+// Checks whether the user may join through the link before a use is taken and
+// returns the member limit to hold the redemption to
+async fn can_redeem(
+    state: &AppState,
+    user: &UserData,
+    link: &InviteLink,
+) -> Result<Option<i64>, (StatusCode, &'static str)> {
+    let internal = |e: Error| {
+        tracing::debug!("Failed to check invite link redemption {}", e);
+        (StatusCode::INTERNAL_SERVER_ERROR, errors::INTERNAL_SERVER_ERROR)
+    };
+    match state.db.member.get_member_profile(user.id, link.workspace).await {
+        Ok(_) => return Err((StatusCode::CONFLICT, ALREADY_A_MEMBER)),
+        Err(Error::RowNotFound) => {}
+        Err(e) => return Err(internal(e)),
+    }
+    if let Some(domain) = &link.domain {
+        // the domain only means something once the user proved their email
+        let verified = state.db.user.is_user_verified(user.id).await.map_err(internal)?;
+        let user_domain = email_domain(&user.email).unwrap_or_default();
+        // hosts below the link's domain count when a verified domain covers them
+        let on_domain = user_domain == *domain
+            || (user_domain.ends_with(&format!(".{}", domain))
+                && covering_verified_domain(state, link.workspace, &user_domain)
+                    .await
+                    .map_err(internal)?
+                    .is_some());
+        if !verified || !on_domain {
+            return Err((StatusCode::FORBIDDEN, INVITE_LINK_DOMAIN_MISMATCH));
+        }
+    }
+    let (_, limits) = plan_limits(state, link.workspace).await.map_err(internal)?;
+    Ok(Quota::MEMBERS.limit(&limits))
+}
+
+pub async fn redeem_invite_link(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserData>,
+    Json(form): Json<RedeemInviteLinkForm>,
+) -> impl IntoResponse {
+    let link = match state.db.invitation.get_invite_link(&form.token).await {
+        Ok(l) => l,
+        Err(Error::RowNotFound) => return json_err!(StatusCode::BAD_REQUEST, INVALID_INVITE_LINK),
+        Err(e) => {
+            tracing::debug!("Failed to load invite link {}", e);
+            return json_err!();
+        }
+    };
+    let member_limit = match can_redeem(&state, &user, &link).await {
+        Ok(limit) => limit,
+        Err((code, msg)) => return json_err!(code, msg),
+    };
+    let new_member = MemberRecord {
+        id: Uuid::new_v4(),
+        joined_at: Local::now().to_utc(),
+        profile_picture: None,
+        status: Some(MemberStatus::VERIFIED),
+        role: Some(link.role.clone()),
+        invitation_token: None,
+        invitation_code: None,
+        user_id: user.id,
+        invited_by: link.created_by,
+        role_granted_by: Some(link.created_by),
+        workspace: link.workspace,
+    };
+    let details = MemberWorkspaceDetails {
+        id: Uuid::new_v4(),
+        workspace_order: 0,
+        is_default: false,
+        is_fav: false,
+        member_id: new_member.id,
+        workspace_id: link.workspace,
+        user_id: user.id,
+    };
+    match state
+        .db
+        .invitation
+        .redeem_invite_link(link.id, new_member.clone(), details, member_limit)
+        .await
+    {
+        Ok(JoinOutcome::JOINED) => {}
+        Ok(JoinOutcome::ALREADY_MEMBER) => return json_err!(StatusCode::CONFLICT, ALREADY_A_MEMBER),
+        Ok(JoinOutcome::NO_SEAT) => {
+            return json_err!(StatusCode::FORBIDDEN, Quota::MEMBERS.exceeded_msg())
+        }
+        Err(Error::RowNotFound) => return json_err!(StatusCode::BAD_REQUEST, INVALID_INVITE_LINK),
+        Err(e) => {
+            tracing::debug!("Failed to redeem invite link {}", e);
+            return json_err!();
+        }
+    };
+    emit_webhook_event(
+        &state.db,
+        link.workspace,
+        WebhookEvent::MEMBER_JOINED,
+        member_joined_data(&new_member),
+    )
+    .await;
+    json_resp::<AcceptedInvitation>(
+        None,
+        AcceptedInvitation {
+            workspace_id: link.workspace,
+            member_id: new_member.id,
+            user_id: user.id,
+            needs_password: false,
+        },
+    )
+}
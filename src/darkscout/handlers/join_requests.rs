@@ -0,0 +1,187 @@
+use crate::darkscout::types::webhooks::WebhookEvent;
+use crate::darkscout::utils::webhooks::{emit_webhook_event, member_joined_data};
+use crate::darkscout::types::errors::JOIN_REQUEST_NOT_FOUND;
+use crate::darkscout::types::join_requests::{
+    JoinPolicy, JoinPolicyForm, JoinRequestStatus, JoinRequestSummary,
+};
+use crate::darkscout::types::member::{MemberRecord, MemberRole, MemberStatus};
+use crate::darkscout::types::plans::Quota;
+use crate::darkscout::types::store::JoinRequestsStore;
+use crate::darkscout::types::user::{MemberProfile, UserData};
+use crate::darkscout::types::workspace::{MemberWorkspace, MemberWorkspaceDetails};
+use crate::darkscout::types::AppState;
+use crate::darkscout::utils::quotas::check_capacity;
+use crate::darkscout::web::{json_error, json_resp};
+use crate::json_err;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::{Extension, Json};
+use chrono::Local;
+use sqlx::Error;
+use uuid::Uuid;
+
+pub async fn get_join_policy(
+    State(state): State<AppState>,
+    Extension(member): Extension<MemberProfile>,
+    Extension(workspace): Extension<MemberWorkspace>,
+) -> impl IntoResponse {
+    if !member.role.is_admin() {
+        return json_err!(StatusCode::UNAUTHORIZED, "Unauthorized!");
+    }
+    return match state.db.join_requests.get_join_policy(workspace.id).await {
+        Ok(policy) => json_resp::<JoinPolicy>(None, policy),
+        Err(e) => {
+            tracing::debug!("Failed to get join policy {}", e);
+            json_err!()
+        }
+    };
+}
+
+pub async fn update_join_policy(
+    State(state): State<AppState>,
+    Extension(member): Extension<MemberProfile>,
+    Extension(workspace): Extension<MemberWorkspace>,
+    Json(form): Json<JoinPolicyForm>,
+) -> impl IntoResponse {
+    if !member.role.is_admin() {
+        return json_err!(StatusCode::UNAUTHORIZED, "Unauthorized!");
+    }
+    return match state
+        .db
+        .join_requests
+        .set_join_policy(workspace.id, form.policy)
+        .await
+    {
+        Ok(_) => json_resp::<JoinPolicy>(None, form.policy),
+        Err(e) => {
+            tracing::debug!("Failed to update join policy {}", e);
+            json_err!()
+        }
+    };
+}
+
+pub async fn list_join_requests(
+    State(state): State<AppState>,
+    Extension(member): Extension<MemberProfile>,
+    Extension(workspace): Extension<MemberWorkspace>,
+) -> impl IntoResponse {
+    if !member.role.is_admin() {
+        return json_err!(StatusCode::UNAUTHORIZED, "Unauthorized!");
+    }
+    return match state.db.join_requests.list_join_requests(workspace.id).await {
+        Ok(requests) => json_resp::<Vec<JoinRequestSummary>>(None, requests),
+        Err(e) => {
+            tracing::debug!("Failed to list join requests {}", e);
+            json_err!()
+        }
+    };
+}
+
+pub async fn approve_join_request(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserData>,
+    Extension(member): Extension<MemberProfile>,
+    Extension(workspace): Extension<MemberWorkspace>,
+    Path(request_id): Path<Uuid>,
+) -> impl IntoResponse {
+    if !member.role.is_admin() {
+        return json_err!(StatusCode::UNAUTHORIZED, "Unauthorized!");
+    }
+    let request = match state
+        .db
+        .join_requests
+        .get_join_request(workspace.id, request_id)
+        .await
+    {
+        Ok(r) if r.status == JoinRequestStatus::PENDING => r,
+        Ok(_) | Err(Error::RowNotFound) => {
+            return json_err!(StatusCode::NOT_FOUND, JOIN_REQUEST_NOT_FOUND)
+        }
+        Err(e) => {
+            tracing::debug!("Failed to load join request {}", e);
+            return json_err!();
+        }
+    };
+    if let Err(e) = check_capacity(&state, workspace.id, Quota::MEMBERS, 1).await {
+        return e.into_json();
+    }
+    let new_member = MemberRecord {
+        id: Uuid::new_v4(),
+        joined_at: Local::now().to_utc(),
+        profile_picture: None,
+        status: Some(MemberStatus::VERIFIED),
+        role: Some(MemberRole::MEMBER),
+        invitation_token: None,
+        invitation_code: None,
+        user_id: request.user_id,
+        invited_by: user.id,
+        role_granted_by: Some(user.id),
+        workspace: workspace.id,
+    };
+    let details = MemberWorkspaceDetails {
+        id: Uuid::new_v4(),
+        workspace_order: 0,
+        is_default: false,
+        is_fav: false,
+        member_id: new_member.id,
+        workspace_id: workspace.id,
+        user_id: request.user_id,
+    };
+    let joined = member_joined_data(&new_member);
+    return match state
+        .db
+        .join_requests
+        .approve_join_request(request.id, user.id, new_member, details)
+        .await
+    {
+        Ok(_) => {
+            emit_webhook_event(&state.db, workspace.id, WebhookEvent::MEMBER_JOINED, joined).await;
+            json_resp::<&str>(None, "Join request approved")
+        }
+        Err(Error::RowNotFound) => json_err!(StatusCode::NOT_FOUND, JOIN_REQUEST_NOT_FOUND),
+        Err(e) => {
+            tracing::debug!("Failed to approve join request {}", e);
+            json_err!()
+        }
+    };
+}
+
+pub async fn reject_join_request(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserData>,
+    Extension(member): Extension<MemberProfile>,
+    Extension(workspace): Extension<MemberWorkspace>,
+    Path(request_id): Path<Uuid>,
+) -> impl IntoResponse {
+    if !member.role.is_admin() {
+        return json_err!(StatusCode::UNAUTHORIZED, "Unauthorized!");
+    }
+    if let Err(e) = state
+        .db
+        .join_requests
+        .get_join_request(workspace.id, request_id)
+        .await
+    {
+        return match e {
+            Error::RowNotFound => json_err!(StatusCode::NOT_FOUND, JOIN_REQUEST_NOT_FOUND),
+            e => {
+                tracing::debug!("Failed to load join request {}", e);
+                json_err!()
+            }
+        };
+    }
+    return match state
+        .db
+        .join_requests
+        .reject_join_request(request_id, user.id)
+        .await
+    {
+        Ok(_) => json_resp::<&str>(None, "Join request rejected"),
+        Err(Error::RowNotFound) => json_err!(StatusCode::NOT_FOUND, JOIN_REQUEST_NOT_FOUND),
+        Err(e) => {
+            tracing::debug!("Failed to reject join request {}", e);
+            json_err!()
+        }
+    };
+}
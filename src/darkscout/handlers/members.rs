@@ -1,13 +1,258 @@
-use crate::darkscout::types::user::UserData;
-use crate::darkscout::types::workspace::MemberWorkspace;
-use crate::darkscout::types::AppState;
+use crate::darkscout::types::errors::{
+    self, CANNOT_MANAGE_MEMBER, INVALID_MEMBER_ROLE, INVALID_MEMBER_STATUS, MEMBER_NOT_FOUND,
+};
+use crate::darkscout::types::member::{
+    ChangeRoleForm, MemberData, MemberListQuery, MemberPage, MemberStatus,
+};
+use crate::darkscout::types::settings::EffectiveSetting;
+use crate::darkscout::types::store::{MemberStore, SettingsStore};
+use crate::darkscout::types::user::MemberProfile;
+use crate::darkscout::types::workspace::{MemberWorkspace, UpdateSettingsForm};
+use crate::darkscout::types::{AppState, DSResponse};
+use crate::darkscout::utils::settings::{effective_settings, validate_settings_form};
+use crate::darkscout::web::{json_error, json_resp};
+use crate::json_err;
+use axum::extract::{Path, Query};
+use axum::http::StatusCode;
 use axum::response::IntoResponse;
-use axum::{extract::State, Extension};
+use axum::{extract::State, Extension, Json};
+use sqlx::Error;
+use uuid::Uuid;
+
+// members manage their own settings, admins can manage anyone's
+fn can_manage_member(member: &MemberProfile, workspace: &MemberWorkspace, member_id: Uuid) -> bool {
+    member_id == workspace.member_id || member.role.is_admin()
+}
 
 pub async fn get_member_settings(
     State(state): State<AppState>,
-    Extension(user_data): Extension<UserData>,
+    Extension(member): Extension<MemberProfile>,
+    Extension(workspace): Extension<MemberWorkspace>,
+    Path(member_id): Path<Uuid>,
+) -> impl IntoResponse {
+    if !can_manage_member(&member, &workspace, member_id) {
+        return json_err!(StatusCode::UNAUTHORIZED, "Unauthorized!");
+    }
+    return match effective_settings(&state, workspace.id, Some(member_id)).await {
+        Ok(settings) => json_resp::<Vec<EffectiveSetting>>(None, settings),
+        Err(e) => {
+            tracing::debug!("Failed to load member settings {}", e);
+            json_err!()
+        }
+    };
+}
+
+pub async fn update_member_settings(
+    State(state): State<AppState>,
+    Extension(member): Extension<MemberProfile>,
+    Extension(workspace): Extension<MemberWorkspace>,
+    Path(member_id): Path<Uuid>,
+    Json(form): Json<UpdateSettingsForm>,
+) -> impl IntoResponse {
+    if !can_manage_member(&member, &workspace, member_id) {
+        return json_err!(StatusCode::UNAUTHORIZED, "Unauthorized!");
+    }
+    let settings = match validate_settings_form(form, true) {
+        Ok(settings) => settings,
+        Err(msg) => return json_err!(StatusCode::BAD_REQUEST, msg),
+    };
+    if let Err(e) = state
+        .db
+        .settings
+        .upsert_settings(workspace.id, Some(member_id), settings)
+        .await
+    {
+        tracing::debug!("Failed to update member settings {}", e);
+        return json_err!();
+    }
+    return match effective_settings(&state, workspace.id, Some(member_id)).await {
+        Ok(settings) => json_resp::<Vec<EffectiveSetting>>(None, settings),
+        Err(e) => {
+            tracing::debug!("Failed to load member settings {}", e);
+            json_err!()
+        }
+    };
+}
+
+pub async fn list_members(
+    State(state): State<AppState>,
+    Extension(member): Extension<MemberProfile>,
+    Extension(workspace): Extension<MemberWorkspace>,
+    Query(query): Query<MemberListQuery>,
+) -> impl IntoResponse {
+    if !member.role.is_admin() {
+        return json_err!(StatusCode::UNAUTHORIZED, "Unauthorized!");
+    }
+    let (page, per_page, offset) = query.bounds();
+    let search = query.search.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+    return match state
+        .db
+        .member
+        .list_workspace_members(
+            workspace.id,
+            search,
+            query.status,
+            per_page,
+            offset,
+        )
+        .await
+    {
+        Ok((members, total)) => json_resp::<MemberPage>(
+            None,
+            MemberPage {
+                members,
+                page,
+                per_page,
+                total,
+            },
+        ),
+        Err(e) => {
+            tracing::debug!("Failed to list members {}", e);
+            json_err!()
+        }
+    };
+}
+
+// Loads a member an admin is about to change. Admins never change their own
+// membership here and only an owner can change another owner.
+async fn managed_member(
+    state: &AppState,
+    admin: &MemberProfile,
+    workspace: &MemberWorkspace,
+    member_id: Uuid,
+) -> Result<MemberData, (StatusCode, &'static str)> {
+    if !admin.role.is_admin() {
+        return Err((StatusCode::UNAUTHORIZED, "Unauthorized!"));
+    }
+    if member_id == workspace.member_id {
+        return Err((StatusCode::FORBIDDEN, CANNOT_MANAGE_MEMBER));
+    }
+    let target = match state
+        .db
+        .member
+        .get_workspace_member(workspace.id, member_id)
+        .await
+    {
+        Ok(m) => m,
+        Err(Error::RowNotFound) => return Err((StatusCode::NOT_FOUND, MEMBER_NOT_FOUND)),
+        Err(e) => {
+            tracing::debug!("Failed to load member {}", e);
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, errors::INTERNAL_SERVER_ERROR));
+        }
+    };
+    if !admin.role.can_manage(&target.role) {
+        return Err((StatusCode::FORBIDDEN, CANNOT_MANAGE_MEMBER));
+    }
+    Ok(target)
+}
+
+pub async fn change_member_role(
+    State(state): State<AppState>,
+    Extension(member): Extension<MemberProfile>,
+    Extension(workspace): Extension<MemberWorkspace>,
+    Path(member_id): Path<Uuid>,
+    Json(form): Json<ChangeRoleForm>,
+) -> impl IntoResponse {
+    if let Err((code, msg)) = managed_member(&state, &member, &workspace, member_id).await {
+        return json_err!(code, msg);
+    }
+    if !form.role.is_assignable() {
+        return json_err!(StatusCode::BAD_REQUEST, INVALID_MEMBER_ROLE);
+    }
+    return match state
+        .db
+        .member
+        .update_member_role(workspace.id, member_id, form.role, member.user_id)
+        .await
+    {
+        Ok(_) => json_resp::<&str>(None, "Role updated"),
+        Err(Error::RowNotFound) => json_err!(StatusCode::NOT_FOUND, MEMBER_NOT_FOUND),
+        Err(e) => {
+            tracing::debug!("Failed to update member role {}", e);
+            json_err!()
+        }
+    };
+}
+
+async fn set_member_status(
+    state: &AppState,
+    member: &MemberProfile,
+    workspace: &MemberWorkspace,
+    member_id: Uuid,
+    from: MemberStatus,
+    to: MemberStatus,
+) -> (StatusCode, Json<DSResponse<&'static str>>) {
+    let target = match managed_member(state, member, workspace, member_id).await {
+        Ok(t) => t,
+        Err((code, msg)) => return json_err!(code, msg),
+    };
+    if target.status != from {
+        return json_err!(StatusCode::BAD_REQUEST, INVALID_MEMBER_STATUS);
+    }
+    return match state
+        .db
+        .member
+        .update_member_status(workspace.id, member_id, to)
+        .await
+    {
+        Ok(_) => json_resp::<&str>(None, "Member updated"),
+        Err(Error::RowNotFound) => json_err!(StatusCode::NOT_FOUND, MEMBER_NOT_FOUND),
+        Err(e) => {
+            tracing::debug!("Failed to update member status {}", e);
+            json_err!()
+        }
+    };
+}
+
+pub async fn suspend_member(
+    State(state): State<AppState>,
+    Extension(member): Extension<MemberProfile>,
+    Extension(workspace): Extension<MemberWorkspace>,
+    Path(member_id): Path<Uuid>,
+) -> impl IntoResponse {
+    set_member_status(
+        &state,
+        &member,
+        &workspace,
+        member_id,
+        MemberStatus::VERIFIED,
+        MemberStatus::SUSPENDED,
+    )
+    .await
+}
+
+pub async fn reactivate_member(
+    State(state): State<AppState>,
+    Extension(member): Extension<MemberProfile>,
+    Extension(workspace): Extension<MemberWorkspace>,
+    Path(member_id): Path<Uuid>,
+) -> impl IntoResponse {
+    set_member_status(
+        &state,
+        &member,
+        &workspace,
+        member_id,
+        MemberStatus::SUSPENDED,
+        MemberStatus::VERIFIED,
+    )
+    .await
+}
+
+pub async fn remove_member(
+    State(state): State<AppState>,
+    Extension(member): Extension<MemberProfile>,
     Extension(workspace): Extension<MemberWorkspace>,
+    Path(member_id): Path<Uuid>,
 ) -> impl IntoResponse {
-    String::from("Member settings endpoint")
+    if let Err((code, msg)) = managed_member(&state, &member, &workspace, member_id).await {
+        return json_err!(code, msg);
+    }
+    return match state.db.member.remove_member(workspace.id, member_id).await {
+        Ok(_) => json_resp::<&str>(None, "Member removed"),
+        Err(Error::RowNotFound) => json_err!(StatusCode::NOT_FOUND, MEMBER_NOT_FOUND),
+        Err(e) => {
+            tracing::debug!("Failed to remove member {}", e);
+            json_err!()
+        }
+    };
 }
@@ -5,3 +5,8 @@ pub mod users;
 pub mod dark_monitor;
 pub mod members;
 pub mod auth;
+pub mod admin;
+pub mod invitations;
+pub mod join_requests;
+pub mod alerts;
+pub mod webhooks;
@@ -1,26 +1,46 @@
+use crate::darkscout::types::webhooks::WebhookEvent;
+use crate::darkscout::utils::webhooks::{emit_webhook_event, member_joined_data};
 use crate::darkscout::types::auth::LoggedInUserClaims;
-use crate::darkscout::types::invitations::InvitationStatus;
+use crate::darkscout::types::errors::{
+    INVALID_PHONE, ONBOARDING_REQUIRED, PHONE_NOT_SET, VERIFICATION_CODE_EXPIRED, WEAK_PASSWORD,
+};
+use crate::darkscout::types::invitations::{InvitationStatus, TransportType};
+use crate::darkscout::types::join_requests::{
+    email_domain, sign_in_error, JoinPolicy, JoinRequest, JoinRequestStatus,
+};
+use crate::darkscout::types::plans::Quota;
 use crate::darkscout::types::member::{MemberData, MemberRecord, MemberRole, MemberStatus};
 use crate::darkscout::types::user::{
-    Auth, MemberProfile, PasswordResetForm, UserData, UserEditForm, UserSignupResponseData,
+    Auth, MemberProfile, PasswordResetForm, PhoneForm, UserData, UserEditForm,
+    UserSignupResponseData, VerifyPhoneForm, WorkspaceMembership,
+};
+use crate::darkscout::types::workspace::{
+    token_workspace, MemberWorkspace, MemberWorkspaceDetails, WorkspaceStatus,
 };
-use crate::darkscout::types::workspace::{MemberWorkspace, MemberWorkspaceDetails};
 use crate::darkscout::types::{errors, DSResponse};
 use crate::darkscout::types::{
     invitations::new_verification_code,
-    store::{InvitationsStore, MemberStore, UserStore, WorkspaceStore},
+    store::{
+        InvitationsStore, JoinRequestsStore, MemberStore, UserStore, VerifiedDomainsStore,
+        WorkspaceStore,
+    },
     user::{LoginForm, LoginResponse, UserRecord, UserSignupForm, VerifyUserForm},
     workspace::Workspace,
     AppState,
 };
-use crate::darkscout::utils::auth::{encrypt_password, verify_password};
+use crate::darkscout::utils::auth::{encrypt_password, is_password_allowed, verify_password};
 use crate::darkscout::utils::jwt::{encode_jwt, TokenClaims};
 use axum::{debug_handler, extract::State, Extension, Json};
 use chrono::{DateTime, Local, Utc};
 use jsonwebtoken::{EncodingKey, Header};
 use uuid::Uuid;
 
-use crate::darkscout::adapters::smtp_mailer::send_smtp_email;
+use crate::darkscout::utils::invitations::send_invitation_message;
+use crate::darkscout::utils::phone::normalize_phone;
+use crate::darkscout::utils::public_suffix::parent_domains;
+use crate::darkscout::utils::quotas::check_capacity;
+use crate::darkscout::utils::settings::session_timeout_hours;
+use crate::darkscout::types::settings::DEFAULT_SESSION_HOURS;
 use crate::darkscout::web::{json_error, json_resp};
 use crate::{json_err, unwrap_or_else_string};
 use axum::body::Body;
@@ -37,7 +57,7 @@ pub fn get_auth_tokens(
     user_record: UserRecord,
     default_workspace: Option<&MemberWorkspace>,
     jwt_secret: String,
-    role: MemberRole,
+    session_hours: i64,
 ) -> Option<Auth> {
     let workspace: Option<MemberWorkspace> = match default_workspace {
         None => None,
@@ -48,6 +68,7 @@ pub fn get_auth_tokens(
             created_by: w.created_by,
             workspace_order: w.workspace_order,
             is_default: w.is_default,
+            is_fav: w.is_fav,
             member_id: w.member_id,
         }),
     };
@@ -58,7 +79,7 @@ pub fn get_auth_tokens(
             first_name: user_record.first_name.clone(),
             last_name: user_record.last_name.clone(),
             email: user_record.email.clone(),
-            role: MemberRole::ADMIN,
+            role: d.role.clone(),
             verified_domains: None,
             created_at: d.created_at,
             updated_at: d.updated_at,
@@ -83,14 +104,14 @@ pub fn get_auth_tokens(
     let access_claim_data = TokenClaims {
         sub: "access-token".to_string(),
         iat: ts as usize,
-        exp: (ts + (60 * 60 * 24) as i64) as usize, // 24 hour in ms
+        exp: (ts + 60 * 60 * session_hours) as usize,
         payload: access_claim,
     };
 
     let auth_claim_data = TokenClaims {
         sub: "auth-token".to_string(),
         iat: ts as usize,
-        exp: (ts + (60 * 60 * 24) as i64) as usize, // 24 hour in ms
+        exp: (ts + 60 * 60 * session_hours) as usize,
         payload: user_profile.clone(),
     };
     let refresh_claim_data = TokenClaims {
@@ -117,47 +138,104 @@ pub async fn log_in(
     State(state): State<AppState>,
     Json(_form): Json<LoginForm>,
 ) -> impl IntoResponse {
-    let db = state.db;
-
-    let jwt_secret: String = state.settings.jwt.jwt_secret;
+    let db = &state.db;
 
     let Ok(user_record) = db.user.get_by_email(_form.email).await else {
         tracing::debug!("UserNotFound: Error while querying user.");
         return json_err!();
     };
-    let member_record = match db.member.get_member_profile(user_record.id).await {
-        Ok(d) => Some(d),
-        Err(e) => {
-            tracing::debug!("Failed to get member profile {}", e);
-            None
-        }
-    };
+    // invited users pick their password when onboarding from the invitation
     let Some(hashed_password) = user_record.clone().password else {
         tracing::debug!("NoPassword: No password provided.");
-        return json_err!();
+        return json_err!(StatusCode::FORBIDDEN, ONBOARDING_REQUIRED);
     };
 
-    let Ok(result) = verify_password(_form.password, hashed_password) else {
+    let Ok(true) = verify_password(_form.password, hashed_password) else {
         return json_err!(StatusCode::UNAUTHORIZED, "Password didn't match");
     };
 
-    let Ok(workspaces) = db
+    match join_request_error(&state, user_record.id).await {
+        Ok(None) => {}
+        Ok(Some(msg)) => return json_err!(StatusCode::FORBIDDEN, msg),
+        Err(e) => {
+            tracing::debug!("Failed to check the join request {}", e);
+            return json_err!();
+        }
+    }
+
+    return match login_response(&state, user_record, None).await {
+        Ok(data) => json_resp::<LoginResponse>(Some(StatusCode::OK), data),
+        Err(e) => {
+            tracing::debug!("Failed to build the login response {}", e);
+            json_err!()
+        }
+    };
+}
+
+// Users waiting on a join request have no workspace to sign in to yet
+async fn join_request_error(
+    state: &AppState,
+    user_id: Uuid,
+) -> Result<Option<&'static str>, Error> {
+    let workspaces = state.db.workspace.get_user_workspaces(user_id).await?;
+    if !workspaces.is_empty() {
+        return Ok(None);
+    }
+    let request = match state.db.join_requests.get_latest_join_request(user_id).await {
+        Ok(r) => Some(r.status),
+        Err(Error::RowNotFound) => None,
+        Err(e) => return Err(e),
+    };
+    Ok(sign_in_error(false, request))
+}
+
+// Builds the tokens and workspace listing returned after a login. Tokens are
+// issued for `workspace_id` when given, otherwise for the user's default.
+pub async fn login_response(
+    state: &AppState,
+    user_record: UserRecord,
+    workspace_id: Option<Uuid>,
+) -> Result<LoginResponse, Error> {
+    let db = &state.db;
+    let workspaces = db
         .workspace
         .get_user_workspaces(user_record.clone().id)
-        .await
-    else {
-        return json_err!();
-    };
+        .await?;
 
-    let default_workspace = workspaces.iter().filter(|x| x.is_default).map(|x| x).next();
+    let default_workspace = token_workspace(&workspaces, workspace_id);
 
+    let members = db.member.get_user_memberships(user_record.id).await?;
+    let mut memberships: Vec<WorkspaceMembership> = Vec::with_capacity(members.len());
+    for m in members.iter() {
+        let verified_domains = db
+            .verified_domains
+            .get_verified_domain_names(m.workspace)
+            .await?;
+        memberships.push(WorkspaceMembership {
+            workspace_id: m.workspace,
+            member_id: m.id,
+            role: m.role.clone(),
+            status: m.status.clone(),
+            verified_domains,
+        });
+    }
+
+    // the token only carries the member of the workspace it is issued for
+    let member_record: Option<MemberData> = default_workspace
+        .and_then(|w| members.into_iter().find(|m| m.id == w.member_id));
+
+    // the workspace decides how long its sessions last
+    let session_hours = match default_workspace {
+        Some(w) => session_timeout_hours(state, w.id).await,
+        None => DEFAULT_SESSION_HOURS,
+    };
     // let before = Instant::now();
     let auth = get_auth_tokens(
         &member_record,
         user_record.clone(),
         default_workspace,
-        jwt_secret,
-        MemberRole::ADMIN,
+        state.settings.jwt.jwt_secret.clone(),
+        session_hours,
     )
     .unwrap_or_else(|| Auth {
         access_token: "".to_string(),
@@ -165,8 +243,6 @@ pub async fn log_in(
         auth_token: "".to_string(),
     });
 
-    let verified_domains: Vec<String> = vec!["krispcall.com".to_string()];
-
     let member_profile = match member_record {
         Some(d) => Some(MemberProfile {
             id: d.id,
@@ -174,21 +250,23 @@ pub async fn log_in(
             first_name: user_record.first_name,
             last_name: user_record.last_name,
             email: user_record.email,
-            role: MemberRole::ADMIN,
+            role: d.role,
             created_at: d.created_at,
             updated_at: d.updated_at,
-            verified_domains: Some(verified_domains),
+            verified_domains: memberships
+                .iter()
+                .find(|m| m.member_id == d.id)
+                .map(|m| m.verified_domains.clone()),
         }),
         None => None,
     };
-    let data = LoginResponse {
+    // println!("Time it took to process request {:.2?} ", before.elapsed());
+    Ok(LoginResponse {
         workspaces,
+        memberships,
         member_profile: member_profile,
         auth,
-    };
-    // println!("Time it took to process request {:.2?} ", before.elapsed());
-    return json_resp::<LoginResponse>(Some(StatusCode::OK), data);
-    // return json_err!();
+    })
 }
 
 pub async fn verify_user(
@@ -205,29 +283,63 @@ pub async fn verify_user(
         }
     };
 
-    if invitation.details != _form.code {
+    match db.invitation.check_verification_code(invitation.id, &_form.code).await {
+        Ok(Some(true)) => {}
         // TODO: Provide correct message to frontend as well
-        return json_err!();
+        Ok(Some(false)) => return json_err!(),
+        Ok(None) => return json_err!(StatusCode::BAD_REQUEST, VERIFICATION_CODE_EXPIRED),
+        Err(e) => {
+            tracing::debug!("Failed to check the verification code {}", e);
+            return json_err!();
+        }
+    }
+
+    // a code sent by sms also proves the phone number given at sign up
+    if invitation.transport == TransportType::PHONE_CODE {
+        if let Some(phone) = invitation.phone.as_deref() {
+            if let Err(e) = db.user.verify_user_phone(_form.id, phone).await {
+                tracing::debug!("Failed to verify user phone {}", e);
+                return json_err!();
+            }
+        }
     }
 
     if let Err(e) = db
         .invitation
-        .update_invitation_status(InvitationStatus::DELETED, _form.id)
+        .update_invitation_status(InvitationStatus::DELETED, invitation.id)
         .await
     {
         tracing::debug!("UpdateInvitationFailed {}", e);
         return json_err!();
     };
 
+    if let Err(e) = db.user.verify_user(_form.id).await {
+        tracing::debug!("Failed to mark the user verified {}", e);
+        return json_err!();
+    }
+
     // TODO: Move this to task queue in the future
     tokio::spawn(async move {
+        // sign ups waiting for an admin only get their join request confirmed
+        match db
+            .join_requests
+            .confirm_join_request(invitation.workspace, _form.id)
+            .await
+        {
+            Ok(false) => {}
+            Ok(true) => return,
+            Err(e) => {
+                tracing::debug!("Failed to confirm join request {}", e);
+                return;
+            }
+        }
         let ts: DateTime<Utc> = Local::now().to_utc();
         let member = MemberRecord {
             id: Uuid::new_v4(),
             joined_at: ts,
             profile_picture: Some(String::from("profile://")),
             status: Some(MemberStatus::VERIFIED),
-            role: Some(MemberRole::ADMIN),
+            role: Some(invitation.role.clone()),
             invitation_token: Some(String::from("NA")),
             invitation_code: Some(0),
             user_id: _form.id,
@@ -235,21 +347,51 @@ pub async fn verify_user(
             role_granted_by: Some(_form.id),
             workspace: invitation.workspace,
         };
-        db.member
-            .create_new_member(member.clone())
-            .await
-            .unwrap_or_else(|e| tracing::debug!("{}", e));
+        match db.member.create_new_member(member.clone()).await {
+            Ok(_) => {
+                emit_webhook_event(
+                    &db,
+                    invitation.workspace,
+                    WebhookEvent::MEMBER_JOINED,
+                    member_joined_data(&member),
+                )
+                .await
+            }
+            Err(e) => tracing::debug!("{}", e),
+        }
 
         db.workspace
             .create_member_workspace_details(MemberWorkspaceDetails {
-                id: invitation.workspace,
+                id: Uuid::new_v4(),
                 workspace_order: 0,
                 is_default: true,
                 is_fav: false,
                 member_id: member.id,
+                workspace_id: invitation.workspace,
+                user_id: member.user_id,
             })
             .await
             .unwrap_or_else(|e| tracing::debug!("{}", e));
+
+        // the owner has proven their email, unlock the workspace unless an
+        // admin has already moved it to another status. Auto joined members
+        // never change the status of the workspace they join.
+        if !invitation.role.is_admin() {
+            return;
+        }
+        if let Ok(WorkspaceStatus::UNVERIFIED) =
+            db.workspace.get_workspace_status(invitation.workspace).await
+        {
+            db.workspace
+                .update_workspace_status(
+                    invitation.workspace,
+                    WorkspaceStatus::VERIFIED,
+                    String::from("Owner email verified"),
+                    _form.id,
+                )
+                .await
+                .unwrap_or_else(|e| tracing::debug!("{}", e));
+        }
     });
     return json_resp::<&str>(None, "Successfully verified user");
 }
@@ -258,7 +400,20 @@ pub async fn sign_up(
     State(state): State<AppState>,
     Json(_form): Json<UserSignupForm>,
 ) -> impl IntoResponse {
-    let db = state.db;
+    let db = state.db.clone();
+
+    let transport = _form.transport.unwrap_or(TransportType::EMAIL_CODE);
+    let valid_transport = match transport {
+        TransportType::EMAIL_CODE => true,
+        TransportType::PHONE_CODE => _form.phone.as_deref().and_then(normalize_phone).is_some(),
+        _ => false,
+    };
+    if !valid_transport {
+        return json_err!(StatusCode::BAD_REQUEST, INVALID_PHONE);
+    }
+    if !is_password_allowed(&_form.password) {
+        return json_err!(StatusCode::BAD_REQUEST, WEAK_PASSWORD);
+    }
 
     let Ok(user_record) = UserRecord::try_from(_form) else {
         return json_err!(
@@ -266,6 +421,39 @@ pub async fn sign_up(
             "Failed to create user. Try again letter."
         );
     };
+    // sign ups on a verified company domain go to that workspace instead of
+    // getting their own
+    let domain = email_domain(&user_record.email);
+    let joinable = match &domain {
+        Some(d) => match db
+            .join_requests
+            .find_domain_workspace(d, &parent_domains(d))
+            .await
+        {
+            Ok(w) => w,
+            Err(e) => {
+                tracing::debug!("Failed to look up the domain workspace {}", e);
+                return json_err!();
+            }
+        },
+        None => None,
+    };
+    let join_policy = match joinable {
+        Some((workspace_id, JoinPolicy::AUTO_JOIN)) => {
+            // a full workspace can still take requests for the admins to sort out
+            match check_capacity(&state, workspace_id, Quota::MEMBERS, 1).await {
+                Ok(_) => Some(JoinPolicy::AUTO_JOIN),
+                Err(_) => Some(JoinPolicy::REQUEST),
+            }
+        }
+        Some((_, policy)) => Some(policy),
+        None => None,
+    };
+    // only a code sent to the email proves the user owns an address on the domain
+    let transport = match join_policy {
+        Some(_) => TransportType::EMAIL_CODE,
+        None => transport,
+    };
     // Create user
     let Ok(id) = db.user.create_user(&user_record).await else {
         return json_err!(
@@ -273,25 +461,45 @@ pub async fn sign_up(
             "Failed to insert user. Please try again with another email."
         );
     };
-    // Create workspace
-    let workspace = match db
-        .workspace
-        .create_workspace(match Workspace::try_from(user_record.clone()) {
-            Ok(w) => w,
-            Err(e) => {
-                tracing::debug!("Failed to create user workspace {}", e);
-                return json_err!(
-                    StatusCode::BAD_REQUEST,
-                    "Failed to create workspace object."
-                );
+    let (workspace_id, role) = match (joinable, join_policy) {
+        (Some((workspace_id, _)), Some(JoinPolicy::AUTO_JOIN)) => (workspace_id, MemberRole::MEMBER),
+        (Some((workspace_id, _)), Some(_)) => {
+            let request = JoinRequest {
+                id: Uuid::new_v4(),
+                workspace: workspace_id,
+                user_id: id,
+                domain: domain.clone().unwrap_or_default(),
+                status: JoinRequestStatus::UNVERIFIED,
+            };
+            if let Err(e) = db.join_requests.create_join_request(request).await {
+                tracing::debug!("Failed to create join request {}", e);
+                return json_err!();
             }
-        })
-        .await
-    {
-        Ok(w) => w,
-        Err(e) => {
-            tracing::debug!("Failed to insert workspace into db {}", e);
-            return json_err!(StatusCode::BAD_REQUEST, "Failed to insert workspace");
+            (workspace_id, MemberRole::MEMBER)
+        }
+        _ => {
+            // Create workspace
+            let workspace = match db
+                .workspace
+                .create_workspace(match Workspace::try_from(user_record.clone()) {
+                    Ok(w) => w,
+                    Err(e) => {
+                        tracing::debug!("Failed to create user workspace {}", e);
+                        return json_err!(
+                            StatusCode::BAD_REQUEST,
+                            "Failed to create workspace object."
+                        );
+                    }
+                })
+                .await
+            {
+                Ok(w) => w,
+                Err(e) => {
+                    tracing::debug!("Failed to insert workspace into db {}", e);
+                    return json_err!(StatusCode::BAD_REQUEST, "Failed to insert workspace");
+                }
+            };
+            (workspace.id, MemberRole::ADMIN)
         }
     };
 
@@ -299,27 +507,21 @@ pub async fn sign_up(
     let verification_email = new_verification_code(
         user_record.id,
         user_record.id,
-        workspace.id,
+        workspace_id,
         user_record.email.clone(),
+        role,
+        transport,
+        user_record.phone.clone(),
     );
 
     if let Err(e) = db.invitation.create_new(verification_email.clone()).await {
         tracing::debug!("InvitationCreationError : {}", e);
         return json_err!(StatusCode::BAD_REQUEST, "Failed to send invitation");
     };
-    // send smtp email
-    let ve = verification_email.clone();
+    // send the code by email or sms
     tokio::spawn(async move {
-        if let Err(e) = send_smtp_email(
-            String::from("iamtheparzival@gmail.com"),
-            ve.email.clone(),
-            ve.msg,
-            ve.subject.as_str(),
-            &state.smtp_client,
-        )
-        .await
-        {
-            tracing::debug!("SMTPError : {}", e);
+        if let Err(e) = send_invitation_message(&state, &verification_email).await {
+            tracing::debug!("VerificationDeliveryError : {}", e);
         }
     });
 
@@ -330,6 +532,8 @@ pub async fn sign_up(
             last_name: user_record.last_name,
             id: user_record.id,
             email: user_record.email,
+            workspace_id,
+            join_policy,
         },
     )
 }
@@ -359,6 +563,9 @@ pub async fn change_password(
             Some("username/password mismatch"),
         );
     }
+    if !is_password_allowed(&reset_form.new_password) {
+        return json_err!(StatusCode::BAD_REQUEST, WEAK_PASSWORD);
+    }
     let Ok(new_password_hash) = encrypt_password(reset_form.new_password) else {
         return json_err!();
     };
@@ -392,6 +599,8 @@ pub async fn edit_user_profile(
         email: user.email,
         ip_str: user.ip_str,
         password: user.password,
+        phone: user.phone,
+        phone_verified: user.phone_verified,
         created_at: user.created_at,
         updated_at: user.updated_at,
     };
@@ -416,3 +625,182 @@ pub async fn edit_user_profile(
     }
     return json_resp::<&'static str>(None, "Success");
 }
+
+// Stores a new unverified phone number and sends a code to it by sms
+pub async fn update_user_phone(
+    Extension(user_data): Extension<UserData>,
+    Extension(workspace): Extension<MemberWorkspace>,
+    State(state): State<AppState>,
+    Json(form): Json<PhoneForm>,
+) -> impl IntoResponse {
+    let Some(phone) = normalize_phone(&form.phone) else {
+        return json_err!(StatusCode::BAD_REQUEST, INVALID_PHONE);
+    };
+    if let Err(e) = state
+        .db
+        .user
+        .update_user_phone(user_data.id, phone.clone())
+        .await
+    {
+        tracing::debug!("Failed to update user phone {}", e);
+        return json_err!();
+    }
+    let verification = new_verification_code(
+        user_data.id,
+        user_data.id,
+        workspace.id,
+        user_data.email,
+        MemberRole::MEMBER,
+        TransportType::PHONE_CODE,
+        Some(phone),
+    );
+    if let Err(e) = state.db.invitation.create_new(verification.clone()).await {
+        tracing::debug!("InvitationCreationError : {}", e);
+        return json_err!();
+    }
+    tokio::spawn(async move {
+        if let Err(e) = send_invitation_message(&state, &verification).await {
+            tracing::debug!("VerificationDeliveryError : {}", e);
+        }
+    });
+    return json_resp::<&str>(None, "Verification code sent");
+}
+
+pub async fn verify_user_phone(
+    Extension(user_data): Extension<UserData>,
+    State(state): State<AppState>,
+    Json(form): Json<VerifyPhoneForm>,
+) -> impl IntoResponse {
+    let db = state.db;
+    let user = match db.user.get_by_id(user_data.id).await {
+        Ok(u) => u,
+        Err(e) => {
+            tracing::debug!("Failed to load user {}", e);
+            return json_err!();
+        }
+    };
+    let Some(phone) = user.phone else {
+        return json_err!(StatusCode::BAD_REQUEST, PHONE_NOT_SET);
+    };
+    let verification = match db.invitation.get_phone_verification(user.id, &phone).await {
+        Ok(v) => v,
+        Err(Error::RowNotFound) => {
+            return json_err!(StatusCode::BAD_REQUEST, "Invalid verification code")
+        }
+        Err(e) => {
+            tracing::debug!("Failed to get the phone verification {}", e);
+            return json_err!();
+        }
+    };
+    match db.invitation.check_verification_code(verification.id, &form.code).await {
+        Ok(Some(true)) => {}
+        Ok(Some(false)) => return json_err!(StatusCode::BAD_REQUEST, "Invalid verification code"),
+        Ok(None) => return json_err!(StatusCode::BAD_REQUEST, VERIFICATION_CODE_EXPIRED),
+        Err(e) => {
+            tracing::debug!("Failed to check the verification code {}", e);
+            return json_err!();
+        }
+    }
+    if let Err(e) = db.user.verify_user_phone(user.id, &phone).await {
+        tracing::debug!("Failed to verify user phone {}", e);
+        return json_err!();
+    }
+    if let Err(e) = db
+        .invitation
+        .update_invitation_status(InvitationStatus::DELETED, verification.id)
+        .await
+    {
+        tracing::debug!("UpdateInvitationFailed {}", e);
+    }
+    return json_resp::<&str>(None, "Phone verified");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::darkscout::utils::jwt::decode_jwt;
+    use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+
+    const SECRET: &str = "test-secret";
+
+    fn user_record() -> UserRecord {
+        UserRecord {
+            id: Uuid::new_v4(),
+            first_name: Some(String::from("Ada")),
+            last_name: None,
+            username: String::from("ada@acme.com"),
+            email: String::from("ada@acme.com"),
+            ip_str: String::new(),
+            password: None,
+            phone: None,
+            phone_verified: false,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+
+    fn access_claims(auth: Auth) -> LoggedInUserClaims {
+        decode_jwt::<TokenClaims<LoggedInUserClaims>>(
+            auth.access_token,
+            &DecodingKey::from_secret(SECRET.as_bytes()),
+            &Validation::new(Algorithm::HS256),
+        )
+        .unwrap()
+        .claims
+        .payload
+    }
+
+    #[test]
+    fn test_token_carries_workspace_role() {
+        let user = user_record();
+        let workspace = MemberWorkspace {
+            id: Uuid::new_v4(),
+            title: None,
+            details: None,
+            created_by: Uuid::new_v4(),
+            workspace_order: 0,
+            is_default: true,
+            is_fav: false,
+            member_id: Uuid::new_v4(),
+        };
+        let member = MemberData {
+            id: workspace.member_id,
+            joined_at: Utc::now().naive_utc(),
+            profile_picture: String::new(),
+            status: MemberStatus::VERIFIED,
+            role: MemberRole::GUEST,
+            user_id: user.id,
+            invited_by: workspace.created_by,
+            workspace: workspace.id,
+            created_at: None,
+            updated_at: None,
+        };
+        let auth = get_auth_tokens(
+            &Some(member),
+            user,
+            Some(&workspace),
+            String::from(SECRET),
+            DEFAULT_SESSION_HOURS,
+        )
+        .unwrap();
+        let claims = access_claims(auth);
+        assert_eq!(claims.member.unwrap().role, MemberRole::GUEST);
+        assert_eq!(claims.workspace.unwrap().id, workspace.id);
+    }
+
+    #[test]
+    fn test_token_without_workspace() {
+        let auth = get_auth_tokens(
+            &None,
+            user_record(),
+            None,
+            String::from(SECRET),
+            DEFAULT_SESSION_HOURS,
+        )
+        .unwrap();
+        let claims = access_claims(auth);
+        assert!(claims.member.is_none());
+        assert!(claims.workspace.is_none());
+        assert_eq!(claims.user.unwrap().email, "ada@acme.com");
+    }
+}
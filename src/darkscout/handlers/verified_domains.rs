@@ -1,14 +1,25 @@
-use crate::darkscout::types::member::MemberRole;
+use crate::darkscout::types::errors;
+use crate::darkscout::types::plans::Quota;
 use crate::darkscout::types::store::{VerifiedDomainRepo, VerifiedDomainsStore};
 use crate::darkscout::types::user::{MemberProfile, UserData};
+use crate::darkscout::types::errors::{INVALID_VERIFIED_DOMAIN, VERIFIED_DOMAIN_NOT_FOUND};
 use crate::darkscout::types::verified_domains::{
     parse_verified_domains,
-    AddVerifiedDomainsForm
+    AddVerifiedDomainsForm, AddVerifiedDomainsResponse, DomainChallenge, DomainCoverageForm,
+    DomainVerificationResult,
+    VerifiedDomainPage, VerifiedDomainRecord,
 };
+use crate::darkscout::types::verified_emails::VerifiedAssetListQuery;
+use crate::darkscout::utils::verified_assets::{normalize_domain, split_new_values};
+use crate::darkscout::utils::domain_verification::{domain_challenge, find_domain_proof};
+use crate::darkscout::types::monitoring::BreachTimeline;
+use crate::darkscout::utils::monitoring::breach_timeline;
+use crate::darkscout::types::workspace::MemberWorkspace;
 use crate::darkscout::types::{AppState, DSResponse};
+use crate::darkscout::utils::quotas::check_capacity;
 use crate::darkscout::web::{json_error, json_resp};
 use crate::{json_err, unwrap_or_else_string};
-use axum::extract::State;
+use axum::extract::{Path, Query, State};
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use axum::{debug_handler, Extension, Json};
@@ -18,26 +29,263 @@ use uuid::Uuid;
 pub async fn add_verified_domains(
     State(state): State<AppState>,
     Extension(member): Extension<MemberProfile>,
+    Extension(workspace): Extension<MemberWorkspace>,
     Json(form): Json<AddVerifiedDomainsForm>,
 ) -> impl IntoResponse {
-    let permission = member.role == MemberRole::ADMIN;
+    let permission = member.role.is_admin();
     if !permission {
         return json_err!(StatusCode::UNAUTHORIZED, "Unauthorized!");
     };
+    let mut domains = Vec::with_capacity(form.domains.len());
+    for domain in &form.domains {
+        // a public suffix like co.uk can't be owned, let alone cover subdomains
+        match normalize_domain(domain) {
+            Some(d) => domains.push(d),
+            None => return json_err!(StatusCode::BAD_REQUEST, INVALID_VERIFIED_DOMAIN),
+        }
+    }
+    let existing = match state
+        .db
+        .verified_domains
+        .get_workspace_domain_names(workspace.id)
+        .await
+    {
+        Ok(d) => d,
+        Err(e) => {
+            tracing::debug!("Failed to load verified domains {}", e);
+            return json_err!();
+        }
+    };
+    let (domains, mut duplicates) = split_new_values(domains, &existing);
+    let adding = domains.len() as i64;
+    if let Err(e) = check_capacity(&state, workspace.id, Quota::VERIFIED_DOMAINS, adding).await {
+        return e.into_json();
+    }
     tracing::debug!("The member found is {:?}", member);
-    let parsed_domains = parse_verified_domains(form, member.id);
+    let parsed_domains = parse_verified_domains(domains, member.id, form.include_subdomains);
     let db = state.db;
-    match db
+    let added = match db
         .verified_domains
-        .add_verified_domains(parsed_domains.clone())
+        .add_verified_domains(workspace.id, parsed_domains.clone())
         .await
     {
-        Ok(_) => {}
+        Ok(added) => added,
         Err(e) => {
             tracing::debug!("Failed to add verified domains {}", e);
             return json_err!(StatusCode::INTERNAL_SERVER_ERROR, "Failed to add domains");
         }
+    };
+    duplicates.extend(
+        parsed_domains
+            .into_iter()
+            .filter(|p| !added.iter().any(|a| a.id == p.id))
+            .map(|p| p.domain),
+    );
+    return json_resp(
+        Some(StatusCode::OK),
+        AddVerifiedDomainsResponse { added, duplicates },
+    );
+}
+
+pub async fn list_verified_domains(
+    State(state): State<AppState>,
+    Extension(member): Extension<MemberProfile>,
+    Extension(workspace): Extension<MemberWorkspace>,
+    Query(query): Query<VerifiedAssetListQuery>,
+) -> impl IntoResponse {
+    if !member.role.is_admin() {
+        return json_err!(StatusCode::UNAUTHORIZED, "Unauthorized!");
+    }
+    let (page, per_page) = query.pagination();
+    return match state
+        .db
+        .verified_domains
+        .list_workspace_domains(workspace.id, query.verified, per_page, (page - 1) * per_page)
+        .await
+    {
+        Ok((domains, total)) => json_resp::<VerifiedDomainPage>(
+            None,
+            VerifiedDomainPage {
+                domains,
+                page,
+                per_page,
+                total,
+            },
+        ),
+        Err(e) => {
+            tracing::debug!("Failed to list verified domains {}", e);
+            json_err!()
+        }
+    };
+}
+
+pub async fn update_domain_subdomains(
+    State(state): State<AppState>,
+    Extension(member): Extension<MemberProfile>,
+    Extension(workspace): Extension<MemberWorkspace>,
+    Path(domain_id): Path<Uuid>,
+    Json(form): Json<DomainCoverageForm>,
+) -> impl IntoResponse {
+    if !member.role.is_admin() {
+        return json_err!(StatusCode::UNAUTHORIZED, "Unauthorized!");
+    }
+    return match state
+        .db
+        .verified_domains
+        .set_domain_subdomains(workspace.id, domain_id, form.include_subdomains)
+        .await
+    {
+        Ok(_) => json_resp::<&str>(None, "Domain updated"),
+        Err(Error::RowNotFound) => json_err!(StatusCode::NOT_FOUND, VERIFIED_DOMAIN_NOT_FOUND),
+        Err(e) => {
+            tracing::debug!("Failed to update domain subdomains {}", e);
+            json_err!()
+        }
+    };
+}
+
+pub async fn delete_verified_domain(
+    State(state): State<AppState>,
+    Extension(member): Extension<MemberProfile>,
+    Extension(workspace): Extension<MemberWorkspace>,
+    Path(domain_id): Path<Uuid>,
+) -> impl IntoResponse {
+    if !member.role.is_admin() {
+        return json_err!(StatusCode::UNAUTHORIZED, "Unauthorized!");
     }
-    return json_resp(Some(StatusCode::OK), parsed_domains);
-    // return json_err!(StatusCode::NOT_FOUND, "Not implemented!");
+    return match state
+        .db
+        .verified_domains
+        .delete_workspace_domain(workspace.id, domain_id)
+        .await
+    {
+        Ok(_) => json_resp::<&str>(None, "Domain deleted"),
+        Err(Error::RowNotFound) => json_err!(StatusCode::NOT_FOUND, VERIFIED_DOMAIN_NOT_FOUND),
+        Err(e) => {
+            tracing::debug!("Failed to delete verified domain {}", e);
+            json_err!()
+        }
+    };
+}
+
+async fn workspace_domain(
+    state: &AppState,
+    member: &MemberProfile,
+    workspace: &MemberWorkspace,
+    domain_id: Uuid,
+) -> Result<VerifiedDomainRecord, (StatusCode, &'static str)> {
+    if !member.role.is_admin() {
+        return Err((StatusCode::UNAUTHORIZED, "Unauthorized!"));
+    }
+    match state
+        .db
+        .verified_domains
+        .get_workspace_domain(workspace.id, domain_id)
+        .await
+    {
+        Ok(d) => Ok(d),
+        Err(Error::RowNotFound) => Err((StatusCode::NOT_FOUND, VERIFIED_DOMAIN_NOT_FOUND)),
+        Err(e) => {
+            tracing::debug!("Failed to load verified domain {}", e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, errors::INTERNAL_SERVER_ERROR))
+        }
+    }
+}
+
+pub async fn get_domain_challenge(
+    State(state): State<AppState>,
+    Extension(member): Extension<MemberProfile>,
+    Extension(workspace): Extension<MemberWorkspace>,
+    Path(domain_id): Path<Uuid>,
+) -> impl IntoResponse {
+    return match workspace_domain(&state, &member, &workspace, domain_id).await {
+        Ok(d) => json_resp::<DomainChallenge>(
+            None,
+            domain_challenge(&d.domain, &d.verification_token),
+        ),
+        Err((code, msg)) => json_err!(code, msg),
+    };
+}
+
+pub async fn get_verified_domain_timeline(
+    State(state): State<AppState>,
+    Extension(member): Extension<MemberProfile>,
+    Extension(workspace): Extension<MemberWorkspace>,
+    Path(domain_id): Path<Uuid>,
+) -> impl IntoResponse {
+    let domain = match workspace_domain(&state, &member, &workspace, domain_id).await {
+        Ok(d) => d,
+        Err((code, msg)) => return json_err!(code, msg),
+    };
+    return match breach_timeline(&state, workspace.id, domain.id, domain.domain).await {
+        Ok(timeline) => json_resp::<BreachTimeline>(None, timeline),
+        Err((code, msg)) => json_err!(code, msg),
+    };
+}
+
+async fn check_domain_proof(
+    state: &AppState,
+    domain: VerifiedDomainRecord,
+    revoke_on_failure: bool,
+) -> (StatusCode, Json<DSResponse<DomainVerificationResult>>) {
+    let check = find_domain_proof(
+        &state.dns_resolver,
+        &state.http_fetcher,
+        &domain.domain,
+        &domain.verification_token,
+    )
+    .await;
+    let update = match check.method {
+        Some(method) => Some((true, Some(method))),
+        None if revoke_on_failure && domain.is_verified => Some((false, None)),
+        None => None,
+    };
+    if let Some((is_verified, method)) = update {
+        if let Err(e) = state
+            .db
+            .verified_domains
+            .update_verified_domain(domain.workspace, domain.id, is_verified, method)
+            .await
+        {
+            tracing::debug!("Failed to update domain verification {}", e);
+            return json_err!();
+        }
+    }
+    json_resp::<DomainVerificationResult>(
+        None,
+        DomainVerificationResult {
+            verified: check.method.is_some(),
+            method: check.method,
+            errors: check.errors,
+        },
+    )
+}
+
+// Looks for the proof right away, the background re-check keeps it honest
+pub async fn verify_domain(
+    State(state): State<AppState>,
+    Extension(member): Extension<MemberProfile>,
+    Extension(workspace): Extension<MemberWorkspace>,
+    Path(domain_id): Path<Uuid>,
+) -> impl IntoResponse {
+    let domain = match workspace_domain(&state, &member, &workspace, domain_id).await {
+        Ok(d) => d,
+        Err((code, msg)) => return json_err!(code, msg),
+    };
+    check_domain_proof(&state, domain, false).await
+}
+
+// Same as the background re-check but immediate, a verified domain whose
+// proof is gone loses its verification right away
+pub async fn reverify_domain(
+    State(state): State<AppState>,
+    Extension(member): Extension<MemberProfile>,
+    Extension(workspace): Extension<MemberWorkspace>,
+    Path(domain_id): Path<Uuid>,
+) -> impl IntoResponse {
+    let domain = match workspace_domain(&state, &member, &workspace, domain_id).await {
+        Ok(d) => d,
+        Err((code, msg)) => return json_err!(code, msg),
+    };
+    check_domain_proof(&state, domain, true).await
 }
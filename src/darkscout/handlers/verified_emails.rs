@@ -1,11 +1,27 @@
-use crate::darkscout::types::member::MemberRole;
+use crate::darkscout::types::plans::Quota;
 use crate::darkscout::types::store::{VerifiedEmailsRepo, VerifiedEmailsStore};
 use crate::darkscout::types::user::{MemberProfile, UserData};
-use crate::darkscout::types::verified_emails::{parse_verified_emails, AddVerifiedEmailsForm};
+use crate::darkscout::types::errors::{
+    self, CONFIRMATION_RESEND_LIMITED, INVALID_EMAIL_CONFIRMATION, INVALID_VERIFIED_EMAIL,
+    VERIFIED_EMAIL_NOT_FOUND,
+};
+use crate::darkscout::types::verified_emails::{
+    parse_verified_emails, AddVerifiedEmailsForm, AddVerifiedEmailsResponse,
+    ConfirmVerifiedEmailForm, VerifiedAssetListQuery, VerifiedEmailPage, VerifiedEmailSummary,
+    CONFIRM_TOKEN_LEN,
+};
+use crate::darkscout::utils::verified_assets::{normalize_email, split_new_values};
+use crate::darkscout::utils::auth::generate_token;
+use crate::darkscout::utils::verified_emails::send_email_confirmation;
+use crate::darkscout::types::monitoring::BreachTimeline;
+use crate::darkscout::utils::monitoring::breach_timeline;
+use crate::darkscout::types::workspace::MemberWorkspace;
 use crate::darkscout::types::{AppState, DSResponse};
+use crate::darkscout::utils::quotas::check_capacity;
+use crate::darkscout::utils::settings::fold_plus_addresses;
 use crate::darkscout::web::{json_error, json_resp};
 use crate::{json_err, unwrap_or_else_string};
-use axum::extract::State;
+use axum::extract::{Path, Query, State};
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use axum::{debug_handler, Extension, Json};
@@ -15,31 +31,265 @@ use uuid::Uuid;
 pub async fn add_verified_emails(
     State(state): State<AppState>,
     Extension(member): Extension<MemberProfile>,
+    Extension(workspace): Extension<MemberWorkspace>,
     Json(form): Json<AddVerifiedEmailsForm>,
 ) -> impl IntoResponse {
-    let permission = member.role == MemberRole::ADMIN;
+    let permission = member.role.is_admin();
     if !permission {
         return json_err!(StatusCode::UNAUTHORIZED, "Unauthorized!");
     };
+    // stores john+news@acme.com as john@acme.com when the workspace asks for it
+    let fold_plus = match fold_plus_addresses(&state, workspace.id).await {
+        Ok(f) => f,
+        Err(e) => {
+            tracing::debug!("Failed to load the workspace settings {}", e);
+            return json_err!();
+        }
+    };
+    let mut emails = Vec::with_capacity(form.emails.len());
+    for email in &form.emails {
+        match normalize_email(email, fold_plus) {
+            Some(e) => emails.push(e),
+            None => return json_err!(StatusCode::BAD_REQUEST, INVALID_VERIFIED_EMAIL),
+        }
+    }
+    let existing = match state
+        .db
+        .verified_emails
+        .get_workspace_email_names(workspace.id)
+        .await
+    {
+        Ok(e) => e,
+        Err(e) => {
+            tracing::debug!("Failed to load verified emails {}", e);
+            return json_err!();
+        }
+    };
+    let (emails, mut duplicates) = split_new_values(emails, &existing);
+    let adding = emails.len() as i64;
+    if let Err(e) = check_capacity(&state, workspace.id, Quota::VERIFIED_EMAILS, adding).await {
+        return e.into_json();
+    }
     tracing::debug!("The member found is {:?}", member);
-    let parsed_emails = parse_verified_emails(form, member.id);
-    let db = state.db;
-    match db
+    let parsed_emails = parse_verified_emails(emails, member.id);
+    let db = state.db.clone();
+    let added = match db
         .verified_emails
-        .add_verified_emails(parsed_emails.clone())
+        .add_verified_emails(workspace.id, parsed_emails.clone())
         .await
     {
-        Ok(_) => {
+        Ok(added) => {
             // Send the verification email in the background
+            let emails = added.clone();
             tokio::spawn(async move {
-                println!("This is background task");
+                for email in emails {
+                    send_email_confirmation(&state, workspace.id, email).await;
+                }
             });
+            added
         }
         Err(e) => {
             tracing::debug!("Failed to add verified emails {}", e);
             return json_err!(StatusCode::INTERNAL_SERVER_ERROR, "Failed to add emails");
         }
+    };
+    // added by a concurrent request in the meantime
+    duplicates.extend(
+        parsed_emails
+            .into_iter()
+            .filter(|p| !added.iter().any(|a| a.id == p.id))
+            .map(|p| p.email),
+    );
+    return json_resp(
+        Some(StatusCode::OK),
+        AddVerifiedEmailsResponse { added, duplicates },
+    );
+}
+
+pub async fn list_verified_emails(
+    State(state): State<AppState>,
+    Extension(member): Extension<MemberProfile>,
+    Extension(workspace): Extension<MemberWorkspace>,
+    Query(query): Query<VerifiedAssetListQuery>,
+) -> impl IntoResponse {
+    if !member.role.is_admin() {
+        return json_err!(StatusCode::UNAUTHORIZED, "Unauthorized!");
+    }
+    let (page, per_page) = query.pagination();
+    return match state
+        .db
+        .verified_emails
+        .list_workspace_emails(workspace.id, query.verified, per_page, (page - 1) * per_page)
+        .await
+    {
+        Ok((emails, total)) => json_resp::<VerifiedEmailPage>(
+            None,
+            VerifiedEmailPage {
+                emails,
+                page,
+                per_page,
+                total,
+            },
+        ),
+        Err(e) => {
+            tracing::debug!("Failed to list verified emails {}", e);
+            json_err!()
+        }
+    };
+}
+
+async fn workspace_email(
+    state: &AppState,
+    member: &MemberProfile,
+    workspace: &MemberWorkspace,
+    email_id: Uuid,
+) -> Result<VerifiedEmailSummary, (StatusCode, &'static str)> {
+    if !member.role.is_admin() {
+        return Err((StatusCode::UNAUTHORIZED, "Unauthorized!"));
+    }
+    match state
+        .db
+        .verified_emails
+        .get_workspace_email(workspace.id, email_id)
+        .await
+    {
+        Ok(e) => Ok(e),
+        Err(Error::RowNotFound) => Err((StatusCode::NOT_FOUND, VERIFIED_EMAIL_NOT_FOUND)),
+        Err(e) => {
+            tracing::debug!("Failed to load verified email {}", e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, errors::INTERNAL_SERVER_ERROR))
+        }
+    }
+}
+
+pub async fn resend_email_confirmation(
+    State(state): State<AppState>,
+    Extension(member): Extension<MemberProfile>,
+    Extension(workspace): Extension<MemberWorkspace>,
+    Path(email_id): Path<Uuid>,
+) -> impl IntoResponse {
+    match workspace_email(&state, &member, &workspace, email_id).await {
+        Ok(e) if !e.is_verified => {}
+        Ok(_) => return json_err!(StatusCode::NOT_FOUND, VERIFIED_EMAIL_NOT_FOUND),
+        Err((code, msg)) => return json_err!(code, msg),
+    }
+    let claimed = match state
+        .db
+        .verified_emails
+        .claim_confirmation_resend(workspace.id, email_id, generate_token(CONFIRM_TOKEN_LEN))
+        .await
+    {
+        Ok(Some(email)) => email,
+        Ok(None) => return json_err!(StatusCode::TOO_MANY_REQUESTS, CONFIRMATION_RESEND_LIMITED),
+        Err(e) => {
+            tracing::debug!("Failed to resend the email confirmation {}", e);
+            return json_err!();
+        }
+    };
+    tokio::spawn(async move { send_email_confirmation(&state, workspace.id, claimed).await });
+    json_resp::<&str>(None, "Confirmation queued")
+}
+
+// Puts a confirmed address back to unconfirmed and sends it a new link
+pub async fn reverify_verified_email(
+    State(state): State<AppState>,
+    Extension(member): Extension<MemberProfile>,
+    Extension(workspace): Extension<MemberWorkspace>,
+    Path(email_id): Path<Uuid>,
+) -> impl IntoResponse {
+    if let Err((code, msg)) = workspace_email(&state, &member, &workspace, email_id).await {
+        return json_err!(code, msg);
+    }
+    let claimed = match state
+        .db
+        .verified_emails
+        .restart_email_confirmation(workspace.id, email_id, generate_token(CONFIRM_TOKEN_LEN))
+        .await
+    {
+        Ok(Some(email)) => email,
+        Ok(None) => return json_err!(StatusCode::TOO_MANY_REQUESTS, CONFIRMATION_RESEND_LIMITED),
+        Err(e) => {
+            tracing::debug!("Failed to restart the email confirmation {}", e);
+            return json_err!();
+        }
+    };
+    tokio::spawn(async move { send_email_confirmation(&state, workspace.id, claimed).await });
+    json_resp::<&str>(None, "Confirmation queued")
+}
+
+pub async fn get_verified_email_timeline(
+    State(state): State<AppState>,
+    Extension(member): Extension<MemberProfile>,
+    Extension(workspace): Extension<MemberWorkspace>,
+    Path(email_id): Path<Uuid>,
+) -> impl IntoResponse {
+    let email = match workspace_email(&state, &member, &workspace, email_id).await {
+        Ok(e) => e,
+        Err((code, msg)) => return json_err!(code, msg),
+    };
+    return match breach_timeline(&state, workspace.id, email.id, email.email).await {
+        Ok(timeline) => json_resp::<BreachTimeline>(None, timeline),
+        Err((code, msg)) => json_err!(code, msg),
+    };
+}
+
+pub async fn delete_verified_email(
+    State(state): State<AppState>,
+    Extension(member): Extension<MemberProfile>,
+    Extension(workspace): Extension<MemberWorkspace>,
+    Path(email_id): Path<Uuid>,
+) -> impl IntoResponse {
+    if !member.role.is_admin() {
+        return json_err!(StatusCode::UNAUTHORIZED, "Unauthorized!");
     }
-    return json_resp(Some(StatusCode::OK), parsed_emails);
-    // return json_err!(StatusCode::NOT_FOUND, "Not implemented!");
+    return match state
+        .db
+        .verified_emails
+        .delete_workspace_email(workspace.id, email_id)
+        .await
+    {
+        Ok(_) => json_resp::<&str>(None, "Email deleted"),
+        Err(Error::RowNotFound) => json_err!(StatusCode::NOT_FOUND, VERIFIED_EMAIL_NOT_FOUND),
+        Err(e) => {
+            tracing::debug!("Failed to delete verified email {}", e);
+            json_err!()
+        }
+    };
+}
+
+// Opened from the link sent to the address, no login needed
+pub async fn confirm_verified_email(
+    State(state): State<AppState>,
+    Json(form): Json<ConfirmVerifiedEmailForm>,
+) -> impl IntoResponse {
+    let confirmation = match state
+        .db
+        .verified_emails
+        .get_email_confirmation(&form.token)
+        .await
+    {
+        Ok(c) if !c.expired && !c.is_verified => c,
+        Ok(_) | Err(Error::RowNotFound) => {
+            return json_err!(StatusCode::BAD_REQUEST, INVALID_EMAIL_CONFIRMATION)
+        }
+        Err(e) => {
+            tracing::debug!("Failed to load the email confirmation {}", e);
+            return json_err!();
+        }
+    };
+    return match state
+        .db
+        .verified_emails
+        .update_verified_email(confirmation.workspace, confirmation.id, true)
+        .await
+    {
+        Ok(_) => json_resp::<&str>(None, "Email confirmed"),
+        Err(Error::RowNotFound) => {
+            json_err!(StatusCode::BAD_REQUEST, INVALID_EMAIL_CONFIRMATION)
+        }
+        Err(e) => {
+            tracing::debug!("Failed to confirm verified email {}", e);
+            json_err!()
+        }
+    };
 }
@@ -0,0 +1,256 @@
+use crate::darkscout::types::errors::{
+    INVALID_WEBHOOK_URL, WEBHOOK_DELIVERY_NOT_FOUND, WEBHOOK_EVENTS_REQUIRED, WEBHOOK_NOT_FOUND,
+};
+use crate::darkscout::types::store::WebhooksStore;
+use crate::darkscout::types::user::MemberProfile;
+use crate::darkscout::types::webhooks::{
+    event_names, CreateWebhookForm, DeliveryListQuery, UpdateWebhookForm, WebhookDelivery,
+    WebhookDeliveryPage, WebhookEndpoint, WebhookEndpointSecret, SECRET_GRACE_HOURS,
+};
+use crate::darkscout::types::workspace::MemberWorkspace;
+use crate::darkscout::types::AppState;
+use crate::darkscout::utils::webhooks::{
+    check_webhook_host, generate_webhook_secret, normalize_webhook_url,
+};
+use crate::darkscout::web::{json_error, json_resp};
+use crate::json_err;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::{Extension, Json};
+use sqlx::Error;
+use uuid::Uuid;
+
+pub async fn list_webhooks(
+    State(state): State<AppState>,
+    Extension(member): Extension<MemberProfile>,
+    Extension(workspace): Extension<MemberWorkspace>,
+) -> impl IntoResponse {
+    if !member.role.is_admin() {
+        return json_err!(StatusCode::UNAUTHORIZED, "Unauthorized!");
+    }
+    return match state.db.webhooks.list_webhook_endpoints(workspace.id).await {
+        Ok(endpoints) => json_resp::<Vec<WebhookEndpoint>>(None, endpoints),
+        Err(e) => {
+            tracing::debug!("Failed to list webhooks {}", e);
+            json_err!()
+        }
+    };
+}
+
+// the secret is only shown here and when it is rotated
+pub async fn create_webhook(
+    State(state): State<AppState>,
+    Extension(member): Extension<MemberProfile>,
+    Extension(workspace): Extension<MemberWorkspace>,
+    Json(form): Json<CreateWebhookForm>,
+) -> impl IntoResponse {
+    if !member.role.is_admin() {
+        return json_err!(StatusCode::UNAUTHORIZED, "Unauthorized!");
+    }
+    let allow_loopback = state.settings.webhooks.allow_loopback;
+    let Some(url) = normalize_webhook_url(&form.url, allow_loopback) else {
+        return json_err!(StatusCode::BAD_REQUEST, INVALID_WEBHOOK_URL);
+    };
+    if !check_webhook_host(&url, allow_loopback).await {
+        return json_err!(StatusCode::BAD_REQUEST, INVALID_WEBHOOK_URL);
+    }
+    if form.events.is_empty() {
+        return json_err!(StatusCode::BAD_REQUEST, WEBHOOK_EVENTS_REQUIRED);
+    }
+    let endpoint = WebhookEndpoint {
+        id: Uuid::new_v4(),
+        workspace: workspace.id,
+        url,
+        events: event_names(&form.events),
+        secret: generate_webhook_secret(),
+        previous_secret: None,
+        previous_secret_expires_at: None,
+        enabled: true,
+        created_by: Some(member.id),
+        created_at: None,
+        updated_at: None,
+    };
+    if let Err(e) = state.db.webhooks.create_webhook_endpoint(&endpoint).await {
+        tracing::debug!("Failed to create webhook {}", e);
+        return json_err!();
+    }
+    json_resp::<WebhookEndpointSecret>(
+        Some(StatusCode::CREATED),
+        WebhookEndpointSecret {
+            secret: endpoint.secret.clone(),
+            endpoint,
+        },
+    )
+}
+
+pub async fn update_webhook(
+    State(state): State<AppState>,
+    Extension(member): Extension<MemberProfile>,
+    Extension(workspace): Extension<MemberWorkspace>,
+    Path(webhook_id): Path<Uuid>,
+    Json(form): Json<UpdateWebhookForm>,
+) -> impl IntoResponse {
+    if !member.role.is_admin() {
+        return json_err!(StatusCode::UNAUTHORIZED, "Unauthorized!");
+    }
+    let allow_loopback = state.settings.webhooks.allow_loopback;
+    let url = match form
+        .url
+        .as_deref()
+        .map(|u| normalize_webhook_url(u, allow_loopback))
+    {
+        Some(None) => return json_err!(StatusCode::BAD_REQUEST, INVALID_WEBHOOK_URL),
+        Some(url) => url,
+        None => None,
+    };
+    if let Some(url) = url.as_deref() {
+        if !check_webhook_host(url, allow_loopback).await {
+            return json_err!(StatusCode::BAD_REQUEST, INVALID_WEBHOOK_URL);
+        }
+    }
+    if form.events.as_ref().is_some_and(|e| e.is_empty()) {
+        return json_err!(StatusCode::BAD_REQUEST, WEBHOOK_EVENTS_REQUIRED);
+    }
+    return match state
+        .db
+        .webhooks
+        .update_webhook_endpoint(
+            workspace.id,
+            webhook_id,
+            url,
+            form.events.as_deref().map(event_names),
+            form.enabled,
+        )
+        .await
+    {
+        Ok(endpoint) => json_resp::<WebhookEndpoint>(None, endpoint),
+        Err(Error::RowNotFound) => json_err!(StatusCode::NOT_FOUND, WEBHOOK_NOT_FOUND),
+        Err(e) => {
+            tracing::debug!("Failed to update webhook {}", e);
+            json_err!()
+        }
+    };
+}
+
+pub async fn delete_webhook(
+    State(state): State<AppState>,
+    Extension(member): Extension<MemberProfile>,
+    Extension(workspace): Extension<MemberWorkspace>,
+    Path(webhook_id): Path<Uuid>,
+) -> impl IntoResponse {
+    if !member.role.is_admin() {
+        return json_err!(StatusCode::UNAUTHORIZED, "Unauthorized!");
+    }
+    return match state
+        .db
+        .webhooks
+        .delete_webhook_endpoint(workspace.id, webhook_id)
+        .await
+    {
+        Ok(_) => json_resp::<&str>(None, "Webhook deleted"),
+        Err(Error::RowNotFound) => json_err!(StatusCode::NOT_FOUND, WEBHOOK_NOT_FOUND),
+        Err(e) => {
+            tracing::debug!("Failed to delete webhook {}", e);
+            json_err!()
+        }
+    };
+}
+
+// the old secret keeps signing next to the new one for SECRET_GRACE_HOURS
+pub async fn rotate_webhook_secret(
+    State(state): State<AppState>,
+    Extension(member): Extension<MemberProfile>,
+    Extension(workspace): Extension<MemberWorkspace>,
+    Path(webhook_id): Path<Uuid>,
+) -> impl IntoResponse {
+    if !member.role.is_admin() {
+        return json_err!(StatusCode::UNAUTHORIZED, "Unauthorized!");
+    }
+    return match state
+        .db
+        .webhooks
+        .rotate_webhook_secret(
+            workspace.id,
+            webhook_id,
+            generate_webhook_secret(),
+            SECRET_GRACE_HOURS,
+        )
+        .await
+    {
+        Ok(endpoint) => json_resp::<WebhookEndpointSecret>(
+            None,
+            WebhookEndpointSecret {
+                secret: endpoint.secret.clone(),
+                endpoint,
+            },
+        ),
+        Err(Error::RowNotFound) => json_err!(StatusCode::NOT_FOUND, WEBHOOK_NOT_FOUND),
+        Err(e) => {
+            tracing::debug!("Failed to rotate webhook secret {}", e);
+            json_err!()
+        }
+    };
+}
+
+pub async fn list_webhook_deliveries(
+    State(state): State<AppState>,
+    Extension(member): Extension<MemberProfile>,
+    Extension(workspace): Extension<MemberWorkspace>,
+    Query(query): Query<DeliveryListQuery>,
+) -> impl IntoResponse {
+    if !member.role.is_admin() {
+        return json_err!(StatusCode::UNAUTHORIZED, "Unauthorized!");
+    }
+    let (page, per_page) = query.pagination();
+    return match state
+        .db
+        .webhooks
+        .list_webhook_deliveries(
+            workspace.id,
+            query.endpoint,
+            query.status,
+            per_page,
+            (page - 1) * per_page,
+        )
+        .await
+    {
+        Ok((deliveries, total)) => json_resp::<WebhookDeliveryPage>(
+            None,
+            WebhookDeliveryPage {
+                deliveries,
+                page,
+                per_page,
+                total,
+            },
+        ),
+        Err(e) => {
+            tracing::debug!("Failed to list webhook deliveries {}", e);
+            json_err!()
+        }
+    };
+}
+
+pub async fn replay_webhook_delivery(
+    State(state): State<AppState>,
+    Extension(member): Extension<MemberProfile>,
+    Extension(workspace): Extension<MemberWorkspace>,
+    Path(delivery_id): Path<Uuid>,
+) -> impl IntoResponse {
+    if !member.role.is_admin() {
+        return json_err!(StatusCode::UNAUTHORIZED, "Unauthorized!");
+    }
+    return match state
+        .db
+        .webhooks
+        .replay_webhook_delivery(workspace.id, delivery_id)
+        .await
+    {
+        Ok(delivery) => json_resp::<WebhookDelivery>(None, delivery),
+        Err(Error::RowNotFound) => json_err!(StatusCode::NOT_FOUND, WEBHOOK_DELIVERY_NOT_FOUND),
+        Err(e) => {
+            tracing::debug!("Failed to replay webhook delivery {}", e);
+            json_err!()
+        }
+    };
+}
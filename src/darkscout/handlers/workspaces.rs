@@ -1,51 +1,185 @@
-use crate::darkscout::types::invitations::InvitationType;
-use crate::darkscout::types::member::MemberRole;
-use crate::darkscout::types::store::{InvitationsStore, MemberStore, UserStore, WorkspaceStore};
-use crate::darkscout::types::user::{MemberProfile, UserData, UserRecord};
+use crate::darkscout::types::webhooks::WebhookEvent;
+use crate::darkscout::utils::webhooks::{emit_webhook_event, member_joined_data};
+use crate::darkscout::types::errors::{
+    self, ALREADY_ONBOARDED, NAME_REQUIRED, WEAK_PASSWORD,
+    ALREADY_A_MEMBER, ALREADY_INVITED, INVALID_INVITATION, INVALID_INVITATION_ROLE,
+    INVALID_INVITATION_TRANSPORT,
+    DEFAULT_WORKSPACE_REQUIRED, INVALID_COUNTRY, INVALID_DISPLAY_PICTURE, WORKSPACE_NOT_FOUND,
+};
+use crate::darkscout::types::invitations::{
+    AcceptedInvitation, Invitation, InvitationStatus, InvitationType, TransportType,
+};
+use crate::darkscout::types::member::{JoinOutcome, MemberRecord, MemberRole, MemberStatus};
+use crate::darkscout::types::plans::{Quota, WorkspaceUsage};
+use crate::darkscout::types::settings::EffectiveSetting;
+use crate::darkscout::types::store::{
+    InvitationsStore, SettingsStore, UserStore, WorkspaceStore,
+};
+use crate::darkscout::types::user::{LoginResponse, MemberProfile, OnboardingForm, UserData};
 use crate::darkscout::types::workspace::{
-    AcceptMemberInvitationForm, EditWorkspaceForm, InvitationResultStatus,
-    MemberInvitationResponseData, MemberInvitationsForm, MemberWorkspace, Workspace, WorkspaceForm,
+    AcceptMemberInvitationForm, EditWorkspaceForm, InvitationForm, InvitationResultStatus,
+    MemberInvitationResponseData, MemberInvitationsForm, MemberWorkspace, ReorderWorkspacesForm,
+    MemberWorkspaceDetails, UpdateSettingsForm, Workspace, WorkspaceForm,
 };
 use crate::darkscout::types::{AppState, DSResponse};
+use crate::darkscout::handlers::users::login_response;
+use crate::darkscout::utils::auth::{encrypt_password, is_password_allowed};
+use crate::darkscout::utils::emails::workspace_branding;
+use crate::darkscout::utils::invitations::{
+    check_invitee, create_member_invitation, decode_invitation_token, deliver_invitation,
+    InviteeCheck,
+};
+use crate::darkscout::utils::countries::find_country;
+use crate::darkscout::utils::phone::normalize_phone;
+use crate::darkscout::utils::quotas::{check_capacity, plan_limits, workspace_usage};
+use crate::darkscout::utils::settings::{effective_settings, validate_settings_form};
 use crate::darkscout::web::{json_error, json_resp};
 use crate::{json_err, unwrap_or_else_string};
 use axum::extract::State;
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use axum::{debug_handler, Extension, Json};
+use chrono::Local;
+use reqwest::Url;
 use sqlx::Error;
 use std::cmp::PartialEq;
 use uuid::Uuid;
 
+// Checks the country fields against ISO 3166 and returns the
+// (alpha-2 code, country name) pair to store
+fn resolve_country(
+    country_code: Option<String>,
+    country: Option<String>,
+) -> Result<Option<(String, String)>, &'static str> {
+    let by_code = match country_code.filter(|c| !c.trim().is_empty()) {
+        Some(code) => Some(find_country(&code).ok_or(INVALID_COUNTRY)?),
+        None => None,
+    };
+    let by_name = match country.filter(|c| !c.trim().is_empty()) {
+        Some(name) => Some(find_country(&name).ok_or(INVALID_COUNTRY)?),
+        None => None,
+    };
+    return match (by_code, by_name) {
+        (Some(a), Some(b)) if a[0] != b[0] => Err(INVALID_COUNTRY),
+        (Some(c), _) | (None, Some(c)) => Ok(Some((c[0].to_string(), c[2].to_string()))),
+        (None, None) => Ok(None),
+    };
+}
+
+fn validate_display_picture(url: Option<String>) -> Result<Option<String>, &'static str> {
+    let Some(url) = url.map(|u| u.trim().to_string()).filter(|u| !u.is_empty()) else {
+        return Ok(None);
+    };
+    match Url::parse(&url) {
+        Ok(parsed) if (parsed.scheme() == "https" || parsed.scheme() == "http") && url.len() <= 255 => {
+            Ok(Some(url))
+        }
+        _ => Err(INVALID_DISPLAY_PICTURE),
+    }
+}
+
 pub async fn edit_workspace(
     State(state): State<AppState>,
     Extension(user_data): Extension<UserData>,
+    Extension(member): Extension<MemberProfile>,
     Extension(workspace): Extension<MemberWorkspace>,
     Json(form): Json<EditWorkspaceForm>,
 ) -> impl IntoResponse {
-    let Ok(w) = state
-        .db
-        .workspace
-        .get_member_workspace(workspace.member_id)
-        .await
-    else {
+    let edits_workspace = form.edits_workspace();
+    if edits_workspace {
+        let permission: bool = workspace.id == form.id && member.role.is_admin();
+        if !permission {
+            return json_err!(StatusCode::UNAUTHORIZED, "Unauthorized");
+        }
+    }
+
+    let Ok(workspaces) = state.db.workspace.get_user_workspaces(user_data.id).await else {
         return json_err!();
     };
-    let permission: bool = workspace.id != form.id;
-    let wks = w.clone();
-    if !permission {
-        return json_err!(StatusCode::UNAUTHORIZED, "Unauthorized");
-    }
-    let record = Workspace {
-        id: form.id,
-        title: unwrap_or_else_string!(form.title, w.title),
-        details: unwrap_or_else_string!(form.details, w.details),
-        created_by: w.created_by,
+    let Some(current) = workspaces.iter().find(|w| w.id == form.id) else {
+        return json_err!(StatusCode::NOT_FOUND, WORKSPACE_NOT_FOUND);
     };
-    let Ok(_) = state.db.workspace.update_workspace(record).await else {
+    if form.is_default == Some(false) && current.is_default {
+        return json_err!(StatusCode::BAD_REQUEST, DEFAULT_WORKSPACE_REQUIRED);
+    }
+
+    let Ok(w) = state.db.workspace.get_workspace(form.id).await else {
         return json_err!();
     };
-    return json_resp::<Workspace>(None, wks);
+    let record = if edits_workspace {
+        let display_picture = match validate_display_picture(form.display_picture) {
+            Ok(p) => p.or(w.display_picture),
+            Err(msg) => return json_err!(StatusCode::BAD_REQUEST, msg),
+        };
+        let (country_code, country) = match resolve_country(form.country_code, form.country) {
+            Ok(Some((code, name))) => (Some(code), Some(name)),
+            Ok(None) => (w.country_code, w.country),
+            Err(msg) => return json_err!(StatusCode::BAD_REQUEST, msg),
+        };
+        let record = Workspace {
+            id: form.id,
+            title: unwrap_or_else_string!(form.title, w.title),
+            details: unwrap_or_else_string!(form.details, w.details),
+            created_by: w.created_by,
+            display_picture,
+            country_code,
+            country,
+        };
+        let Ok(_) = state.db.workspace.update_workspace(record.clone()).await else {
+            return json_err!();
+        };
+        record
+    } else {
+        w
+    };
+
+    if form.order.is_some() || form.is_fav.is_some() {
+        if let Err(e) = state
+            .db
+            .workspace
+            .update_member_workspace_details(user_data.id, form.id, form.order, form.is_fav)
+            .await
+        {
+            tracing::debug!("Failed to update member workspace details {}", e);
+            return json_err!();
+        }
+    }
+    if form.is_default == Some(true) && !current.is_default {
+        if let Err(e) = state
+            .db
+            .workspace
+            .set_default_workspace(user_data.id, form.id)
+            .await
+        {
+            tracing::debug!("Failed to set default workspace {}", e);
+            return json_err!();
+        }
+    }
+    return json_resp::<Workspace>(None, record);
+}
+
+pub async fn reorder_workspaces(
+    State(state): State<AppState>,
+    Extension(user_data): Extension<UserData>,
+    Json(form): Json<ReorderWorkspacesForm>,
+) -> impl IntoResponse {
+    match state
+        .db
+        .workspace
+        .reorder_user_workspaces(user_data.id, form.workspaces)
+        .await
+    {
+        Ok(_) => {}
+        Err(Error::RowNotFound) => return json_err!(StatusCode::NOT_FOUND, WORKSPACE_NOT_FOUND),
+        Err(e) => {
+            tracing::debug!("Failed to reorder workspaces {}", e);
+            return json_err!();
+        }
+    };
+    match state.db.workspace.get_user_workspaces(user_data.id).await {
+        Ok(workspaces) => json_resp::<Vec<MemberWorkspace>>(None, workspaces),
+        Err(_) => json_err!(),
+    }
 }
 
 pub async fn create_workspace(
@@ -53,11 +187,23 @@ pub async fn create_workspace(
     Extension(user_data): Extension<UserData>,
     Json(form): Json<WorkspaceForm>,
 ) -> impl IntoResponse {
+    let display_picture = match validate_display_picture(form.display_picture) {
+        Ok(p) => p,
+        Err(msg) => return json_err!(StatusCode::BAD_REQUEST, msg),
+    };
+    let (country_code, country) = match resolve_country(form.country_code, form.country) {
+        Ok(Some((code, name))) => (Some(code), Some(name)),
+        Ok(None) => (None, None),
+        Err(msg) => return json_err!(StatusCode::BAD_REQUEST, msg),
+    };
     let record = Workspace {
         id: Uuid::new_v4(),
         title: form.title,
         details: form.details,
         created_by: user_data.id,
+        display_picture,
+        country_code,
+        country,
     };
     return match state.db.workspace.create_workspace(record).await {
         Ok(w) => json_resp(None, Some(w)),
@@ -68,60 +214,364 @@ pub async fn create_workspace(
     };
 }
 
-fn create_user_records(data: MemberInvitationsForm) -> Vec<UserRecord> {
-    data.invitations
-        .iter()
-        .map(|r| UserRecord {
-            id: Uuid::new_v4(),
-            first_name: r.first_name.clone(),
-            created_at: None,
-            updated_at: None,
-            last_name: r.last_name.clone(),
-            username: r.email.clone(),
-            email: r.email.clone(),
-            ip_str: "".to_string(),
-            password: None,
-        })
-        .collect()
+pub async fn get_workspace_usage(
+    State(state): State<AppState>,
+    Extension(workspace): Extension<MemberWorkspace>,
+) -> impl IntoResponse {
+    return match workspace_usage(&state, workspace.id).await {
+        Ok(usage) => json_resp::<WorkspaceUsage>(None, usage),
+        Err(e) => {
+            tracing::debug!("Failed to load workspace usage {}", e);
+            json_err!()
+        }
+    };
+}
+
+pub async fn get_workspace_settings(
+    State(state): State<AppState>,
+    Extension(workspace): Extension<MemberWorkspace>,
+) -> impl IntoResponse {
+    return match effective_settings(&state, workspace.id, None).await {
+        Ok(settings) => json_resp::<Vec<EffectiveSetting>>(None, settings),
+        Err(e) => {
+            tracing::debug!("Failed to load workspace settings {}", e);
+            json_err!()
+        }
+    };
+}
+
+pub async fn update_workspace_settings(
+    State(state): State<AppState>,
+    Extension(member): Extension<MemberProfile>,
+    Extension(workspace): Extension<MemberWorkspace>,
+    Json(form): Json<UpdateSettingsForm>,
+) -> impl IntoResponse {
+    if !member.role.is_admin() {
+        return json_err!(StatusCode::UNAUTHORIZED, "Unauthorized!");
+    }
+    let settings = match validate_settings_form(form, false) {
+        Ok(settings) => settings,
+        Err(msg) => return json_err!(StatusCode::BAD_REQUEST, msg),
+    };
+    if let Err(e) = state
+        .db
+        .settings
+        .upsert_settings(workspace.id, None, settings)
+        .await
+    {
+        tracing::debug!("Failed to update workspace settings {}", e);
+        return json_err!();
+    }
+    return match effective_settings(&state, workspace.id, None).await {
+        Ok(settings) => json_resp::<Vec<EffectiveSetting>>(None, settings),
+        Err(e) => {
+            tracing::debug!("Failed to load workspace settings {}", e);
+            json_err!()
+        }
+    };
+}
+
+fn invitation_result(
+    email: String,
+    status: InvitationResultStatus,
+    reason: Option<&str>,
+) -> MemberInvitationResponseData {
+    MemberInvitationResponseData {
+        email,
+        status,
+        reason: reason.map(String::from),
+    }
 }
 
 pub async fn invite_new_members(
     State(state): State<AppState>,
     Extension(member): Extension<MemberProfile>,
+    Extension(workspace): Extension<MemberWorkspace>,
     Json(form): Json<MemberInvitationsForm>,
 ) -> impl IntoResponse {
-    let permission = member.role == MemberRole::ADMIN;
-    if !permission {
+    if !member.role.is_admin() {
         return json_err!(StatusCode::UNAUTHORIZED, "Unauthorized!");
     };
-    let user_records = create_user_records(form);
+    let adding = form.invitations.len() as i64;
+    if let Err(e) = check_capacity(&state, workspace.id, Quota::MEMBERS, adding).await {
+        return e.into_json();
+    }
+    let Ok(branding) = workspace_branding(&state, workspace.id).await else {
+        return json_err!();
+    };
     let mut resp_data: Vec<MemberInvitationResponseData> = vec![];
-    for user in user_records {
-        let result = match state.db.user.create_user(&user).await {
-            Ok(_) => InvitationResultStatus::SUCCESS,
-            Err(_) => InvitationResultStatus::FAILURE,
+    for invitee in form.invitations.iter() {
+        let email = invitee.email.trim().to_lowercase();
+        let role = invitee.role.clone().unwrap_or(MemberRole::MEMBER);
+        // ownership is never handed out through an invitation
+        if role == MemberRole::OWNER {
+            resp_data.push(invitation_result(
+                email,
+                InvitationResultStatus::FAILURE,
+                Some(INVALID_INVITATION_ROLE),
+            ));
+            continue;
+        }
+        let reason = match check_invitee(&state, workspace.id, &email).await {
+            Ok(InviteeCheck::New) => None,
+            Ok(InviteeCheck::AlreadyMember) => Some(ALREADY_A_MEMBER),
+            Ok(InviteeCheck::AlreadyInvited) => Some(ALREADY_INVITED),
+            Err(e) => {
+                tracing::debug!("Failed to check invitee {}", e);
+                resp_data.push(invitation_result(email, InvitationResultStatus::FAILURE, None));
+                continue;
+            }
+        };
+        if reason.is_some() {
+            resp_data.push(invitation_result(email, InvitationResultStatus::FAILURE, reason));
+            continue;
+        }
+        let transport = invitee.transport.unwrap_or(TransportType::EMAIL_LINK);
+        let phone = invitee.phone.as_deref().and_then(normalize_phone);
+        let valid_transport = match transport {
+            TransportType::EMAIL_LINK => true,
+            TransportType::PHONE_LINK => phone.is_some(),
+            _ => false,
         };
-        resp_data.push(MemberInvitationResponseData {
-            email: user.email,
-            status: result,
-        });
+        if !valid_transport {
+            resp_data.push(invitation_result(
+                email,
+                InvitationResultStatus::FAILURE,
+                Some(INVALID_INVITATION_TRANSPORT),
+            ));
+            continue;
+        }
+        let invitee = InvitationForm {
+            email: email.clone(),
+            role: None,
+            first_name: invitee.first_name.clone(),
+            last_name: invitee.last_name.clone(),
+            phone,
+            transport: Some(transport),
+        };
+        let invitation = match create_member_invitation(
+            &state,
+            member.user_id,
+            workspace.id,
+            &branding.name,
+            &invitee,
+            role,
+        )
+        .await
+        {
+            Ok(i) => i,
+            Err(e) => {
+                tracing::debug!("{}", e);
+                resp_data.push(invitation_result(email, InvitationResultStatus::FAILURE, None));
+                continue;
+            }
+        };
+
+        let state = state.clone();
+        tokio::spawn(async move { deliver_invitation(&state, invitation).await });
+        resp_data.push(invitation_result(email, InvitationResultStatus::SUCCESS, None));
     }
     return json_resp::<Vec<MemberInvitationResponseData>>(None, resp_data);
 }
 
+// Loads the member invitation behind an accept link, whatever its status
+async fn linked_invitation(state: &AppState, token: &str) -> Result<Invitation, &'static str> {
+    let claims = decode_invitation_token(token, &state.settings.jwt.jwt_secret).map_err(|e| {
+        tracing::debug!("InvitationTokenError {}", e);
+        INVALID_INVITATION
+    })?;
+    let invitation = state
+        .db
+        .invitation
+        .get_invitation(claims.invitation_id)
+        .await
+        .map_err(|_| INVALID_INVITATION)?;
+    if invitation.workspace != claims.workspace_id
+        || !matches!(invitation.inv_type, InvitationType::MEMBER_INVITATION)
+    {
+        return Err(INVALID_INVITATION);
+    }
+    Ok(invitation)
+}
+
+fn is_pending(invitation: &Invitation) -> bool {
+    matches!(
+        invitation.status,
+        InvitationStatus::QUEUED | InvitationStatus::INVITED
+    )
+}
+
+// Loads the invitation behind an accept link if it can still be answered
+async fn pending_invitation(state: &AppState, token: &str) -> Result<Invitation, &'static str> {
+    let invitation = linked_invitation(state, token).await?;
+    if !is_pending(&invitation) {
+        return Err(INVALID_INVITATION);
+    }
+    Ok(invitation)
+}
+
+// Adds the invited user to the workspace and returns the new member id
+async fn accept_pending_invitation(
+    state: &AppState,
+    invitation: &Invitation,
+) -> Result<Uuid, (StatusCode, &'static str)> {
+    let member = MemberRecord {
+        id: Uuid::new_v4(),
+        joined_at: Local::now().to_utc(),
+        profile_picture: None,
+        status: Some(MemberStatus::VERIFIED),
+        role: Some(invitation.role.clone()),
+        invitation_token: None,
+        invitation_code: None,
+        user_id: invitation.sent_to,
+        invited_by: invitation.created_by,
+        role_granted_by: Some(invitation.created_by),
+        workspace: invitation.workspace,
+    };
+    let details = MemberWorkspaceDetails {
+        id: Uuid::new_v4(),
+        workspace_order: 0,
+        is_default: false,
+        is_fav: false,
+        member_id: member.id,
+        workspace_id: invitation.workspace,
+        user_id: invitation.sent_to,
+    };
+    let member_limit = match plan_limits(state, invitation.workspace).await {
+        Ok((_, limits)) => Quota::MEMBERS.limit(&limits),
+        Err(e) => {
+            tracing::debug!("Failed to load plan limits {}", e);
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, errors::INTERNAL_SERVER_ERROR));
+        }
+    };
+    match state
+        .db
+        .invitation
+        .accept_invitation(invitation.id, member.clone(), details, member_limit)
+        .await
+    {
+        Ok(JoinOutcome::JOINED) => {}
+        Ok(JoinOutcome::ALREADY_MEMBER) => return Err((StatusCode::CONFLICT, ALREADY_A_MEMBER)),
+        Ok(JoinOutcome::NO_SEAT) => {
+            return Err((StatusCode::FORBIDDEN, Quota::MEMBERS.exceeded_msg()))
+        }
+        Err(Error::RowNotFound) => return Err((StatusCode::BAD_REQUEST, INVALID_INVITATION)),
+        Err(e) => {
+            tracing::debug!("Failed to accept invitation {}", e);
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, errors::INTERNAL_SERVER_ERROR));
+        }
+    };
+    // following the emailed link proves the address
+    if let Err(e) = state.db.user.verify_user(invitation.sent_to).await {
+        tracing::debug!("Failed to verify invited user {}", e);
+    }
+    emit_webhook_event(
+        &state.db,
+        invitation.workspace,
+        WebhookEvent::MEMBER_JOINED,
+        member_joined_data(&member),
+    )
+    .await;
+    Ok(member.id)
+}
+
 pub async fn accept_member_invitation(
     State(state): State<AppState>,
-    Extension(user_data): Extension<UserData>,
-    Extension(workspace): Extension<MemberWorkspace>,
     Json(form): Json<AcceptMemberInvitationForm>,
 ) -> impl IntoResponse {
-    let Ok(inv) = state
+    let invitation = match pending_invitation(&state, &form.token).await {
+        Ok(i) => i,
+        Err(msg) => return json_err!(StatusCode::BAD_REQUEST, msg),
+    };
+    let Ok(user) = state.db.user.get_by_id(invitation.sent_to).await else {
+        return json_err!();
+    };
+    let member_id = match accept_pending_invitation(&state, &invitation).await {
+        Ok(id) => id,
+        Err((code, msg)) => return json_err!(code, msg),
+    };
+    json_resp::<AcceptedInvitation>(
+        None,
+        AcceptedInvitation {
+            workspace_id: invitation.workspace,
+            member_id,
+            user_id: user.id,
+            needs_password: user.password.is_none(),
+        },
+    )
+}
+
+// Lets an invitee without a password set one, accepting the invitation on
+// the way if needed, and logs them into the inviting workspace
+pub async fn onboard_invited_user(
+    State(state): State<AppState>,
+    Json(form): Json<OnboardingForm>,
+) -> impl IntoResponse {
+    let invitation = match linked_invitation(&state, &form.token).await {
+        Ok(i) if is_pending(&i) || i.status == InvitationStatus::ACCEPTED => i,
+        Ok(_) => return json_err!(StatusCode::BAD_REQUEST, INVALID_INVITATION),
+        Err(msg) => return json_err!(StatusCode::BAD_REQUEST, msg),
+    };
+    let Ok(user) = state.db.user.get_by_id(invitation.sent_to).await else {
+        return json_err!();
+    };
+    if user.password.is_some() {
+        return json_err!(StatusCode::CONFLICT, ALREADY_ONBOARDED);
+    }
+    let first_name = form.first_name.trim().to_string();
+    if first_name.is_empty() {
+        return json_err!(StatusCode::BAD_REQUEST, NAME_REQUIRED);
+    }
+    if !is_password_allowed(&form.password) {
+        return json_err!(StatusCode::BAD_REQUEST, WEAK_PASSWORD);
+    }
+    if is_pending(&invitation) {
+        if let Err((code, msg)) = accept_pending_invitation(&state, &invitation).await {
+            return json_err!(code, msg);
+        }
+    }
+    let Ok(password_hash) = encrypt_password(form.password) else {
+        return json_err!();
+    };
+    let last_name = form.last_name.unwrap_or_default().trim().to_string();
+    let user = match state
+        .db
+        .user
+        .complete_onboarding(user.id, password_hash, first_name, last_name)
+        .await
+    {
+        Ok(u) => u,
+        Err(Error::RowNotFound) => return json_err!(StatusCode::CONFLICT, ALREADY_ONBOARDED),
+        Err(e) => {
+            tracing::debug!("Failed to onboard user {}", e);
+            return json_err!();
+        }
+    };
+    return match login_response(&state, user, Some(invitation.workspace)).await {
+        Ok(data) => json_resp::<LoginResponse>(None, data),
+        Err(e) => {
+            tracing::debug!("Failed to build the login response {}", e);
+            json_err!()
+        }
+    };
+}
+
+pub async fn reject_member_invitation(
+    State(state): State<AppState>,
+    Json(form): Json<AcceptMemberInvitationForm>,
+) -> impl IntoResponse {
+    let invitation = match pending_invitation(&state, &form.token).await {
+        Ok(i) => i,
+        Err(msg) => return json_err!(StatusCode::BAD_REQUEST, msg),
+    };
+    if let Err(e) = state
         .db
         .invitation
-        .get_verification_data(&form.user_id)
+        .update_invitation_status(InvitationStatus::REJECTED, invitation.id)
         .await
-    else {
+    {
+        tracing::debug!("UpdateInvitationFailed {}", e);
         return json_err!();
-    };
-    json_resp::<&str>(None, "Success")
+    }
+    json_resp::<&str>(None, "Invitation rejected")
 }
@@ -0,0 +1,173 @@
+use std::time::Duration;
+
+use crate::darkscout::adapters::dsbreach::BreachFetchError;
+use crate::darkscout::adapters::DSProvider;
+use crate::darkscout::types::monitoring::{BreachSnapshot, MonitoredAsset, ScannedBreach};
+use crate::darkscout::types::store::MonitoringStore;
+use crate::darkscout::types::usage::LookupTargetType;
+use crate::darkscout::types::AppState;
+use crate::darkscout::utils::alerts::notify_breach_alerts;
+use crate::darkscout::utils::monitoring::{jittered, monitored_plans, scan_interval_minutes};
+
+const TICK_INTERVAL: Duration = Duration::from_secs(60);
+// a failed scan is retried long before the plan interval is up
+const RETRY_MINUTES: i32 = 60;
+// used when the provider rate limits without a Retry-After
+const DEFAULT_BACKOFF: Duration = Duration::from_secs(60);
+const MAX_BACKOFF: Duration = Duration::from_secs(60 * 60);
+
+// Waits until the budget shared by every instance allows another provider
+// lookup
+async fn wait_for_scan_token(state: &AppState, lookups_per_minute: u32) {
+    let pause = Duration::from_millis(60_000 / lookups_per_minute as u64);
+    loop {
+        match state
+            .db
+            .monitoring
+            .take_scan_token(lookups_per_minute as i32)
+            .await
+        {
+            Ok(true) => return,
+            Ok(false) => {}
+            Err(e) => tracing::debug!("Failed to take a scan token: {}", e),
+        }
+        tokio::time::sleep(pause).await;
+    }
+}
+
+async fn scan_asset(
+    state: &AppState,
+    asset: &MonitoredAsset,
+) -> Result<Vec<ScannedBreach>, BreachFetchError> {
+    match asset.asset_type {
+        LookupTargetType::EMAIL => Ok(state
+            .ds_provider
+            .refresh_email_breaches(&asset.asset)
+            .await?
+            .into_iter()
+            .map(|b| ScannedBreach {
+                name: b.name,
+                data_classes: b.data_classes.unwrap_or_default(),
+                pwn_count: b.pwn_count.unwrap_or(0) as i64,
+                is_sensitive: b.is_sensitive.unwrap_or(false),
+            })
+            .collect()),
+        LookupTargetType::DOMAIN => Ok(state
+            .ds_provider
+            .refresh_domain_breaches(&asset.asset)
+            .await?
+            .into_iter()
+            .map(|name| ScannedBreach {
+                name,
+                data_classes: Vec::new(),
+                pwn_count: 0,
+                is_sensitive: false,
+            })
+            .collect()),
+    }
+}
+
+// Re-scans the verified emails and domains of the plans with monitoring
+// straight from the breach provider. Every instance runs it, the claims
+// keep them from scanning the same asset and the lookups come out of one
+// budget for all of them.
+pub async fn run_asset_monitoring(state: AppState) {
+    let settings = state.settings.monitoring.clone();
+    if !settings.enabled {
+        return;
+    }
+    let lookups_per_minute = settings.lookups_per_minute.max(1);
+    // enough time for a whole batch at the configured rate
+    let lease_minutes = (settings.batch_size / lookups_per_minute as i64) as i32 + 10;
+    let mut ticker = tokio::time::interval(TICK_INTERVAL);
+    loop {
+        ticker.tick().await;
+        // alerts whose notification failed earlier
+        if let Err(e) = notify_breach_alerts(&state).await {
+            tracing::debug!("Failed to notify the breach alerts: {}", e);
+        }
+        let plans = monitored_plans(&state.settings.plans);
+        if plans.is_empty() {
+            continue;
+        }
+        let assets = match state
+            .db
+            .monitoring
+            .claim_due_assets(plans, settings.batch_size, lease_minutes)
+            .await
+        {
+            Ok(a) => a,
+            Err(e) => {
+                tracing::debug!("Failed to claim the assets to scan: {}", e);
+                continue;
+            }
+        };
+        let mut pending = assets.into_iter();
+        while let Some(asset) = pending.next() {
+            wait_for_scan_token(&state, lookups_per_minute).await;
+            let saved = match scan_asset(&state, &asset).await {
+                Ok(breaches) => {
+                    let interval = scan_interval_minutes(&state.settings.plans, asset.plan)
+                        .unwrap_or(RETRY_MINUTES);
+                    let snapshot = BreachSnapshot::new(
+                        asset.workspace,
+                        asset.asset_type,
+                        asset.id,
+                        &asset.asset,
+                        &breaches,
+                    );
+                    if let Err(e) = state.db.monitoring.save_breach_snapshot(&snapshot).await {
+                        tracing::debug!(
+                            "Failed to save the breach snapshot of {}: {}",
+                            asset.asset,
+                            e
+                        );
+                    }
+                    match state
+                        .db
+                        .monitoring
+                        .record_asset_scan(&asset, breaches, jittered(interval))
+                        .await
+                    {
+                        Ok(alerts) if !alerts.is_empty() => notify_breach_alerts(&state).await,
+                        Ok(_) => Ok(()),
+                        Err(e) => Err(e),
+                    }
+                }
+                Err(BreachFetchError::RateLimited(retry_after)) => {
+                    // the rest of the batch goes back to the next claim
+                    let rest: Vec<MonitoredAsset> = std::iter::once(asset).chain(pending).collect();
+                    if let Err(e) = state.db.monitoring.release_assets(&rest).await {
+                        tracing::debug!("Failed to release the claimed assets: {}", e);
+                    }
+                    let backoff = retry_after
+                        .map(Duration::from_secs)
+                        .unwrap_or(DEFAULT_BACKOFF)
+                        .min(MAX_BACKOFF);
+                    tracing::debug!("Breach provider rate limited, backing off {:?}", backoff);
+                    if let Err(e) = state
+                        .db
+                        .monitoring
+                        .pause_scans(backoff.as_secs() as i64)
+                        .await
+                    {
+                        tracing::debug!("Failed to pause the scans: {}", e);
+                    }
+                    tokio::time::sleep(backoff).await;
+                    break;
+                }
+                Err(BreachFetchError::Failed(e)) => {
+                    tracing::debug!("Failed to scan {}: {}", asset.asset, e);
+                    state
+                        .db
+                        .monitoring
+                        .record_failed_asset_scan(&asset, e, jittered(RETRY_MINUTES))
+                        .await
+                }
+            };
+            if let Err(e) = saved {
+                tracing::debug!("Failed to save the scan of {}: {}", asset.asset, e);
+            }
+        }
+    }
+}
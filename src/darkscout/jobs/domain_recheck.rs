@@ -0,0 +1,73 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::darkscout::adapters::{DnsResolver, HttpFetcher};
+use crate::darkscout::types::store::{PgStore, VerifiedDomainsStore};
+use crate::darkscout::types::verified_domains::{VerifiedDomainRecord, MAX_FAILED_DOMAIN_CHECKS};
+use crate::darkscout::utils::domain_verification::find_domain_proof;
+
+const RECHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const RECHECK_BATCH_SIZE: i64 = 100;
+
+async fn recheck_domain<R: DnsResolver, F: HttpFetcher>(
+    db: &PgStore,
+    resolver: &R,
+    fetcher: &F,
+    domain: VerifiedDomainRecord,
+) -> Result<(), sqlx::Error> {
+    let check = find_domain_proof(
+        resolver,
+        fetcher,
+        &domain.domain,
+        &domain.verification_token,
+    )
+    .await;
+    if let Some(method) = check.method {
+        return db
+            .verified_domains
+            .update_verified_domain(domain.workspace, domain.id, true, Some(method))
+            .await;
+    }
+    let failed = db
+        .verified_domains
+        .record_failed_domain_check(domain.workspace, domain.id, check.errors.join("; "))
+        .await?;
+    // one failed lookup can be a dns or hosting hiccup
+    if failed >= MAX_FAILED_DOMAIN_CHECKS {
+        tracing::debug!("Revoking the verification of {}", domain.domain);
+        db.verified_domains
+            .update_verified_domain(domain.workspace, domain.id, false, None)
+            .await?;
+    }
+    Ok(())
+}
+
+// Checks the proof of every verified domain once a day and revokes the
+// verification of domains that took it down
+pub async fn run_domain_rechecks<R: DnsResolver, F: HttpFetcher>(
+    db: Arc<PgStore>,
+    resolver: R,
+    fetcher: F,
+) {
+    let mut interval = tokio::time::interval(RECHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+        let domains = match db
+            .verified_domains
+            .get_domains_due_for_recheck(RECHECK_BATCH_SIZE)
+            .await
+        {
+            Ok(d) => d,
+            Err(e) => {
+                tracing::debug!("Failed to load the domains to re-check: {}", e);
+                continue;
+            }
+        };
+        for domain in domains {
+            let name = domain.domain.clone();
+            if let Err(e) = recheck_domain(&db, &resolver, &fetcher, domain).await {
+                tracing::debug!("Failed to re-check {}: {}", name, e);
+            }
+        }
+    }
+}
@@ -0,0 +1,5 @@
+// Background tasks spawned next to the web api
+pub mod usage_rollup;
+pub mod domain_recheck;
+pub mod asset_monitor;
+pub mod webhook_delivery;
@@ -0,0 +1,24 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{Days, Utc};
+
+use crate::darkscout::types::store::{PgStore, UsageStore};
+
+const ROLLUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+// Rolls the usage events of yesterday and today up every hour. Yesterday is
+// redone so events written around midnight are not lost.
+pub async fn run_usage_rollups(db: Arc<PgStore>) {
+    let mut interval = tokio::time::interval(ROLLUP_INTERVAL);
+    loop {
+        interval.tick().await;
+        let today = Utc::now().date_naive();
+        let yesterday = today.checked_sub_days(Days::new(1)).unwrap_or(today);
+        for day in [yesterday, today] {
+            if let Err(e) = db.usage.rollup_usage_events(day).await {
+                tracing::debug!("Failed to roll up usage events for {}: {}", day, e);
+            }
+        }
+    }
+}
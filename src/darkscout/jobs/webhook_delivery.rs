@@ -0,0 +1,72 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+
+use crate::darkscout::adapters::WebhookSender;
+use crate::darkscout::types::store::{PgStore, WebhooksStore};
+use crate::darkscout::types::webhooks::{
+    retry_delay_minutes, DeliveryStatus, DueDelivery, MAX_DELIVERY_ATTEMPTS,
+};
+use crate::darkscout::utils::webhooks::send_delivery;
+
+const DELIVERY_INTERVAL: Duration = Duration::from_secs(15);
+const DELIVERY_BATCH_SIZE: i64 = 50;
+// a claimed delivery is picked up again if the instance dies while sending
+const DELIVERY_LEASE_MINUTES: i32 = 5;
+
+async fn deliver<S: WebhookSender>(
+    db: &PgStore,
+    sender: &S,
+    delivery: DueDelivery,
+) -> Result<(), sqlx::Error> {
+    let (response_status, error) =
+        match send_delivery(sender, &delivery, Utc::now().timestamp()).await {
+            Ok(code) if (200..300).contains(&code) => (Some(code as i32), None),
+            Ok(code) => (
+                Some(code as i32),
+                Some(format!("receiver answered {}", code)),
+            ),
+            Err(e) => (None, Some(e)),
+        };
+    let status = match error {
+        None => DeliveryStatus::SUCCEEDED,
+        Some(_) if delivery.attempts >= MAX_DELIVERY_ATTEMPTS => DeliveryStatus::FAILED,
+        Some(_) => DeliveryStatus::PENDING,
+    };
+    db.webhooks
+        .record_delivery_result(
+            delivery.id,
+            status,
+            response_status,
+            error,
+            retry_delay_minutes(delivery.attempts),
+        )
+        .await
+}
+
+// Sends the queued webhook deliveries, a failed one is retried with an
+// exponential backoff until it runs out of attempts
+pub async fn run_webhook_deliveries<S: WebhookSender>(db: Arc<PgStore>, sender: S) {
+    let mut interval = tokio::time::interval(DELIVERY_INTERVAL);
+    loop {
+        interval.tick().await;
+        let deliveries = match db
+            .webhooks
+            .claim_due_deliveries(DELIVERY_BATCH_SIZE, DELIVERY_LEASE_MINUTES)
+            .await
+        {
+            Ok(d) => d,
+            Err(e) => {
+                tracing::debug!("Failed to claim the webhook deliveries: {}", e);
+                continue;
+            }
+        };
+        for delivery in deliveries {
+            let id = delivery.id;
+            if let Err(e) = deliver(&db, &sender, delivery).await {
+                tracing::debug!("Failed to record webhook delivery {}: {}", id, e);
+            }
+        }
+    }
+}
@@ -3,6 +3,7 @@ pub mod cache;
 pub mod database;
 pub mod entrypoints;
 pub mod handlers;
+pub mod jobs;
 pub mod types;
 pub mod utils;
 pub mod web;
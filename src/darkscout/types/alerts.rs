@@ -0,0 +1,283 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, Type};
+use uuid::Uuid;
+
+use crate::darkscout::adapters::smtp_mailer::escape_html;
+use crate::darkscout::types::monitoring::{MonitoredAsset, ScannedBreach};
+use crate::darkscout::types::settings::{format_local_time, EffectiveSetting};
+use crate::darkscout::types::usage::LookupTargetType;
+use crate::darkscout::types::workspace::WorkspaceBranding;
+
+pub const DEFAULT_ALERTS_PER_PAGE: i64 = 25;
+pub const MAX_ALERTS_PER_PAGE: i64 = 100;
+
+// declared from least to most severe so they compare
+#[derive(Serialize, Deserialize, Clone, Copy, Type, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[sqlx(type_name = "ALERT_SEVERITY")]
+pub enum AlertSeverity {
+    LOW,
+    MEDIUM,
+    HIGH,
+    CRITICAL,
+}
+
+impl AlertSeverity {
+    // the values of the alert_min_severity setting
+    pub fn from_setting(value: &str) -> Option<Self> {
+        match value {
+            "LOW" => Some(AlertSeverity::LOW),
+            "MEDIUM" => Some(AlertSeverity::MEDIUM),
+            "HIGH" => Some(AlertSeverity::HIGH),
+            "CRITICAL" => Some(AlertSeverity::CRITICAL),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Type, PartialEq, Debug)]
+#[sqlx(type_name = "ALERT_STATUS")]
+pub enum AlertStatus {
+    OPEN,
+    ACKNOWLEDGED,
+    RESOLVED,
+}
+
+impl AlertStatus {
+    // alerts only move forward, a resolved one stays resolved
+    pub fn can_move_to(&self, next: AlertStatus) -> bool {
+        matches!(
+            (self, next),
+            (AlertStatus::OPEN, AlertStatus::ACKNOWLEDGED)
+                | (AlertStatus::OPEN, AlertStatus::RESOLVED)
+                | (AlertStatus::ACKNOWLEDGED, AlertStatus::RESOLVED)
+        )
+    }
+}
+
+#[derive(Serialize, Deserialize, FromRow, Clone, Debug)]
+pub struct BreachAlert {
+    pub id: Uuid,
+    pub workspace: Uuid,
+    pub asset_type: LookupTargetType,
+    pub asset_id: Uuid,
+    pub asset: String,
+    pub breach_name: String,
+    pub data_classes: Vec<String>,
+    pub severity: AlertSeverity,
+    pub status: AlertStatus,
+    pub acknowledged_by: Option<Uuid>,
+    pub acknowledged_at: Option<NaiveDateTime>,
+    pub resolved_by: Option<Uuid>,
+    pub resolved_at: Option<NaiveDateTime>,
+    pub created_at: Option<NaiveDateTime>,
+}
+
+impl BreachAlert {
+    // a new open alert for a breach the asset showed up in
+    pub fn for_breach(asset: &MonitoredAsset, breach: ScannedBreach) -> Self {
+        BreachAlert {
+            id: Uuid::new_v4(),
+            workspace: asset.workspace,
+            asset_type: asset.asset_type,
+            asset_id: asset.id,
+            asset: asset.asset.clone(),
+            severity: breach_severity(&breach.data_classes),
+            breach_name: breach.name,
+            data_classes: breach.data_classes,
+            status: AlertStatus::OPEN,
+            acknowledged_by: None,
+            acknowledged_at: None,
+            resolved_by: None,
+            resolved_at: None,
+            created_at: None,
+        }
+    }
+}
+
+// a member the alerts of a workspace can be emailed to
+#[derive(Serialize, Deserialize, FromRow, Clone)]
+pub struct AlertRecipient {
+    pub member_id: Uuid,
+    pub email: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct AlertListQuery {
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+    pub status: Option<AlertStatus>,
+}
+
+impl AlertListQuery {
+    // (page, per_page)
+    pub fn pagination(&self) -> (i64, i64) {
+        let page = self.page.unwrap_or(1).max(1);
+        let per_page = self
+            .per_page
+            .unwrap_or(DEFAULT_ALERTS_PER_PAGE)
+            .clamp(1, MAX_ALERTS_PER_PAGE);
+        (page, per_page)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BreachAlertPage {
+    pub alerts: Vec<BreachAlert>,
+    pub page: i64,
+    pub per_page: i64,
+    pub total: i64,
+}
+
+const CRITICAL_DATA_CLASSES: [&str; 6] = [
+    "passwords",
+    "credit cards",
+    "bank account numbers",
+    "social security numbers",
+    "government issued ids",
+    "auth tokens",
+];
+
+const HIGH_DATA_CLASSES: [&str; 8] = [
+    "password hints",
+    "security questions and answers",
+    "partial credit card data",
+    "phone numbers",
+    "physical addresses",
+    "dates of birth",
+    "private messages",
+    "ip addresses",
+];
+
+// what a breach exposes besides the address itself
+const LOW_DATA_CLASSES: [&str; 3] = ["email addresses", "usernames", "names"];
+
+// How bad a breach is by the worst data it exposed. Breaches without data
+// classes, like the ones found through a domain, are MEDIUM.
+pub fn breach_severity(data_classes: &[String]) -> AlertSeverity {
+    let classes: Vec<String> = data_classes
+        .iter()
+        .map(|c| c.trim().to_lowercase())
+        .collect();
+    if classes.is_empty() {
+        return AlertSeverity::MEDIUM;
+    }
+    if classes
+        .iter()
+        .any(|c| CRITICAL_DATA_CLASSES.contains(&c.as_str()))
+    {
+        return AlertSeverity::CRITICAL;
+    }
+    if classes
+        .iter()
+        .any(|c| HIGH_DATA_CLASSES.contains(&c.as_str()))
+    {
+        return AlertSeverity::HIGH;
+    }
+    if classes
+        .iter()
+        .all(|c| LOW_DATA_CLASSES.contains(&c.as_str()))
+    {
+        return AlertSeverity::LOW;
+    }
+    AlertSeverity::MEDIUM
+}
+
+// one email per asset and member, listing the alerts they asked to hear about
+pub fn breach_alert_email(
+    asset: &str,
+    alerts: &[&BreachAlert],
+    alerts_link: &str,
+    settings: &[EffectiveSetting],
+    branding: &WorkspaceBranding,
+) -> (String, String) {
+    let rows: String = alerts
+        .iter()
+        .map(|a| {
+            let classes = if a.data_classes.is_empty() {
+                String::from("Unknown")
+            } else {
+                escape_html(&a.data_classes.join(", "))
+            };
+            let detected = a
+                .created_at
+                .map(|at| format_local_time(at, settings))
+                .unwrap_or_default();
+            format!(
+                "<tr><td>{}</td><td>{:?}</td><td>{}</td><td>{}</td></tr>",
+                escape_html(&a.breach_name),
+                a.severity,
+                classes,
+                detected
+            )
+        })
+        .collect();
+    (
+        format!("{}: {} appeared in a new breach", branding.name, asset),
+        format!(
+            "<p><b>{}</b> showed up in breaches it wasn't in before.</p>\
+            <table><tr><th>Breach</th><th>Severity</th><th>Exposed data</th><th>Detected</th></tr>{}</table>\
+            <p><a href=\"{}\">Review the alerts</a></p>",
+            escape_html(asset),
+            rows,
+            alerts_link
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classes(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_breach_severity() {
+        assert_eq!(
+            breach_severity(&classes(&["Email addresses", "Passwords"])),
+            AlertSeverity::CRITICAL
+        );
+        assert_eq!(
+            breach_severity(&classes(&["Email addresses", "Phone numbers"])),
+            AlertSeverity::HIGH
+        );
+        assert_eq!(
+            breach_severity(&classes(&["Email addresses", "Genders"])),
+            AlertSeverity::MEDIUM
+        );
+        assert_eq!(
+            breach_severity(&classes(&["Email addresses", "Usernames"])),
+            AlertSeverity::LOW
+        );
+        assert_eq!(breach_severity(&[]), AlertSeverity::MEDIUM);
+    }
+
+    #[test]
+    fn test_alert_transitions() {
+        assert!(AlertStatus::OPEN.can_move_to(AlertStatus::ACKNOWLEDGED));
+        assert!(AlertStatus::ACKNOWLEDGED.can_move_to(AlertStatus::RESOLVED));
+        assert!(!AlertStatus::RESOLVED.can_move_to(AlertStatus::ACKNOWLEDGED));
+        assert!(!AlertStatus::ACKNOWLEDGED.can_move_to(AlertStatus::ACKNOWLEDGED));
+        assert!(AlertSeverity::HIGH > AlertSeverity::MEDIUM);
+    }
+
+    #[test]
+    fn test_alert_email_branding() {
+        let branding = WorkspaceBranding {
+            name: String::from("Acme"),
+            logo_url: None,
+        };
+        let (subject, _) = breach_alert_email("a@acme.com", &[], "https://x", &[], &branding);
+        assert_eq!(subject, "Acme: a@acme.com appeared in a new breach");
+        let (subject, _) = breach_alert_email(
+            "a@acme.com",
+            &[],
+            "https://x",
+            &[],
+            &WorkspaceBranding::default(),
+        );
+        assert!(subject.starts_with("DarkScout: "));
+    }
+}
@@ -1,3 +1,5 @@
+use crate::darkscout::types::monitoring::SnapshotTotals;
+use crate::darkscout::types::workspace::WorkspaceBranding;
 use rand::Rng;
 use serde::{Deserialize, Serialize};
 
@@ -74,6 +76,7 @@ pub struct EmailAnalytics {
     pub geo_data: GeoGraphData,
     pub bento_data: BentoData,
     pub list_data: Vec<BreachInfo>,
+    pub branding: Option<WorkspaceBranding>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -184,9 +187,31 @@ pub struct TotalDetectedBreaches {
     pub increase_percentage: String,
 }
 
+// how many days back the bento numbers are compared to
+pub const TREND_PERIOD_DAYS: i32 = 30;
+
+// The snapshots of a monitored asset the bento numbers come from, empty for
+// lookups of anything else.
+#[derive(Default, Clone, Debug)]
+pub struct BreachTrend {
+    pub current: Option<SnapshotTotals>,
+    pub previous: Option<SnapshotTotals>,
+}
+
+// change from previous to current in whole percent, 0 without a baseline
+pub fn percentage_change(previous: Option<i64>, current: i64) -> String {
+    let change = match previous {
+        None => 0,
+        Some(0) if current == 0 => 0,
+        Some(0) => 100,
+        Some(p) => ((current - p) as f64 / p as f64 * 100.0).round() as i64,
+    };
+    change.to_string()
+}
+
 pub trait EmailStatsConverter {
-    fn convert_to_email_analytics(&self) -> EmailAnalytics;
-    fn create_bento_data(&self) -> BentoData;
+    fn convert_to_email_analytics(&self, trend: &BreachTrend) -> EmailAnalytics;
+    fn create_bento_data(&self, trend: &BreachTrend) -> BentoData;
     fn create_geo_graph_data(&self) -> GeoGraphData;
     fn create_radial_stack_data(&self) -> RadialStackData;
 }
@@ -206,42 +231,60 @@ impl EmailStatsConverter for EmailStats {
         GeoGraphData { data: geo_data }
     }
 
-    fn create_bento_data(&self) -> BentoData {
+    fn create_bento_data(&self, trend: &BreachTrend) -> BentoData {
         let total_records_from_breach: u64 = self
             .breach_info
             .iter()
             .map(|breach| breach.pwn_count.unwrap_or(0))
             .sum();
-        let total_breanches = 789;
         let total_sensitive_breaches = self
             .breach_info
             .iter()
             .filter(|breach| breach.is_sensitive.unwrap_or(false))
             .count();
         let total_detected_breaches = self.breach_info.len();
+        // breaches the asset has left since still count towards its total
+        let total_breaches = trend
+            .current
+            .as_ref()
+            .map(|t| t.total_breaches)
+            .unwrap_or(0)
+            .max(total_detected_breaches as i64);
+        let previous = trend.previous.as_ref();
         return BentoData {
             total_records: TotalRecords {
                 field_name: "Total Records".to_string(),
                 value: total_records_from_breach.to_string(),
-                increase_percentage: "100".to_string(),
+                increase_percentage: percentage_change(
+                    previous.map(|p| p.total_records),
+                    total_records_from_breach as i64,
+                ),
             },
             total_breaches: TotalBreaches {
                 field_name: "Total Breaches".to_string(),
-                value: total_breanches.to_string(),
-                increase_percentage: "100".to_string(),
+                value: total_breaches.to_string(),
+                increase_percentage: percentage_change(
+                    previous.map(|p| p.total_breaches),
+                    total_breaches,
+                ),
             },
             total_sensitive_breaches: TotalSensitiveBreaches {
                 field_name: "Total Sensitive Breaches".to_string(),
                 value: total_sensitive_breaches.to_string(),
-                increase_percentage: "100".to_string(),
+                increase_percentage: percentage_change(
+                    previous.map(|p| p.sensitive_count as i64),
+                    total_sensitive_breaches as i64,
+                ),
             },
             total_detected_breaches: TotalDetectedBreaches {
                 field_name: "Total Detected Breaches".to_string(),
                 value: total_detected_breaches.to_string(),
-                increase_percentage: "100".to_string(),
+                increase_percentage: percentage_change(
+                    previous.map(|p| p.breach_count as i64),
+                    total_detected_breaches as i64,
+                ),
             },
         };
-        // put increase percentages as 100 for now
     }
     fn create_radial_stack_data(&self) -> RadialStackData {
         let label = TotalFoundLabel {
@@ -265,7 +308,7 @@ impl EmailStatsConverter for EmailStats {
             }
         };
     }
-    fn convert_to_email_analytics(&self) -> EmailAnalytics {
+    fn convert_to_email_analytics(&self, trend: &BreachTrend) -> EmailAnalytics {
         let chart_data = self
             .breach_info
             .iter()
@@ -311,9 +354,10 @@ impl EmailStatsConverter for EmailStats {
                 fill_info,
             },
             geo_data,
-            bento_data: self.create_bento_data(),
+            bento_data: self.create_bento_data(trend),
             list_data: self.breach_info.clone(),
             radial_stack_data: self.create_radial_stack_data(),
+            branding: None,
         }
     }
 }
@@ -381,3 +425,76 @@ impl EmailStatsConverter for EmailStats {
 // 6
 //
 // +20.1% from last month
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn breach(name: &str, pwn_count: u64, is_sensitive: bool) -> BreachInfo {
+        BreachInfo {
+            name: String::from(name),
+            title: String::from(name),
+            description: String::new(),
+            domain: None,
+            breach_date: None,
+            added_date: None,
+            modified_date: None,
+            pwn_count: Some(pwn_count),
+            data_classes: None,
+            logo_path: String::new(),
+            is_verified: None,
+            is_fabricated: None,
+            is_sensitive: Some(is_sensitive),
+            is_retired: None,
+            is_spamlist: None,
+            is_malicious_verified: None,
+            is_subscription_free: None,
+            country_code: String::new(),
+        }
+    }
+
+    fn totals(breaches: i32, sensitive: i32, records: i64, total: i64) -> SnapshotTotals {
+        SnapshotTotals {
+            snapshot_date: NaiveDate::from_ymd_opt(2024, 10, 1).unwrap(),
+            breach_count: breaches,
+            sensitive_count: sensitive,
+            total_records: records,
+            total_breaches: total,
+        }
+    }
+
+    #[test]
+    fn test_percentage_change() {
+        assert_eq!(percentage_change(None, 10), "0");
+        assert_eq!(percentage_change(Some(0), 0), "0");
+        assert_eq!(percentage_change(Some(0), 3), "100");
+        assert_eq!(percentage_change(Some(4), 5), "25");
+        assert_eq!(percentage_change(Some(3), 4), "33");
+        assert_eq!(percentage_change(Some(4), 2), "-50");
+    }
+
+    #[test]
+    fn test_create_bento_data() {
+        let stats = EmailStats {
+            breach_info: vec![breach("Adobe", 100, true), breach("Canva", 50, false)],
+        };
+        let bento = stats.create_bento_data(&BreachTrend::default());
+        assert_eq!(bento.total_records.value, "150");
+        assert_eq!(bento.total_breaches.value, "2");
+        assert_eq!(bento.total_detected_breaches.value, "2");
+        assert_eq!(bento.total_sensitive_breaches.value, "1");
+        assert_eq!(bento.total_records.increase_percentage, "0");
+
+        let trend = BreachTrend {
+            current: Some(totals(2, 1, 150, 3)),
+            previous: Some(totals(1, 1, 100, 2)),
+        };
+        let bento = stats.create_bento_data(&trend);
+        assert_eq!(bento.total_breaches.value, "3");
+        assert_eq!(bento.total_breaches.increase_percentage, "50");
+        assert_eq!(bento.total_records.increase_percentage, "50");
+        assert_eq!(bento.total_detected_breaches.increase_percentage, "100");
+        assert_eq!(bento.total_sensitive_breaches.increase_percentage, "0");
+    }
+}
@@ -5,3 +5,63 @@ pub const INCORRECT_USERNAME_PASSWORD: &'static str =
 pub const INTERNAL_SERVER_ERROR: &'static str = "Something went wrong. Please try again later.";
 pub const INVITATION_FAILED: &'static str = "Failed to create invitation.";
 pub const VERIFY_FAILED: &'static str = "Failed to create invitation.";
+pub const STATUS_REASON_REQUIRED: &'static str = "A reason is required to change the workspace status.";
+pub const INVALID_COUNTRY: &'static str = "Please provide a valid ISO 3166 country.";
+pub const INVALID_DISPLAY_PICTURE: &'static str = "Display picture must be an http(s) url.";
+pub const DEFAULT_WORKSPACE_REQUIRED: &'static str =
+    "A default workspace is required, set another workspace as default instead.";
+pub const WORKSPACE_NOT_FOUND: &'static str = "Workspace not found.";
+pub const INVALID_INVITATION: &'static str = "This invitation is invalid or has expired.";
+pub const INVALID_INVITATION_ROLE: &'static str = "Members can't be invited as owners.";
+pub const ALREADY_A_MEMBER: &'static str = "This user is already a member of the workspace.";
+pub const ALREADY_INVITED: &'static str = "This user already has a pending invitation.";
+pub const INVITATION_NOT_FOUND: &'static str = "Invitation not found.";
+pub const INVITATION_RESEND_LIMITED: &'static str =
+    "This invitation was sent recently or too many times. Please try again later.";
+pub const INVALID_IMPORT_FILE: &'static str =
+    "Please upload a valid csv file with at least an email column.";
+pub const TOO_MANY_IMPORT_ROWS: &'static str = "A csv import can have at most 1000 rows.";
+pub const MEMBER_NOT_FOUND: &'static str = "Member not found.";
+pub const CANNOT_MANAGE_MEMBER: &'static str =
+    "You can't change your own membership or the one of a workspace owner.";
+pub const INVALID_MEMBER_ROLE: &'static str = "Ownership can't be granted to a member.";
+pub const INVALID_MEMBER_STATUS: &'static str = "The member is not in a state that allows this.";
+pub const INVALID_INVITATION_TRANSPORT: &'static str =
+    "Invitations go out as EMAIL_LINK or as PHONE_LINK with a valid phone number.";
+pub const INVALID_PHONE: &'static str =
+    "Please provide a valid phone number including the country code.";
+pub const PHONE_NOT_SET: &'static str = "Please add a phone number first.";
+pub const VERIFICATION_CODE_EXPIRED: &'static str =
+    "This code has expired or was entered wrong too many times. Please request a new one.";
+pub const JOIN_REQUEST_NOT_FOUND: &'static str = "Join request not found.";
+pub const JOIN_REQUEST_PENDING: &'static str =
+    "Your request to join the workspace is waiting for an admin to approve it.";
+pub const INVALID_INVITE_LINK: &'static str = "This join link is invalid, expired or used up.";
+pub const INVITE_LINK_NOT_FOUND: &'static str = "Join link not found.";
+pub const INVITE_LINK_DOMAIN_NOT_VERIFIED: &'static str =
+    "Join links can only be restricted to one of the workspace's verified domains.";
+pub const INVITE_LINK_DOMAIN_MISMATCH: &'static str =
+    "This join link is only for verified accounts on the workspace's domain.";
+pub const ONBOARDING_REQUIRED: &'static str =
+    "Please finish setting up your account from your invitation link.";
+pub const ALREADY_ONBOARDED: &'static str = "This account is already set up, please log in.";
+pub const NAME_REQUIRED: &'static str = "Please provide your first name.";
+pub const WEAK_PASSWORD: &'static str =
+    "Passwords need 8 to 72 characters with at least one letter and one digit.";
+pub const VERIFIED_EMAIL_NOT_FOUND: &'static str = "Email not found.";
+pub const CONFIRMATION_RESEND_LIMITED: &'static str =
+    "A confirmation was sent recently or too many times. Please try again later.";
+pub const INVALID_EMAIL_CONFIRMATION: &'static str =
+    "This confirmation link is invalid or has expired.";
+pub const VERIFIED_DOMAIN_NOT_FOUND: &'static str = "Domain not found.";
+pub const INVALID_VERIFIED_EMAIL: &'static str = "Please provide valid email addresses.";
+pub const INVALID_VERIFIED_DOMAIN: &'static str = "Please provide valid domain names.";
+pub const LOOKUP_NOT_ALLOWED: &'static str =
+    "Lookups are limited to your own email and the emails and domains verified in this workspace.";
+pub const ALERT_NOT_FOUND: &'static str = "Alert not found.";
+pub const ALERT_STATUS_CONFLICT: &'static str =
+    "This alert has already been acknowledged or resolved.";
+pub const WEBHOOK_NOT_FOUND: &'static str = "Webhook not found.";
+pub const INVALID_WEBHOOK_URL: &'static str = "Please provide a valid https url on a public host.";
+pub const WEBHOOK_EVENTS_REQUIRED: &'static str = "Please subscribe to at least one event.";
+pub const WEBHOOK_DELIVERY_NOT_FOUND: &'static str = "Delivery not found.";
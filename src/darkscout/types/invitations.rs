@@ -1,10 +1,31 @@
+use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 use sqlx::{Decode, FromRow, Type};
 use uuid::Uuid;
 
+use crate::darkscout::adapters::smtp_mailer::escape_html;
+use crate::darkscout::types::member::MemberRole;
 use crate::darkscout::utils::auth::generate_code;
 
-#[derive(Serialize, Deserialize, Clone, Type)]
+pub const INVITATION_TTL_HOURS: i64 = 72;
+// an invitation can be resent at most MAX_INVITATION_RESENDS times, waiting
+// RESEND_INTERVAL_MINUTES between two emails
+pub const RESEND_INTERVAL_MINUTES: i32 = 10;
+pub const MAX_INVITATION_RESENDS: i32 = 5;
+// codes sent by sms are short lived, every code allows a few wrong guesses
+pub const PHONE_CODE_TTL_MINUTES: i32 = 10;
+pub const MAX_CODE_ATTEMPTS: i32 = 5;
+// csv imports are capped and their emails go out in throttled batches
+pub const MAX_IMPORT_ROWS: usize = 1000;
+pub const IMPORT_BATCH_SIZE: usize = 20;
+pub const IMPORT_BATCH_INTERVAL_SECS: u64 = 60;
+// join links live a week unless the admin picks another expiry
+pub const DEFAULT_INVITE_LINK_TTL_HOURS: i32 = 24 * 7;
+pub const MAX_INVITE_LINK_TTL_HOURS: i32 = 24 * 30;
+pub const MAX_INVITE_LINK_USES: i32 = 1000;
+pub const INVITE_LINK_TOKEN_LEN: usize = 32;
+
+#[derive(Serialize, Deserialize, Clone, Type, PartialEq, Debug)]
 #[sqlx(type_name = "INVITATION_STATUS")]
 pub enum InvitationStatus {
     QUEUED,
@@ -12,6 +33,7 @@ pub enum InvitationStatus {
     FAILED,
     REJECTED,
     DELETED,
+    ACCEPTED,
 }
 
 impl TryInto<String> for InvitationStatus {
@@ -23,12 +45,13 @@ impl TryInto<String> for InvitationStatus {
             InvitationStatus::FAILED => Ok(String::from("FAILED")),
             InvitationStatus::REJECTED => Ok(String::from("REJECTED ")),
             InvitationStatus::DELETED => Ok(String::from("DELETED")),
+            InvitationStatus::ACCEPTED => Ok(String::from("ACCEPTED")),
         };
     }
 }
 
 #[allow(non_camel_case_types)]
-#[derive(Clone, Type)]
+#[derive(Serialize, Deserialize, Clone, Copy, Type, PartialEq, Debug)]
 #[sqlx(type_name = "TRANSPORT_TYPE")]
 pub enum TransportType {
     EMAIL_CODE,
@@ -37,6 +60,12 @@ pub enum TransportType {
     PHONE_LINK,
 }
 
+impl TransportType {
+    pub fn is_phone(&self) -> bool {
+        matches!(self, TransportType::PHONE_CODE | TransportType::PHONE_LINK)
+    }
+}
+
 #[allow(non_camel_case_types)]
 #[derive(Clone, Type)]
 #[sqlx(type_name = "INVITATION_TYPE")]
@@ -82,6 +111,23 @@ pub struct Invitation {
     pub transport: TransportType,
     pub status: InvitationStatus,
     pub inv_type: InvitationType,
+    pub role: MemberRole,
+    pub phone: Option<String>,
+}
+
+// an invitation as listed to the admins of a workspace
+#[derive(Serialize, Deserialize, FromRow, Clone)]
+pub struct InvitationSummary {
+    pub id: Uuid,
+    pub email: String,
+    pub role: MemberRole,
+    pub status: InvitationStatus,
+    pub error: Option<String>,
+    pub created_by: Uuid,
+    pub sender_email: String,
+    pub resend_count: i32,
+    pub last_sent_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
 }
 
 #[derive(FromRow, Clone, Decode)]
@@ -98,24 +144,36 @@ pub struct InvitationRecord {
     pub status: String,
 }
 
-#[derive(FromRow, Clone, Decode)]
+#[derive(FromRow, Clone)]
 pub struct VerificationData {
+    pub id: Uuid,
     pub details: String,
     pub created_by: Uuid,
     pub sent_to: Uuid,
     pub workspace: Uuid,
     pub transport: TransportType,
     pub status: InvitationStatus,
+    pub role: MemberRole,
+    pub phone: Option<String>,
 }
 
+// phone must be set for the PHONE_CODE transport, role is the one the user
+// gets in the workspace once verified
 pub fn new_verification_code(
     user: Uuid,
     inviter: Uuid,
     workspace: Uuid,
     email: String,
+    role: MemberRole,
+    transport: TransportType,
+    phone: Option<String>,
 ) -> Invitation {
     let code = generate_code();
-    let msg = format!("<h1>Your verification code for darkscout account is {} </h1>", &code);
+    let msg = if transport.is_phone() {
+        format!("Your DarkScout verification code is {}", &code)
+    } else {
+        format!("<h1>Your verification code for darkscout account is {} </h1>", &code)
+    };
     Invitation {
         id: Uuid::new_v4(),
         subject: String::from("DarkScout: Verification Email"),
@@ -125,32 +183,182 @@ pub fn new_verification_code(
         created_by: inviter,
         sent_to: user,
         workspace,
-        transport: TransportType::EMAIL_CODE,
+        transport,
         status: InvitationStatus::QUEUED,
         inv_type: InvitationType::VERIFY_TOKEN,
+        role,
+        phone,
     }
 }
 
-
+// phone must be set for the PHONE_LINK transport
 pub fn new_workspace_invitation(
-    user_id: Uuid, name: Option<String>, workspace_name: String, email: String, workspace_id: Uuid, invited_user: Uuid
+    id: Uuid,
+    inviter: Uuid,
+    name: Option<String>,
+    workspace_name: String,
+    email: String,
+    workspace_id: Uuid,
+    invited_user: Uuid,
+    role: MemberRole,
+    accept_link: String,
+    transport: TransportType,
+    phone: Option<String>,
 ) -> Invitation {
-
-    let name = name.unwrap_or_else(|| String::from("default"));
-    let link = String::from("https://darkscout.com/invitations/user_id/");
-    let email_link = format!("{} Link", link);
-    Invitation{
-        id: Uuid::new_v4(),
-        subject: format!("Hello {}. You've been invited to {} on darkscout.", &name, workspace_name),
-        msg: format!("Hello {}. You've been invited to darkscout by {}. Please visit {} to accept invitation", &name, &workspace_name, email_link),
+    let name = name.unwrap_or_else(|| String::from("there"));
+    let msg = if transport.is_phone() {
+        format!(
+            "Hello {}. You've been invited to join {} on DarkScout: {}",
+            &name, &workspace_name, &accept_link
+        )
+    } else {
+        format!(
+            "<p>Hello {}. You've been invited to join {} on DarkScout.</p>\
+            <p><a href=\"{}\">Accept the invitation</a></p>\
+            <p>The link expires in {} hours.</p>",
+            escape_html(&name),
+            escape_html(&workspace_name),
+            &accept_link,
+            INVITATION_TTL_HOURS
+        )
+    };
+    Invitation {
+        id,
+        subject: format!("You've been invited to {} on DarkScout", workspace_name),
+        msg,
         email,
-        details: link,
-        created_by: user_id,
+        details: String::new(),
+        created_by: inviter,
         sent_to: invited_user,
         workspace: workspace_id,
-        transport: TransportType::EMAIL_LINK,
+        transport,
         status: InvitationStatus::QUEUED,
-        inv_type: InvitationType::MEMBER_INVITATION
+        inv_type: InvitationType::MEMBER_INVITATION,
+        role,
+        phone,
     }
+}
+
+// Claims of the signed link sent with a member invitation
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct InvitationClaims {
+    pub invitation_id: Uuid,
+    pub workspace_id: Uuid,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct AcceptedInvitation {
+    pub workspace_id: Uuid,
+    pub member_id: Uuid,
+    pub user_id: Uuid,
+    // invited users without an account still have to pick a password
+    pub needs_password: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct MemberImportQuery {
+    pub dry_run: Option<bool>,
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum ImportRowStatus {
+    NEW_INVITE,
+    ALREADY_MEMBER,
+    ALREADY_INVITED,
+    DUPLICATE,
+    INVALID_EMAIL,
+    INVALID_ROLE,
+    FAILED,
+}
+
+// a csv row after validation, line is the line number in the uploaded file
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MemberImportRow {
+    pub line: u64,
+    pub email: String,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub role: Option<MemberRole>,
+    pub status: ImportRowStatus,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct MemberImportReport {
+    pub dry_run: bool,
+    pub total: usize,
+    pub invited: usize,
+    pub rows: Vec<MemberImportRow>,
+}
+
+#[derive(Serialize, Deserialize, FromRow, Clone)]
+pub struct InviteLink {
+    pub id: Uuid,
+    pub workspace: Uuid,
+    pub token: String,
+    pub role: MemberRole,
+    // only users with an email on this domain can redeem the link
+    pub domain: Option<String>,
+    pub max_uses: i32,
+    pub use_count: i32,
+    pub expires_at: NaiveDateTime,
+    pub revoked_at: Option<NaiveDateTime>,
+    pub created_by: Uuid,
+    pub created_at: NaiveDateTime,
+}
+
+pub struct NewInviteLink {
+    pub id: Uuid,
+    pub workspace: Uuid,
+    pub token: String,
+    pub role: MemberRole,
+    pub domain: Option<String>,
+    pub max_uses: i32,
+    pub ttl_hours: i32,
+    pub created_by: Uuid,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct InviteLinkForm {
+    pub role: Option<MemberRole>,
+    pub domain: Option<String>,
+    pub max_uses: i32,
+    pub expires_in_hours: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct InviteLinkResponse {
+    #[serde(flatten)]
+    pub link: InviteLink,
+    pub url: String,
+}
 
+#[derive(Serialize, Deserialize)]
+pub struct RedeemInviteLinkForm {
+    pub token: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invitation_escapes_names() {
+        let invitation = new_workspace_invitation(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Some(String::from("<b>Eve</b>")),
+            String::from("Acme & <i>Co</i>"),
+            String::from("eve@acme.com"),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            MemberRole::MEMBER,
+            String::from("https://app/accept"),
+            TransportType::EMAIL_LINK,
+            None,
+        );
+        assert!(invitation.msg.contains("&lt;b&gt;Eve&lt;/b&gt;"));
+        assert!(invitation.msg.contains("Acme &amp; &lt;i&gt;Co&lt;/i&gt;"));
+        assert!(!invitation.msg.contains("<b>"));
+    }
 }
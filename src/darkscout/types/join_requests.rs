@@ -0,0 +1,113 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, Type};
+use uuid::Uuid;
+
+use crate::darkscout::types::errors::JOIN_REQUEST_PENDING;
+
+// what sign ups on one of the workspace's verified domains get
+#[allow(non_camel_case_types)]
+#[derive(Serialize, Deserialize, Clone, Copy, Type, PartialEq, Debug)]
+#[sqlx(type_name = "JOIN_POLICY")]
+pub enum JoinPolicy {
+    OFF,
+    REQUEST,
+    AUTO_JOIN,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Type, PartialEq, Debug)]
+#[sqlx(type_name = "JOIN_REQUEST_STATUS")]
+pub enum JoinRequestStatus {
+    UNVERIFIED,
+    PENDING,
+    APPROVED,
+    REJECTED,
+}
+
+#[derive(Serialize, Deserialize, FromRow, Clone)]
+pub struct JoinRequest {
+    pub id: Uuid,
+    pub workspace: Uuid,
+    pub user_id: Uuid,
+    pub domain: String,
+    pub status: JoinRequestStatus,
+}
+
+// a join request as listed to the admins of a workspace
+#[derive(Serialize, Deserialize, FromRow, Clone)]
+pub struct JoinRequestSummary {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub email: String,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub domain: String,
+    pub status: JoinRequestStatus,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct JoinPolicyForm {
+    pub policy: JoinPolicy,
+}
+
+// the part after the @, lowercased so it can be matched to verified domains
+pub fn email_domain(email: &str) -> Option<String> {
+    let (_, domain) = email.trim().rsplit_once('@')?;
+    let domain = domain.trim_end_matches('.').to_lowercase();
+    (!domain.is_empty()).then_some(domain)
+}
+
+// Users who signed up into a join request have no workspace until an admin
+// approves it. They can't sign in while it's pending, `request` is the status
+// of their latest request. Once it's declined they sign in without a
+// workspace, so they can create one or join through a link.
+pub fn sign_in_error(
+    has_workspace: bool,
+    request: Option<JoinRequestStatus>,
+) -> Option<&'static str> {
+    if has_workspace {
+        return None;
+    }
+    match request {
+        Some(JoinRequestStatus::UNVERIFIED) | Some(JoinRequestStatus::PENDING) => {
+            Some(JOIN_REQUEST_PENDING)
+        }
+        Some(JoinRequestStatus::APPROVED) | Some(JoinRequestStatus::REJECTED) | None => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_email_domain() {
+        assert_eq!(email_domain("Jane@Acme.COM").as_deref(), Some("acme.com"));
+        assert_eq!(email_domain(" jane@acme.com. ").as_deref(), Some("acme.com"));
+        assert_eq!(email_domain("jane@"), None);
+        assert_eq!(email_domain("jane"), None);
+    }
+
+    #[test]
+    fn test_pending_user_sign_in() {
+        assert_eq!(
+            sign_in_error(false, Some(JoinRequestStatus::PENDING)),
+            Some(JOIN_REQUEST_PENDING)
+        );
+        assert_eq!(
+            sign_in_error(false, Some(JoinRequestStatus::UNVERIFIED)),
+            Some(JOIN_REQUEST_PENDING)
+        );
+        // approved requests come with a workspace, a rejected request doesn't
+        // matter once the user belongs somewhere
+        assert_eq!(sign_in_error(true, Some(JoinRequestStatus::REJECTED)), None);
+        assert_eq!(sign_in_error(true, Some(JoinRequestStatus::APPROVED)), None);
+        assert_eq!(sign_in_error(false, None), None);
+    }
+
+    #[test]
+    fn test_rejected_user_signs_in_without_workspace() {
+        assert_eq!(sign_in_error(false, Some(JoinRequestStatus::REJECTED)), None);
+    }
+}
@@ -3,7 +3,7 @@ use serde::{Deserialize, Serialize};
 use sqlx::prelude::{FromRow, Type};
 use uuid::Uuid;
 
-#[derive(Serialize, Clone, Deserialize, Type, PartialEq)]
+#[derive(Serialize, Clone, Deserialize, Type, PartialEq, Debug)]
 #[sqlx(type_name = "MEMBER_STATUS")]
 pub enum MemberStatus {
     VERIFIED,
@@ -11,6 +11,22 @@ pub enum MemberStatus {
     DELETED,
 }
 
+impl MemberStatus {
+    // only verified members get past the auth middleware
+    pub fn is_active(&self) -> bool {
+        *self == MemberStatus::VERIFIED
+    }
+}
+
+// what came of adding a user to a workspace through an invitation or link
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum JoinOutcome {
+    JOINED,
+    ALREADY_MEMBER,
+    // the plan has no seat left
+    NO_SEAT,
+}
+
 #[derive(Serialize, Clone, Deserialize, Type, PartialEq, Debug)]
 #[sqlx(type_name = "MEMBER_ROLE")]
 pub enum MemberRole {
@@ -20,6 +36,23 @@ pub enum MemberRole {
     GUEST,
 }
 
+impl MemberRole {
+    // roles allowed to manage the workspace and its members
+    pub fn is_admin(&self) -> bool {
+        matches!(self, MemberRole::ADMIN | MemberRole::OWNER)
+    }
+
+    // only an owner can change another owner
+    pub fn can_manage(&self, target: &MemberRole) -> bool {
+        self.is_admin() && (*target != MemberRole::OWNER || *self == MemberRole::OWNER)
+    }
+
+    // ownership isn't handed out through role changes
+    pub fn is_assignable(&self) -> bool {
+        *self != MemberRole::OWNER
+    }
+}
+
 #[derive(Serialize, Clone, Deserialize, FromRow, sqlx::Encode)]
 pub struct MemberRecord {
     pub id: Uuid,
@@ -48,3 +81,99 @@ pub struct MemberData {
     pub created_at: Option<NaiveDateTime>,
     pub updated_at: Option<NaiveDateTime>,
 }
+
+pub const DEFAULT_MEMBERS_PER_PAGE: i64 = 25;
+pub const MAX_MEMBERS_PER_PAGE: i64 = 100;
+
+#[derive(Serialize, Deserialize)]
+pub struct MemberListQuery {
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+    // matched against email, first and last name
+    pub search: Option<String>,
+    pub status: Option<MemberStatus>,
+}
+
+impl MemberListQuery {
+    // page, page size and row offset with out of range values clamped
+    pub fn bounds(&self) -> (i64, i64, i64) {
+        let page = self.page.unwrap_or(1).max(1);
+        let per_page = self
+            .per_page
+            .unwrap_or(DEFAULT_MEMBERS_PER_PAGE)
+            .clamp(1, MAX_MEMBERS_PER_PAGE);
+        (page, per_page, (page - 1) * per_page)
+    }
+}
+
+#[derive(Serialize, Deserialize, FromRow, Clone)]
+pub struct MemberListItem {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub email: String,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub profile_picture: String,
+    pub role: MemberRole,
+    pub status: MemberStatus,
+    pub invited_by: Uuid,
+    pub role_granted_by: Option<Uuid>,
+    pub joined_at: NaiveDateTime,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct MemberPage {
+    pub members: Vec<MemberListItem>,
+    pub page: i64,
+    pub per_page: i64,
+    pub total: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ChangeRoleForm {
+    pub role: MemberRole,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(page: Option<i64>, per_page: Option<i64>) -> MemberListQuery {
+        MemberListQuery {
+            page,
+            per_page,
+            search: None,
+            status: None,
+        }
+    }
+
+    #[test]
+    fn test_can_manage() {
+        assert!(MemberRole::ADMIN.can_manage(&MemberRole::MEMBER));
+        assert!(MemberRole::ADMIN.can_manage(&MemberRole::ADMIN));
+        assert!(!MemberRole::ADMIN.can_manage(&MemberRole::OWNER));
+        assert!(MemberRole::OWNER.can_manage(&MemberRole::OWNER));
+        assert!(!MemberRole::MEMBER.can_manage(&MemberRole::GUEST));
+        assert!(!MemberRole::GUEST.can_manage(&MemberRole::GUEST));
+        assert!(!MemberRole::OWNER.is_assignable());
+        assert!(MemberRole::ADMIN.is_assignable());
+    }
+
+    #[test]
+    fn test_member_status() {
+        assert!(MemberStatus::VERIFIED.is_active());
+        assert!(!MemberStatus::SUSPENDED.is_active());
+        assert!(!MemberStatus::DELETED.is_active());
+    }
+
+    #[test]
+    fn test_page_bounds() {
+        assert_eq!(query(None, None).bounds(), (1, DEFAULT_MEMBERS_PER_PAGE, 0));
+        assert_eq!(query(Some(3), Some(10)).bounds(), (3, 10, 20));
+        assert_eq!(query(Some(0), Some(0)).bounds(), (1, 1, 0));
+        assert_eq!(
+            query(Some(-2), Some(1000)).bounds(),
+            (1, MAX_MEMBERS_PER_PAGE, 0)
+        );
+    }
+}
@@ -1,5 +1,10 @@
+use self::plans::Plans;
 use self::store::PgStore;
 use crate::darkscout::adapters::dsbreach::BreachClient;
+use crate::darkscout::adapters::dns::SystemDnsResolver;
+use crate::darkscout::adapters::http_fetcher::ReqwestFetcher;
+use crate::darkscout::adapters::sms::SmsClient;
+use crate::darkscout::adapters::webhooks::WebhookClient;
 use lettre::SmtpTransport;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
@@ -8,14 +13,21 @@ use tokio::io::AsyncReadExt;
 
 use super::adapters::ds_darkengine::api::DarkSearchClient;
 
+pub mod alerts;
 pub mod errors;
 pub mod invitations;
+pub mod join_requests;
 pub mod member;
+pub mod monitoring;
+pub mod plans;
+pub mod settings;
 pub mod store;
+pub mod usage;
 pub mod user;
 pub mod workspace;
 pub mod verified_emails;
 pub mod verified_domains;
+pub mod webhooks;
 
 pub mod auth;
 pub mod darkmonitor;
@@ -42,12 +54,77 @@ pub struct SmtpSettings {
     pub password: String,
 }
 
+#[derive(Clone, Deserialize, Serialize, Default)]
+pub struct AdminSettings {
+    // emails of the users allowed to call the /admin routes
+    pub super_admins: Vec<String>,
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy, Deserialize, Serialize, Default, Debug)]
+pub enum SmsBackend {
+    #[default]
+    console,
+    gateway,
+}
+
+#[derive(Clone, Deserialize, Serialize, Default)]
+#[serde(default)]
+pub struct SmsSettings {
+    pub backend: SmsBackend,
+    // console backend only, messages are appended to this file when set
+    pub file: String,
+    pub gateway_url: String,
+    pub api_key: String,
+    pub sender: String,
+}
+
+// background breach monitoring of the verified emails and domains, how
+// often each asset is scanned is set per plan
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MonitoringSettings {
+    pub enabled: bool,
+    // upstream calls allowed per minute, shared by every asset
+    pub lookups_per_minute: u32,
+    // assets claimed per tick by one instance
+    pub batch_size: i64,
+}
+
+impl Default for MonitoringSettings {
+    fn default() -> Self {
+        MonitoringSettings {
+            enabled: true,
+            lookups_per_minute: 30,
+            batch_size: 50,
+        }
+    }
+}
+
+// outgoing webhook deliveries
+#[derive(Clone, Deserialize, Serialize, Default)]
+#[serde(default)]
+pub struct WebhookSettings {
+    // local testing only, lets endpoints point at http://localhost
+    pub allow_loopback: bool,
+}
+
 #[derive(Clone, Deserialize, Serialize)]
 pub struct SettingsEnv {
     pub config: Settings,
     pub app: Application,
     pub jwt: JwtSettings,
     pub smtp: SmtpSettings,
+    #[serde(default)]
+    pub admin: AdminSettings,
+    #[serde(default)]
+    pub plans: Plans,
+    #[serde(default)]
+    pub sms: SmsSettings,
+    #[serde(default)]
+    pub monitoring: MonitoringSettings,
+    #[serde(default)]
+    pub webhooks: WebhookSettings,
 }
 
 impl SettingsEnv {
@@ -86,6 +163,10 @@ pub struct AppState {
     pub ds_provider: BreachClient,
     pub ds_darkengine_provider: DarkSearchClient,
     pub smtp_client: SmtpTransport,
+    pub sms_client: SmsClient,
+    pub dns_resolver: SystemDnsResolver,
+    pub http_fetcher: ReqwestFetcher,
+    pub webhook_client: WebhookClient,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -0,0 +1,163 @@
+use std::collections::HashSet;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::darkscout::types::usage::LookupTargetType;
+use crate::darkscout::types::workspace::WorkspaceType;
+
+// a verified email or domain leased for a background breach scan
+#[derive(Serialize, Deserialize, FromRow, Clone, Debug)]
+pub struct MonitoredAsset {
+    pub id: Uuid,
+    pub workspace: Uuid,
+    pub asset_type: LookupTargetType,
+    pub asset: String,
+    pub plan: WorkspaceType,
+    // false until a scan went through, the first one only records a baseline
+    pub has_baseline: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ScannedBreach {
+    pub name: String,
+    pub data_classes: Vec<String>,
+    // 0 and false when the provider only gave the name
+    pub pwn_count: i64,
+    pub is_sensitive: bool,
+}
+
+// the breaches found that aren't known for the asset yet, in the order the
+// provider returned them
+pub fn new_breaches(known: &[String], found: &[ScannedBreach]) -> Vec<ScannedBreach> {
+    let mut seen: HashSet<&str> = known.iter().map(|n| n.as_str()).collect();
+    found
+        .iter()
+        .filter(|b| seen.insert(b.name.as_str()))
+        .cloned()
+        .collect()
+}
+
+// what a lookup of a monitored asset found, kept as the snapshot of the day
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BreachSnapshot {
+    pub workspace: Uuid,
+    pub asset_type: LookupTargetType,
+    pub asset_id: Uuid,
+    pub asset: String,
+    pub breach_count: i32,
+    pub sensitive_count: i32,
+    pub total_records: i64,
+    pub breaches: Vec<String>,
+}
+
+impl BreachSnapshot {
+    pub fn new(
+        workspace: Uuid,
+        asset_type: LookupTargetType,
+        asset_id: Uuid,
+        asset: &str,
+        found: &[ScannedBreach],
+    ) -> Self {
+        let mut seen = HashSet::new();
+        let found: Vec<&ScannedBreach> = found
+            .iter()
+            .filter(|b| seen.insert(b.name.as_str()))
+            .collect();
+        BreachSnapshot {
+            workspace,
+            asset_type,
+            asset_id,
+            asset: String::from(asset),
+            breach_count: found.len() as i32,
+            sensitive_count: found.iter().filter(|b| b.is_sensitive).count() as i32,
+            total_records: found.iter().map(|b| b.pwn_count).sum(),
+            breaches: found.iter().map(|b| b.name.clone()).collect(),
+        }
+    }
+}
+
+// the numbers of an asset's snapshot, total_breaches counts every breach the
+// asset was seen in up to that day
+#[derive(Serialize, Deserialize, FromRow, Clone, Debug)]
+pub struct SnapshotTotals {
+    pub snapshot_date: NaiveDate,
+    pub breach_count: i32,
+    pub sensitive_count: i32,
+    pub total_records: i64,
+    pub total_breaches: i64,
+}
+
+#[derive(Serialize, Deserialize, FromRow, Clone, Debug)]
+pub struct BreachTimelineEntry {
+    pub breach_name: String,
+    pub first_seen: NaiveDate,
+    pub last_seen: NaiveDate,
+    // whether the latest lookup still listed it
+    pub still_listed: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BreachTimeline {
+    pub asset_id: Uuid,
+    pub asset: String,
+    pub breaches: Vec<BreachTimelineEntry>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breach(name: &str) -> ScannedBreach {
+        ScannedBreach {
+            name: String::from(name),
+            data_classes: Vec::new(),
+            pwn_count: 0,
+            is_sensitive: false,
+        }
+    }
+
+    #[test]
+    fn test_new_breaches() {
+        let known = vec![String::from("Adobe"), String::from("LinkedIn")];
+        let found = vec![
+            breach("LinkedIn"),
+            breach("Canva"),
+            breach("Canva"),
+            breach("Adobe"),
+        ];
+        let names: Vec<String> = new_breaches(&known, &found)
+            .into_iter()
+            .map(|b| b.name)
+            .collect();
+        assert_eq!(names, vec![String::from("Canva")]);
+        assert!(new_breaches(&known, &[breach("Adobe")]).is_empty());
+        assert!(new_breaches(&known, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_breach_snapshot() {
+        let mut adobe = breach("Adobe");
+        adobe.pwn_count = 150;
+        adobe.is_sensitive = true;
+        let mut canva = breach("Canva");
+        canva.pwn_count = 30;
+        let found = vec![adobe.clone(), canva, adobe];
+        let snapshot = BreachSnapshot::new(
+            Uuid::new_v4(),
+            LookupTargetType::EMAIL,
+            Uuid::new_v4(),
+            "a@example.com",
+            &found,
+        );
+        assert_eq!(snapshot.breach_count, 2);
+        assert_eq!(snapshot.sensitive_count, 1);
+        assert_eq!(snapshot.total_records, 180);
+        assert_eq!(
+            snapshot.breaches,
+            vec![String::from("Adobe"), String::from("Canva")]
+        );
+    }
+}
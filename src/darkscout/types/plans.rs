@@ -0,0 +1,187 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::Type;
+
+use crate::darkscout::types::workspace::WorkspaceType;
+
+// A limit that is left out of the config is unlimited
+#[derive(Clone, Deserialize, Serialize, Default, Debug)]
+pub struct PlanLimits {
+    pub daily_lookups: Option<i64>,
+    pub verified_emails: Option<i64>,
+    pub verified_domains: Option<i64>,
+    pub members: Option<i64>,
+    pub dark_search_pages: Option<i64>,
+    // emails and domains the plan may look up without verifying them, a
+    // domain also covers the emails on it
+    #[serde(default)]
+    pub lookup_exceptions: Vec<String>,
+    // hours between background breach scans of each verified asset, left
+    // out the plan isn't monitored
+    pub monitoring_interval_hours: Option<i32>,
+}
+
+#[derive(Clone, Deserialize, Serialize, Default)]
+pub struct Plans {
+    #[serde(rename = "B2C_FREE", default)]
+    pub b2c_free: PlanLimits,
+    #[serde(rename = "B2B_FREE", default)]
+    pub b2b_free: PlanLimits,
+    #[serde(rename = "B2B_PRO", default)]
+    pub b2b_pro: PlanLimits,
+    #[serde(rename = "B2C_PRO", default)]
+    pub b2c_pro: PlanLimits,
+    #[serde(rename = "B2B_ENTP", default)]
+    pub b2b_entp: PlanLimits,
+}
+
+pub const ALL_PLANS: [WorkspaceType; 5] = [
+    WorkspaceType::B2C_FREE,
+    WorkspaceType::B2B_FREE,
+    WorkspaceType::B2B_PRO,
+    WorkspaceType::B2C_PRO,
+    WorkspaceType::B2B_ENTP,
+];
+
+impl Plans {
+    pub fn limits(&self, plan: WorkspaceType) -> &PlanLimits {
+        match plan {
+            WorkspaceType::B2C_FREE => &self.b2c_free,
+            WorkspaceType::B2B_FREE => &self.b2b_free,
+            WorkspaceType::B2B_PRO => &self.b2b_pro,
+            WorkspaceType::B2C_PRO => &self.b2c_pro,
+            WorkspaceType::B2B_ENTP => &self.b2b_entp,
+        }
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Serialize, Deserialize, Clone, Copy, Type, PartialEq, Debug)]
+#[sqlx(type_name = "QUOTA")]
+pub enum Quota {
+    DAILY_LOOKUPS,
+    VERIFIED_EMAILS,
+    VERIFIED_DOMAINS,
+    MEMBERS,
+    DARK_SEARCH_PAGES,
+}
+
+pub const ALL_QUOTAS: [Quota; 5] = [
+    Quota::DAILY_LOOKUPS,
+    Quota::VERIFIED_EMAILS,
+    Quota::VERIFIED_DOMAINS,
+    Quota::MEMBERS,
+    Quota::DARK_SEARCH_PAGES,
+];
+
+impl Quota {
+    // daily quotas reset every day, the others cap how many rows can exist
+    pub fn is_daily(&self) -> bool {
+        matches!(self, Quota::DAILY_LOOKUPS | Quota::DARK_SEARCH_PAGES)
+    }
+
+    pub fn limit(&self, limits: &PlanLimits) -> Option<i64> {
+        match self {
+            Quota::DAILY_LOOKUPS => limits.daily_lookups,
+            Quota::VERIFIED_EMAILS => limits.verified_emails,
+            Quota::VERIFIED_DOMAINS => limits.verified_domains,
+            Quota::MEMBERS => limits.members,
+            Quota::DARK_SEARCH_PAGES => limits.dark_search_pages,
+        }
+    }
+
+    pub fn exceeded_msg(&self) -> &'static str {
+        match self {
+            Quota::DAILY_LOOKUPS => {
+                "Quota exceeded: your plan's daily lookup limit has been reached."
+            }
+            Quota::VERIFIED_EMAILS => {
+                "Quota exceeded: your plan doesn't allow more verified emails."
+            }
+            Quota::VERIFIED_DOMAINS => {
+                "Quota exceeded: your plan doesn't allow more verified domains."
+            }
+            Quota::MEMBERS => "Quota exceeded: your plan doesn't allow more members.",
+            Quota::DARK_SEARCH_PAGES => {
+                "Quota exceeded: your plan's daily dark search limit has been reached."
+            }
+        }
+    }
+}
+
+// whether `adding` more still fits when `used` are taken, None is unlimited
+pub fn has_room(limit: Option<i64>, used: i64, adding: i64) -> bool {
+    match limit {
+        None => true,
+        Some(limit) => used.saturating_add(adding) <= limit,
+    }
+}
+
+// The day a daily quota is counted against. Days roll over at midnight UTC
+// whatever the timezone of the database.
+pub fn quota_day(now: DateTime<Utc>) -> NaiveDate {
+    now.date_naive()
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct QuotaUsage {
+    pub quota: Quota,
+    pub used: i64,
+    pub limit: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct WorkspaceUsage {
+    pub plan: WorkspaceType,
+    pub usage: Vec<QuotaUsage>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_has_room() {
+        assert!(has_room(Some(5), 4, 1));
+        // reaching the limit exactly is allowed, going past it isn't
+        assert!(!has_room(Some(5), 5, 1));
+        assert!(!has_room(Some(5), 3, 3));
+        assert!(has_room(Some(5), 0, 5));
+        assert!(!has_room(Some(0), 0, 1));
+        assert!(has_room(None, i64::MAX, 1));
+    }
+
+    #[test]
+    fn test_quota_day_rolls_over_at_midnight_utc() {
+        let before = Utc.with_ymd_and_hms(2024, 9, 3, 23, 59, 59).unwrap();
+        let after = Utc.with_ymd_and_hms(2024, 9, 4, 0, 0, 0).unwrap();
+        assert_eq!(
+            quota_day(before),
+            NaiveDate::from_ymd_opt(2024, 9, 3).unwrap()
+        );
+        assert_eq!(
+            quota_day(after),
+            NaiveDate::from_ymd_opt(2024, 9, 4).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_unlimited_plans() {
+        let plans: Plans = toml::from_str(
+            r#"
+            [B2B_ENTP]
+            members = 500
+            "#,
+        )
+        .unwrap();
+        let limits = plans.limits(WorkspaceType::B2B_ENTP);
+        assert_eq!(Quota::MEMBERS.limit(limits), Some(500));
+        for quota in ALL_QUOTAS.iter().filter(|q| **q != Quota::MEMBERS) {
+            assert_eq!(quota.limit(limits), None);
+        }
+        // a plan left out of the config is unlimited altogether
+        let free = plans.limits(WorkspaceType::B2C_FREE);
+        assert!(ALL_QUOTAS.iter().all(|q| q.limit(free).is_none()));
+    }
+}
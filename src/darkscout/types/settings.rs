@@ -0,0 +1,258 @@
+use chrono::NaiveDateTime;
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::darkscout::types::workspace::WorkspaceSettings;
+
+pub enum SettingKind {
+    Bool,
+    Integer { min: i64, max: i64 },
+    Choice(&'static [&'static str]),
+    // an IANA time zone name such as Asia/Kathmandu
+    TimeZone,
+}
+
+pub struct SettingDefinition {
+    pub name: &'static str,
+    pub default: &'static str,
+    pub kind: SettingKind,
+    // false for the settings that only make sense for the whole workspace
+    pub member_scope: bool,
+}
+
+// timezone and date_format are how dates show up in the emails a member gets
+pub const SETTINGS_SCHEMA: [SettingDefinition; 6] = [
+    SettingDefinition {
+        name: "timezone",
+        default: "UTC",
+        kind: SettingKind::TimeZone,
+        member_scope: true,
+    },
+    SettingDefinition {
+        name: "date_format",
+        default: "YYYY-MM-DD",
+        kind: SettingKind::Choice(&["YYYY-MM-DD", "DD/MM/YYYY", "MM/DD/YYYY"]),
+        member_scope: true,
+    },
+    SettingDefinition {
+        name: "email_notifications",
+        default: "true",
+        kind: SettingKind::Bool,
+        member_scope: true,
+    },
+    SettingDefinition {
+        name: "alert_min_severity",
+        default: "LOW",
+        kind: SettingKind::Choice(&["LOW", "MEDIUM", "HIGH", "CRITICAL"]),
+        member_scope: true,
+    },
+    SettingDefinition {
+        name: "session_timeout_hours",
+        default: "24",
+        kind: SettingKind::Integer { min: 1, max: 168 },
+        member_scope: false,
+    },
+    SettingDefinition {
+        name: "fold_plus_addresses",
+        default: "false",
+        kind: SettingKind::Bool,
+        member_scope: false,
+    },
+];
+
+// the session_timeout_hours default, also used for tokens without a workspace
+pub const DEFAULT_SESSION_HOURS: i64 = 24;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum SettingSource {
+    DEFAULT,
+    WORKSPACE,
+    MEMBER,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EffectiveSetting {
+    pub name: String,
+    pub value: String,
+    pub source: SettingSource,
+}
+
+pub fn find_setting(name: &str) -> Option<&'static SettingDefinition> {
+    SETTINGS_SCHEMA.iter().find(|s| s.name == name)
+}
+
+// Returns the value the way it should be stored
+pub fn validate_setting(
+    name: &str,
+    value: &str,
+    member_scope: bool,
+) -> Result<String, &'static str> {
+    let Some(definition) = find_setting(name) else {
+        return Err("Unknown setting.");
+    };
+    if member_scope && !definition.member_scope {
+        return Err("This setting can only be changed for the whole workspace.");
+    }
+    let value = value.trim();
+    match definition.kind {
+        SettingKind::Bool => match value.to_lowercase().as_str() {
+            "true" => Ok(String::from("true")),
+            "false" => Ok(String::from("false")),
+            _ => Err("Setting value must be true or false."),
+        },
+        SettingKind::Integer { min, max } => match value.parse::<i64>() {
+            Ok(n) if n >= min && n <= max => Ok(n.to_string()),
+            _ => Err("Setting value is not a number in the allowed range."),
+        },
+        SettingKind::Choice(choices) => match choices.iter().find(|c| **c == value) {
+            Some(c) => Ok(c.to_string()),
+            None => Err("Setting value is not one of the allowed choices."),
+        },
+        SettingKind::TimeZone => match value.parse::<Tz>() {
+            Ok(tz) => Ok(tz.name().to_string()),
+            Err(_) => Err("Setting value is not a known time zone."),
+        },
+    }
+}
+
+// Formats a UTC timestamp in the time zone and date format of the settings
+pub fn format_local_time(at: NaiveDateTime, settings: &[EffectiveSetting]) -> String {
+    let value = |name: &str| {
+        settings
+            .iter()
+            .find(|s| s.name == name)
+            .map(|s| s.value.as_str())
+    };
+    let tz: Tz = value("timezone")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(Tz::UTC);
+    let format = match value("date_format") {
+        Some("DD/MM/YYYY") => "%d/%m/%Y %H:%M",
+        Some("MM/DD/YYYY") => "%m/%d/%Y %H:%M",
+        _ => "%Y-%m-%d %H:%M",
+    };
+    format!(
+        "{} {}",
+        at.and_utc().with_timezone(&tz).format(format),
+        tz.name()
+    )
+}
+
+// Layers the stored rows over the schema defaults. Member values override
+// workspace values, and rows of other members are ignored.
+pub fn resolve_settings(
+    rows: &[WorkspaceSettings],
+    member_id: Option<Uuid>,
+) -> Vec<EffectiveSetting> {
+    SETTINGS_SCHEMA
+        .iter()
+        .map(|definition| {
+            let stored = |member: Option<Uuid>| {
+                rows.iter()
+                    .find(|r| r.settings_name == definition.name && r.member_id == member)
+            };
+            let member_row = match member_id {
+                Some(id) if definition.member_scope => stored(Some(id)),
+                _ => None,
+            };
+            let (value, source) = match (member_row, stored(None)) {
+                (Some(r), _) => (r.settings_value.clone(), SettingSource::MEMBER),
+                (None, Some(r)) => (r.settings_value.clone(), SettingSource::WORKSPACE),
+                (None, None) => (definition.default.to_string(), SettingSource::DEFAULT),
+            };
+            EffectiveSetting {
+                name: definition.name.to_string(),
+                value,
+                source,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn row(member_id: Option<Uuid>, name: &str, value: &str) -> WorkspaceSettings {
+        WorkspaceSettings {
+            id: Uuid::new_v4(),
+            member_id,
+            workspace_id: Uuid::nil(),
+            settings_name: name.to_string(),
+            settings_value: value.to_string(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn value_of(settings: &[EffectiveSetting], name: &str) -> (String, SettingSource) {
+        let s = settings.iter().find(|s| s.name == name).unwrap();
+        (s.value.clone(), s.source)
+    }
+
+    #[test]
+    fn test_validate_setting() {
+        assert_eq!(
+            validate_setting("email_notifications", "TRUE", true),
+            Ok(String::from("true"))
+        );
+        assert!(validate_setting("date_format", "xx", true).is_err());
+        assert_eq!(
+            validate_setting("timezone", " Asia/Kathmandu ", true),
+            Ok(String::from("Asia/Kathmandu"))
+        );
+        assert!(validate_setting("timezone", "Mars/Olympus", true).is_err());
+        assert!(validate_setting("session_timeout_hours", "500", false).is_err());
+        assert!(validate_setting("fold_plus_addresses", "true", true).is_err());
+        assert!(validate_setting("unknown", "1", false).is_err());
+    }
+
+    #[test]
+    fn test_member_overrides_workspace() {
+        let me = Uuid::new_v4();
+        let rows = vec![
+            row(None, "date_format", "DD/MM/YYYY"),
+            row(Some(me), "date_format", "MM/DD/YYYY"),
+            row(None, "timezone", "Asia/Kathmandu"),
+            row(Some(Uuid::new_v4()), "timezone", "UTC"),
+        ];
+        let settings = resolve_settings(&rows, Some(me));
+        assert_eq!(
+            value_of(&settings, "date_format"),
+            (String::from("MM/DD/YYYY"), SettingSource::MEMBER)
+        );
+        assert_eq!(
+            value_of(&settings, "timezone"),
+            (String::from("Asia/Kathmandu"), SettingSource::WORKSPACE)
+        );
+        assert_eq!(
+            value_of(&settings, "email_notifications"),
+            (String::from("true"), SettingSource::DEFAULT)
+        );
+
+        let workspace_only = resolve_settings(&rows, None);
+        assert_eq!(
+            value_of(&workspace_only, "date_format"),
+            (String::from("DD/MM/YYYY"), SettingSource::WORKSPACE)
+        );
+    }
+
+    #[test]
+    fn test_format_local_time() {
+        let at = chrono::NaiveDate::from_ymd_opt(2024, 9, 30)
+            .unwrap()
+            .and_hms_opt(20, 0, 0)
+            .unwrap();
+        assert_eq!(format_local_time(at, &[]), "2024-09-30 20:00 UTC");
+        let rows = vec![
+            row(None, "timezone", "Asia/Kathmandu"),
+            row(None, "date_format", "DD/MM/YYYY"),
+        ];
+        assert_eq!(
+            format_local_time(at, &resolve_settings(&rows, None)),
+            "01/10/2024 01:45 Asia/Kathmandu"
+        );
+    }
+}
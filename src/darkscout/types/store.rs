@@ -1,9 +1,31 @@
+use crate::darkscout::types::alerts::{AlertRecipient, AlertStatus, BreachAlert};
 use crate::darkscout::types::auth::PasswordResetRequest;
-use crate::darkscout::types::invitations::{InvitationRecord, InvitationStatus};
-use crate::darkscout::types::member::MemberData;
-use crate::darkscout::types::verified_emails::VerifiedEmail;
-use crate::darkscout::types::workspace::{MemberWorkspace, MemberWorkspaceDetails};
-use crate::darkscout::types::verified_domains::VerifiedDomain;
+use crate::darkscout::types::invitations::{
+    InvitationRecord, InvitationStatus, InvitationSummary, InviteLink, NewInviteLink,
+};
+use crate::darkscout::types::join_requests::{JoinPolicy, JoinRequest, JoinRequestSummary};
+use crate::darkscout::types::member::{
+    JoinOutcome, MemberData, MemberListItem, MemberRole, MemberStatus,
+};
+use crate::darkscout::types::monitoring::{
+    BreachSnapshot, BreachTimelineEntry, MonitoredAsset, ScannedBreach, SnapshotTotals,
+};
+use crate::darkscout::types::plans::Quota;
+use crate::darkscout::types::usage::{LookupRefusal, LookupTargetType, UsageEvent, UsageRollup};
+use chrono::NaiveDate;
+use crate::darkscout::types::verified_emails::{
+    EmailConfirmation, VerifiedEmail, VerifiedEmailSummary,
+};
+use crate::darkscout::types::workspace::{
+    MemberWorkspace, MemberWorkspaceDetails, WorkspaceOrderForm, WorkspaceSettings, WorkspaceStatus,
+    WorkspaceType,
+};
+use crate::darkscout::types::webhooks::{
+    DeliveryStatus, DueDelivery, WebhookDelivery, WebhookEndpoint, WebhookEvent,
+};
+use crate::darkscout::types::verified_domains::{
+    DomainProofMethod, VerifiedDomain, VerifiedDomainRecord,
+};
 use mongodb::Client;
 use sqlx::{error::Error, Pool, Postgres};
 use std::sync::Arc;
@@ -33,6 +55,12 @@ pub struct PgStore {
     pub verified_emails: Arc<VerifiedEmailsRepo>,
     pub verified_domains: Arc<VerifiedDomainRepo>,
     pub auth: Arc<AuthRepo>,
+    pub usage: Arc<UsageRepo>,
+    pub settings: Arc<SettingsRepo>,
+    pub join_requests: Arc<JoinRequestRepo>,
+    pub monitoring: Arc<MonitoringRepo>,
+    pub alerts: Arc<AlertsRepo>,
+    pub webhooks: Arc<WebhooksRepo>,
 }
 
 #[derive(Clone)]
@@ -68,10 +96,40 @@ pub struct VerifiedDomainRepo {
     pub db: Pool<Postgres>,
 }
 
+#[derive(Clone)]
+pub struct JoinRequestRepo {
+    pub db: Pool<Postgres>,
+}
+
 #[derive(Clone)]
 pub struct AuthRepo {
     pub db: Pool<Postgres>,
 }
+
+#[derive(Clone)]
+pub struct UsageRepo {
+    pub db: Pool<Postgres>,
+}
+
+#[derive(Clone)]
+pub struct SettingsRepo {
+    pub db: Pool<Postgres>,
+}
+
+#[derive(Clone)]
+pub struct MonitoringRepo {
+    pub db: Pool<Postgres>,
+}
+
+#[derive(Clone)]
+pub struct AlertsRepo {
+    pub db: Pool<Postgres>,
+}
+
+#[derive(Clone)]
+pub struct WebhooksRepo {
+    pub db: Pool<Postgres>,
+}
 #[allow(async_fn_in_trait)]
 pub trait UserStore {
     async fn create_user(&self, user: &UserRecord) -> Result<Uuid, Error>;
@@ -84,6 +142,16 @@ pub trait UserStore {
         user_id: Uuid,
     ) -> Result<(), Error>;
     async fn update_user(&self, record: UserRecord) -> Result<(), Error>;
+    async fn update_user_phone(&self, user_id: Uuid, phone: String) -> Result<(), Error>;
+    async fn verify_user_phone(&self, user_id: Uuid, phone: &str) -> Result<(), Error>;
+    async fn is_user_verified(&self, user_id: Uuid) -> Result<bool, Error>;
+    async fn complete_onboarding(
+        &self,
+        user_id: Uuid,
+        password_hash: String,
+        first_name: String,
+        last_name: String,
+    ) -> Result<UserRecord, Error>;
 }
 
 #[allow(async_fn_in_trait)]
@@ -98,8 +166,31 @@ pub trait WorkspaceStore {
         user_id: Uuid,
     ) -> Result<Vec<Workspace>, sqlx::error::Error>;
     async fn get_user_workspaces(&self, user_id: Uuid) -> Result<Vec<MemberWorkspace>, Error>;
+    async fn update_member_workspace_details(
+        &self,
+        user_id: Uuid,
+        workspace_id: Uuid,
+        order: Option<i32>,
+        is_fav: Option<bool>,
+    ) -> Result<(), Error>;
+    async fn set_default_workspace(&self, user_id: Uuid, workspace_id: Uuid) -> Result<(), Error>;
+    async fn reorder_user_workspaces(
+        &self,
+        user_id: Uuid,
+        orders: Vec<WorkspaceOrderForm>,
+    ) -> Result<(), Error>;
+    async fn get_workspace(&self, workspace_id: Uuid) -> Result<Workspace, Error>;
     async fn get_member_workspace(&self, member_id: Uuid) -> Result<Workspace, sqlx::error::Error>;
     async fn update_workspace(&self, record: Workspace) -> Result<(), sqlx::error::Error>;
+    async fn get_workspace_status(&self, workspace_id: Uuid) -> Result<WorkspaceStatus, Error>;
+    async fn get_workspace_type(&self, workspace_id: Uuid) -> Result<WorkspaceType, Error>;
+    async fn update_workspace_status(
+        &self,
+        workspace_id: Uuid,
+        status: WorkspaceStatus,
+        reason: String,
+        changed_by: Uuid,
+    ) -> Result<(), Error>;
 }
 
 #[allow(async_fn_in_trait)]
@@ -112,31 +203,163 @@ pub trait InvitationsStore {
         status: InvitationStatus,
         id: Uuid,
     ) -> Result<(), Error>;
+    async fn get_invitation(&self, id: Uuid) -> Result<Invitation, Error>;
+    async fn has_pending_invitation(&self, workspace_id: Uuid, user_id: Uuid)
+        -> Result<bool, Error>;
+    // The member is only added while the plan has a seat for them, the
+    // invitation stays pending otherwise.
+    async fn accept_invitation(
+        &self,
+        invitation_id: Uuid,
+        member: MemberRecord,
+        details: MemberWorkspaceDetails,
+        member_limit: Option<i64>,
+    ) -> Result<JoinOutcome, Error>;
+    async fn list_workspace_invitations(
+        &self,
+        workspace_id: Uuid,
+    ) -> Result<Vec<InvitationSummary>, Error>;
+    async fn mark_invitation_sent(&self, id: Uuid) -> Result<(), Error>;
+    async fn mark_invitation_failed(&self, id: Uuid, error: String) -> Result<(), Error>;
+    async fn claim_invitation_resend(
+        &self,
+        workspace_id: Uuid,
+        id: Uuid,
+        msg: String,
+    ) -> Result<Option<Invitation>, Error>;
+    async fn revoke_invitation(&self, workspace_id: Uuid, id: Uuid) -> Result<(), Error>;
+    async fn get_phone_verification(&self, user_id: Uuid, phone: &str)
+        -> Result<VerificationData, Error>;
+    // Counts a guess against a verification code. None once the code has
+    // expired or ran out of attempts, otherwise whether the guess matched.
+    async fn check_verification_code(&self, id: Uuid, code: &str) -> Result<Option<bool>, Error>;
+    async fn create_invite_link(&self, link: NewInviteLink) -> Result<InviteLink, Error>;
+    async fn list_invite_links(&self, workspace_id: Uuid) -> Result<Vec<InviteLink>, Error>;
+    async fn revoke_invite_link(&self, workspace_id: Uuid, link_id: Uuid) -> Result<(), Error>;
+    async fn get_invite_link(&self, token: &str) -> Result<InviteLink, Error>;
+    // Takes one use of a live link and adds the member with its details and
+    // the redemption in one transaction. Err(RowNotFound) when the link was
+    // revoked, expired or used up in the meantime.
+    async fn redeem_invite_link(
+        &self,
+        link_id: Uuid,
+        member: MemberRecord,
+        details: MemberWorkspaceDetails,
+        member_limit: Option<i64>,
+    ) -> Result<JoinOutcome, Error>;
 }
 
 #[allow(async_fn_in_trait)]
 pub trait MemberStore {
     async fn create_new_member(&self, record: MemberRecord) -> Result<(), Error>;
-    async fn get_member_profile(&self, user_id: Uuid) -> Result<MemberData, Error>;
+    async fn get_member_profile(&self, user_id: Uuid, workspace_id: Uuid)
+        -> Result<MemberData, Error>;
+    async fn get_user_memberships(&self, user_id: Uuid) -> Result<Vec<MemberData>, Error>;
+    async fn get_workspace_member(&self, workspace_id: Uuid, member_id: Uuid)
+        -> Result<MemberData, Error>;
+    async fn get_member_access(&self, member_id: Uuid) -> Result<(MemberStatus, MemberRole), Error>;
+    async fn list_workspace_members(
+        &self,
+        workspace_id: Uuid,
+        search: Option<String>,
+        status: Option<MemberStatus>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<MemberListItem>, i64), Error>;
+    async fn update_member_role(
+        &self,
+        workspace_id: Uuid,
+        member_id: Uuid,
+        role: MemberRole,
+        granted_by: Uuid,
+    ) -> Result<(), Error>;
+    async fn update_member_status(
+        &self,
+        workspace_id: Uuid,
+        member_id: Uuid,
+        status: MemberStatus,
+    ) -> Result<(), Error>;
+    async fn remove_member(&self, workspace_id: Uuid, member_id: Uuid) -> Result<(), Error>;
     async fn update_member_profile_picture(
         &self,
         member_id: Uuid,
         url: String,
     ) -> Result<(), Error>;
-    async fn get_member_settings(&self, member_id: Uuid) -> Result<(), Error>;
+    async fn count_workspace_members(&self, workspace_id: Uuid) -> Result<i64, Error>;
 }
 
 
 #[allow(async_fn_in_trait)]
 pub trait VerifiedEmailsStore {
-    async fn add_verified_emails(&self, records: Vec<VerifiedEmail>)->Result<(), Error>;
-    async fn update_verified_email(&self, email: &str, is_verified: bool)->Result<(), Error>;
+    async fn add_verified_emails(&self, workspace_id: Uuid, records: Vec<VerifiedEmail>)
+        -> Result<Vec<VerifiedEmail>, Error>;
+    async fn update_verified_email(&self, workspace_id: Uuid, id: Uuid, is_verified: bool)
+        -> Result<(), Error>;
+    async fn count_workspace_emails(&self, workspace_id: Uuid) -> Result<i64, Error>;
+    async fn get_workspace_email_names(&self, workspace_id: Uuid) -> Result<Vec<String>, Error>;
+    async fn is_email_verified(&self, workspace_id: Uuid, email: &str) -> Result<bool, Error>;
+    async fn list_workspace_emails(
+        &self,
+        workspace_id: Uuid,
+        verified: Option<bool>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<VerifiedEmailSummary>, i64), Error>;
+    async fn get_workspace_email(&self, workspace_id: Uuid, id: Uuid)
+        -> Result<VerifiedEmailSummary, Error>;
+    async fn delete_workspace_email(&self, workspace_id: Uuid, id: Uuid) -> Result<(), Error>;
+    async fn restart_email_confirmation(
+        &self,
+        workspace_id: Uuid,
+        id: Uuid,
+        token: String,
+    ) -> Result<Option<VerifiedEmail>, Error>;
+    async fn get_email_confirmation(&self, token: &str) -> Result<EmailConfirmation, Error>;
+    async fn claim_address_send(&self, email: &str) -> Result<bool, Error>;
+    async fn mark_confirmation_sent(&self, id: Uuid) -> Result<(), Error>;
+    async fn mark_confirmation_failed(&self, id: Uuid, error: String) -> Result<(), Error>;
+    async fn claim_confirmation_resend(
+        &self,
+        workspace_id: Uuid,
+        id: Uuid,
+        token: String,
+    ) -> Result<Option<VerifiedEmail>, Error>;
 }
 
 #[allow(async_fn_in_trait)]
 pub trait VerifiedDomainsStore {
-    async fn add_verified_domains(&self, records: Vec<VerifiedDomain>)->Result<(), Error>;
-    async fn update_verified_domain(&self, domain: &str, is_verified: bool)->Result<(), Error>;
+    async fn add_verified_domains(&self, workspace_id: Uuid, records: Vec<VerifiedDomain>)
+        -> Result<Vec<VerifiedDomain>, Error>;
+    async fn update_verified_domain(
+        &self,
+        workspace_id: Uuid,
+        id: Uuid,
+        is_verified: bool,
+        method: Option<DomainProofMethod>,
+    ) -> Result<(), Error>;
+    async fn record_failed_domain_check(&self, workspace_id: Uuid, id: Uuid, error: String)
+        -> Result<i32, Error>;
+    async fn get_workspace_domain(&self, workspace_id: Uuid, id: Uuid)
+        -> Result<VerifiedDomainRecord, Error>;
+    async fn get_domains_due_for_recheck(&self, limit: i64)
+        -> Result<Vec<VerifiedDomainRecord>, Error>;
+    async fn count_workspace_domains(&self, workspace_id: Uuid) -> Result<i64, Error>;
+    async fn get_verified_domain_names(&self, workspace_id: Uuid) -> Result<Vec<String>, Error>;
+    async fn get_workspace_domain_names(&self, workspace_id: Uuid) -> Result<Vec<String>, Error>;
+    // the verified domain covering host, either host itself or one of its
+    // parents that includes subdomains
+    async fn find_covering_domain(&self, workspace_id: Uuid, host: &str, parents: &[String])
+        -> Result<Option<String>, Error>;
+    async fn set_domain_subdomains(&self, workspace_id: Uuid, id: Uuid, include_subdomains: bool)
+        -> Result<(), Error>;
+    async fn list_workspace_domains(
+        &self,
+        workspace_id: Uuid,
+        verified: Option<bool>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<VerifiedDomainRecord>, i64), Error>;
+    async fn delete_workspace_domain(&self, workspace_id: Uuid, id: Uuid) -> Result<(), Error>;
 }
 
 #[allow(async_fn_in_trait)]
@@ -152,8 +375,232 @@ pub trait AuthStore {
     ) -> Result<PasswordResetRequest, Error>;
 }
 
+#[allow(async_fn_in_trait)]
+pub trait UsageStore {
+    // Adds one to the counter of the day unless that would go over the
+    // limit. Returns false when the limit has already been reached.
+    async fn consume_daily_usage(
+        &self,
+        workspace_id: Uuid,
+        quota: Quota,
+        day: NaiveDate,
+        limit: Option<i64>,
+    ) -> Result<bool, Error>;
+    async fn get_daily_usage(
+        &self,
+        workspace_id: Uuid,
+        quota: Quota,
+        day: NaiveDate,
+    ) -> Result<i64, Error>;
+    async fn record_usage_event(&self, event: UsageEvent) -> Result<(), Error>;
+    async fn record_lookup_refusal(&self, refusal: LookupRefusal) -> Result<(), Error>;
+    // Recomputes the rollup rows of a day from the raw events, safe to re-run.
+    async fn rollup_usage_events(&self, day: NaiveDate) -> Result<(), Error>;
+    async fn get_usage_rollups(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+        workspace_id: Option<Uuid>,
+    ) -> Result<Vec<UsageRollup>, Error>;
+}
+
+#[allow(async_fn_in_trait)]
+pub trait SettingsStore {
+    // workspace wide rows, plus the rows of the member when one is given
+    async fn get_settings(
+        &self,
+        workspace_id: Uuid,
+        member_id: Option<Uuid>,
+    ) -> Result<Vec<WorkspaceSettings>, Error>;
+    async fn upsert_settings(
+        &self,
+        workspace_id: Uuid,
+        member_id: Option<Uuid>,
+        settings: Vec<(String, String)>,
+    ) -> Result<(), Error>;
+}
+
 #[allow(async_fn_in_trait)]
 pub trait CacheStore {
     async fn get(&self, key: &str) -> Result<(), Error>;
     // async fn set(&self, key: &str, value: _) -> Result<(), Error>;
 }
+
+#[allow(async_fn_in_trait)]
+pub trait JoinRequestsStore {
+    async fn get_join_policy(&self, workspace_id: Uuid) -> Result<JoinPolicy, Error>;
+    async fn set_join_policy(&self, workspace_id: Uuid, policy: JoinPolicy) -> Result<(), Error>;
+    async fn find_domain_workspace(&self, domain: &str, parents: &[String])
+        -> Result<Option<(Uuid, JoinPolicy)>, Error>;
+    async fn create_join_request(&self, request: JoinRequest) -> Result<(), Error>;
+    async fn confirm_join_request(&self, workspace_id: Uuid, user_id: Uuid)
+        -> Result<bool, Error>;
+    async fn list_join_requests(&self, workspace_id: Uuid)
+        -> Result<Vec<JoinRequestSummary>, Error>;
+    async fn get_join_request(&self, workspace_id: Uuid, request_id: Uuid)
+        -> Result<JoinRequest, Error>;
+    async fn get_latest_join_request(&self, user_id: Uuid) -> Result<JoinRequest, Error>;
+    async fn approve_join_request(
+        &self,
+        request_id: Uuid,
+        decided_by: Uuid,
+        member: MemberRecord,
+        details: MemberWorkspaceDetails,
+    ) -> Result<(), Error>;
+    async fn reject_join_request(&self, request_id: Uuid, decided_by: Uuid) -> Result<(), Error>;
+}
+
+#[allow(async_fn_in_trait)]
+pub trait MonitoringStore {
+    // Leases up to `limit` verified assets due a scan on one of the given
+    // plans by pushing their next scan out by `lease_minutes`. Rows another
+    // instance is claiming are skipped.
+    async fn claim_due_assets(
+        &self,
+        plans: Vec<String>,
+        limit: i64,
+        lease_minutes: i32,
+    ) -> Result<Vec<MonitoredAsset>, Error>;
+    // Stores what a scan found together with an alert per breach that is new
+    // for the asset, never any on its first scan. Returns the alerts created.
+    async fn record_asset_scan(
+        &self,
+        asset: &MonitoredAsset,
+        breaches: Vec<ScannedBreach>,
+        next_scan_minutes: i32,
+    ) -> Result<Vec<BreachAlert>, Error>;
+    async fn record_failed_asset_scan(
+        &self,
+        asset: &MonitoredAsset,
+        error: String,
+        next_scan_minutes: i32,
+    ) -> Result<(), Error>;
+    // Takes one provider lookup from the budget shared by every instance,
+    // false when the caller has to wait.
+    async fn take_scan_token(&self, lookups_per_minute: i32) -> Result<bool, Error>;
+    // stops every instance from scanning for a while after a rate limit
+    async fn pause_scans(&self, seconds: i64) -> Result<(), Error>;
+    // hands leased assets back to the next claim
+    async fn release_assets(&self, assets: &[MonitoredAsset]) -> Result<(), Error>;
+    // Remembers the dark search documents found for a target and returns the
+    // ones the workspace hadn't seen yet.
+    async fn record_dark_search_hits(
+        &self,
+        workspace_id: Uuid,
+        target: &str,
+        document_ids: Vec<String>,
+    ) -> Result<Vec<String>, Error>;
+    // the verified email or domain of the workspace matching a lookup target
+    async fn find_monitored_asset(
+        &self,
+        workspace_id: Uuid,
+        asset_type: LookupTargetType,
+        asset: &str,
+    ) -> Result<Uuid, Error>;
+    // merges into the asset's snapshot of today, if it has one already
+    async fn save_breach_snapshot(&self, snapshot: &BreachSnapshot) -> Result<(), Error>;
+    // the totals of the latest snapshot at least `days_back` days old
+    async fn get_snapshot_totals(
+        &self,
+        workspace_id: Uuid,
+        asset_id: Uuid,
+        days_back: i32,
+    ) -> Result<SnapshotTotals, Error>;
+    async fn get_breach_timeline(
+        &self,
+        workspace_id: Uuid,
+        asset_id: Uuid,
+    ) -> Result<Vec<BreachTimelineEntry>, Error>;
+}
+
+#[allow(async_fn_in_trait)]
+pub trait AlertsStore {
+    // Leases up to `limit` alerts whose emails and webhooks haven't gone out
+    // by pushing NOTIFY_AFTER out by `lease_minutes`.
+    async fn claim_unnotified_alerts(
+        &self,
+        limit: i64,
+        lease_minutes: i32,
+    ) -> Result<Vec<BreachAlert>, Error>;
+    async fn mark_alerts_notified(&self, ids: Vec<Uuid>) -> Result<(), Error>;
+    async fn list_breach_alerts(
+        &self,
+        workspace_id: Uuid,
+        status: Option<AlertStatus>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<BreachAlert>, i64), Error>;
+    async fn get_breach_alert(&self, workspace_id: Uuid, id: Uuid) -> Result<BreachAlert, Error>;
+    // RowNotFound when the alert is no longer in the `from` status
+    async fn update_alert_status(
+        &self,
+        workspace_id: Uuid,
+        id: Uuid,
+        from: AlertStatus,
+        to: AlertStatus,
+        member_id: Uuid,
+    ) -> Result<BreachAlert, Error>;
+    // the active members of the workspace with their email
+    async fn get_alert_recipients(&self, workspace_id: Uuid) -> Result<Vec<AlertRecipient>, Error>;
+}
+
+#[allow(async_fn_in_trait)]
+pub trait WebhooksStore {
+    async fn create_webhook_endpoint(&self, endpoint: &WebhookEndpoint) -> Result<(), Error>;
+    async fn list_webhook_endpoints(&self, workspace_id: Uuid) -> Result<Vec<WebhookEndpoint>, Error>;
+    async fn get_webhook_endpoint(&self, workspace_id: Uuid, id: Uuid) -> Result<WebhookEndpoint, Error>;
+    // None leaves a field as it is
+    async fn update_webhook_endpoint(
+        &self,
+        workspace_id: Uuid,
+        id: Uuid,
+        url: Option<String>,
+        events: Option<Vec<String>>,
+        enabled: Option<bool>,
+    ) -> Result<WebhookEndpoint, Error>;
+    async fn delete_webhook_endpoint(&self, workspace_id: Uuid, id: Uuid) -> Result<(), Error>;
+    // the current secret keeps signing for `grace_hours` next to the new one
+    async fn rotate_webhook_secret(
+        &self,
+        workspace_id: Uuid,
+        id: Uuid,
+        secret: String,
+        grace_hours: i32,
+    ) -> Result<WebhookEndpoint, Error>;
+    // Queues a delivery of the event to every enabled endpoint of the
+    // workspace subscribed to it, returns how many were queued.
+    async fn enqueue_webhook_event(
+        &self,
+        workspace_id: Uuid,
+        event: WebhookEvent,
+        event_id: Uuid,
+        payload: String,
+    ) -> Result<u64, Error>;
+    // Leases due deliveries of enabled endpoints for `lease_minutes` and
+    // counts the attempt. Locked rows are skipped so instances don't send
+    // the same delivery.
+    async fn claim_due_deliveries(
+        &self,
+        limit: i64,
+        lease_minutes: i32,
+    ) -> Result<Vec<DueDelivery>, Error>;
+    // `retry_minutes` schedules the next attempt of a PENDING delivery
+    async fn record_delivery_result(
+        &self,
+        id: Uuid,
+        status: DeliveryStatus,
+        response_status: Option<i32>,
+        error: Option<String>,
+        retry_minutes: i32,
+    ) -> Result<(), Error>;
+    async fn list_webhook_deliveries(
+        &self,
+        workspace_id: Uuid,
+        endpoint: Option<Uuid>,
+        status: Option<DeliveryStatus>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<WebhookDelivery>, i64), Error>;
+    // queues the payload of a past delivery again as a new delivery
+    async fn replay_webhook_delivery(&self, workspace_id: Uuid, id: Uuid) -> Result<WebhookDelivery, Error>;
+}
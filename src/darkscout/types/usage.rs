@@ -0,0 +1,76 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, Type};
+use uuid::Uuid;
+
+use crate::darkscout::adapters::CacheTier;
+
+#[allow(non_camel_case_types)]
+#[derive(Serialize, Deserialize, Clone, Copy, Type, PartialEq, Debug)]
+#[sqlx(type_name = "USAGE_PROVIDER")]
+pub enum UsageProvider {
+    DSBREACH,
+    DS_DARKENGINE,
+}
+
+#[derive(Serialize, Deserialize, Clone, FromRow)]
+pub struct UsageEvent {
+    pub id: Uuid,
+    pub workspace: Uuid,
+    pub member: Option<Uuid>,
+    pub user_id: Option<Uuid>,
+    pub query: String,
+    pub provider: UsageProvider,
+    pub tier: CacheTier,
+    pub latency_ms: i64,
+    pub succeeded: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, Clone, FromRow)]
+pub struct UsageRollup {
+    pub usage_date: NaiveDate,
+    pub workspace: Uuid,
+    pub user_id: Option<Uuid>,
+    pub provider: UsageProvider,
+    pub tier: CacheTier,
+    pub lookups: i64,
+    pub failed_lookups: i64,
+    pub total_latency_ms: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct UsageReportQuery {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub workspace: Option<Uuid>,
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Serialize, Deserialize, Clone, Copy, Type, PartialEq, Debug)]
+#[sqlx(type_name = "LOOKUP_TARGET")]
+pub enum LookupTargetType {
+    EMAIL,
+    DOMAIN,
+}
+
+// The target of a breach lookup as the lookup policy normalized and allowed
+// it. Handlers behind the policy look up this, never the raw path.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LookupTarget {
+    pub target_type: LookupTargetType,
+    pub value: String,
+}
+
+// a breach lookup the lookup policy refused
+#[derive(Serialize, Deserialize, Clone, FromRow)]
+pub struct LookupRefusal {
+    pub id: Uuid,
+    pub workspace: Uuid,
+    pub member: Option<Uuid>,
+    pub user_id: Uuid,
+    pub target_type: LookupTargetType,
+    pub target: String,
+    pub path: String,
+    pub created_at: DateTime<Utc>,
+}
@@ -1,6 +1,9 @@
-use crate::darkscout::types::member::MemberRole;
+use crate::darkscout::types::invitations::TransportType;
+use crate::darkscout::types::join_requests::JoinPolicy;
+use crate::darkscout::types::member::{MemberRole, MemberStatus};
 use crate::darkscout::types::workspace::MemberWorkspace;
 use crate::darkscout::utils::auth::encrypt_password;
+use crate::darkscout::utils::phone::normalize_phone;
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 use sqlx::{prelude::FromRow, Encode};
@@ -19,6 +22,9 @@ pub struct UserSignupForm {
     pub password: String,
     pub email: String,
     pub ip: Option<String>,
+    pub phone: Option<String>,
+    // EMAIL_CODE unless the verification code should go out by sms
+    pub transport: Option<TransportType>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -39,6 +45,9 @@ pub struct UserSignupResponseData {
     pub first_name: Option<String>,
     pub last_name: Option<String>,
     pub email: String,
+    pub workspace_id: Uuid,
+    // set when the email domain routed the user to an existing workspace
+    pub join_policy: Option<JoinPolicy>,
 }
 #[derive(Serialize, Deserialize)]
 pub struct UserSignupResponse {
@@ -52,6 +61,25 @@ pub struct VerifyUserForm {
     pub code: String,
 }
 
+// sent by invitees without a password to finish their account
+#[derive(Serialize, Deserialize)]
+pub struct OnboardingForm {
+    pub token: String,
+    pub password: String,
+    pub first_name: String,
+    pub last_name: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PhoneForm {
+    pub phone: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct VerifyPhoneForm {
+    pub code: String,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct LoginForm {
     pub email: String,
@@ -71,9 +99,20 @@ pub struct MemberProfile {
     pub updated_at: Option<NaiveDateTime>,
 }
 
+// role and status of the user in one of their workspaces
+#[derive(Serialize, Deserialize, Clone)]
+pub struct WorkspaceMembership {
+    pub workspace_id: Uuid,
+    pub member_id: Uuid,
+    pub role: MemberRole,
+    pub status: MemberStatus,
+    pub verified_domains: Vec<String>,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct LoginResponse {
     pub workspaces: Vec<MemberWorkspace>,
+    pub memberships: Vec<WorkspaceMembership>,
     pub auth: Auth,
     pub member_profile: Option<MemberProfile>,
 }
@@ -94,6 +133,8 @@ pub struct UserRecord {
     pub email: String,
     pub ip_str: String,
     pub password: Option<String>,
+    pub phone: Option<String>,
+    pub phone_verified: bool,
     pub created_at: Option<NaiveDateTime>,
     pub updated_at: Option<NaiveDateTime>,
 }
@@ -130,6 +171,8 @@ impl TryFrom<UserSignupForm> for UserRecord {
             email: value.email,
             ip_str: value.ip.unwrap_or_else(|| "NA".to_string()),
             password: Some(hashed),
+            phone: value.phone.as_deref().and_then(normalize_phone),
+            phone_verified: false,
             created_at: None,
             updated_at: None,
         });
@@ -1,23 +1,104 @@
-
+use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
-use sqlx::FromRow;
+use sqlx::{FromRow, Type};
+
+use crate::darkscout::utils::auth::generate_token;
+
+pub const DOMAIN_TOKEN_LEN: usize = 32;
+// verified domains are checked again once a day and lose their verification
+// after MAX_FAILED_DOMAIN_CHECKS checks in a row without the proof
+pub const DOMAIN_RECHECK_HOURS: i32 = 24;
+pub const MAX_FAILED_DOMAIN_CHECKS: i32 = 3;
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct AddVerifiedDomainsForm {
     pub domains: Vec<String>,
+    // lets example.com cover mail.example.com and eu.example.com
+    #[serde(default)]
+    pub include_subdomains: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DomainCoverageForm {
+    pub include_subdomains: bool,
 }
 
 #[derive(Serialize, Deserialize, FromRow, Clone)]
 pub struct VerifiedDomain {
     pub id: uuid::Uuid,
     pub domain: String,
-    pub added_by: uuid::Uuid,
+    pub added_by: Option<uuid::Uuid>,
+    pub verification_token: String,
+    pub include_subdomains: bool,
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Serialize, Deserialize, Clone, Copy, Type, PartialEq, Debug)]
+#[sqlx(type_name = "DOMAIN_PROOF")]
+pub enum DomainProofMethod {
+    DNS_TXT,
+    WELL_KNOWN_FILE,
+    META_TAG,
+}
+
+#[derive(Serialize, Deserialize, FromRow, Clone)]
+pub struct VerifiedDomainRecord {
+    pub id: uuid::Uuid,
+    pub workspace: uuid::Uuid,
+    pub domain: String,
+    pub verification_token: String,
+    pub is_verified: bool,
+    pub include_subdomains: bool,
+    pub verification_method: Option<DomainProofMethod>,
+    pub verified_at: Option<NaiveDateTime>,
+    pub last_checked_at: Option<NaiveDateTime>,
+    pub failed_checks: i32,
+    pub check_error: Option<String>,
+}
+
+// what the admin has to publish, any one of the three proves the domain
+#[derive(Serialize, Deserialize)]
+pub struct DomainChallenge {
+    pub domain: String,
+    pub token: String,
+    pub txt_record: String,
+    pub file_url: String,
+    pub file_content: String,
+    pub meta_tag: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct VerifiedDomainPage {
+    pub domains: Vec<VerifiedDomainRecord>,
+    pub page: i64,
+    pub per_page: i64,
+    pub total: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct AddVerifiedDomainsResponse {
+    pub added: Vec<VerifiedDomain>,
+    pub duplicates: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DomainVerificationResult {
+    pub verified: bool,
+    pub method: Option<DomainProofMethod>,
+    pub errors: Vec<String>,
 }
 
-pub fn parse_verified_domains(form: AddVerifiedDomainsForm, added_by: uuid::Uuid) -> Vec<VerifiedDomain> {
-    form.domains.into_iter().map(|domain| VerifiedDomain {
+// domains are expected to be normalized already
+pub fn parse_verified_domains(
+    domains: Vec<String>,
+    added_by: uuid::Uuid,
+    include_subdomains: bool,
+) -> Vec<VerifiedDomain> {
+    domains.into_iter().map(|domain| VerifiedDomain {
         id: uuid::Uuid::new_v4(),
         domain,
-        added_by
+        added_by: Some(added_by),
+        verification_token: generate_token(DOMAIN_TOKEN_LEN),
+        include_subdomains,
     }).collect()
 }
@@ -1,22 +1,149 @@
+use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
+use crate::darkscout::adapters::smtp_mailer::escape_html;
+use crate::darkscout::types::workspace::WorkspaceBranding;
+use crate::darkscout::utils::auth::generate_token;
+
+// confirmation links expire after VERIFIED_EMAIL_TTL_HOURS, admins can send a
+// new one every CONFIRMATION_RESEND_MINUTES up to MAX_CONFIRMATION_SENDS times.
+// An address gets at most MAX_CONFIRMATION_SENDS emails per
+// CONFIRMATION_SEND_WINDOW_HOURS however often it is removed and added again
+pub const VERIFIED_EMAIL_TTL_HOURS: i32 = 48;
+pub const CONFIRMATION_RESEND_MINUTES: i32 = 10;
+pub const MAX_CONFIRMATION_SENDS: i32 = 5;
+pub const CONFIRMATION_SEND_WINDOW_HOURS: i32 = 24;
+pub const CONFIRM_TOKEN_LEN: usize = 32;
+
+pub const DEFAULT_ASSETS_PER_PAGE: i64 = 25;
+pub const MAX_ASSETS_PER_PAGE: i64 = 100;
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct AddVerifiedEmailsForm {
     pub emails: Vec<String>,
 }
 
+// shared by the verified emails and domains lists
+#[derive(Serialize, Deserialize)]
+pub struct VerifiedAssetListQuery {
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+    pub verified: Option<bool>,
+}
+
+impl VerifiedAssetListQuery {
+    // (page, per_page)
+    pub fn pagination(&self) -> (i64, i64) {
+        let page = self.page.unwrap_or(1).max(1);
+        let per_page = self
+            .per_page
+            .unwrap_or(DEFAULT_ASSETS_PER_PAGE)
+            .clamp(1, MAX_ASSETS_PER_PAGE);
+        (page, per_page)
+    }
+}
+
 #[derive(Serialize, Deserialize, FromRow, Clone)]
 pub struct VerifiedEmail {
     pub id: uuid::Uuid,
     pub email: String,
-    pub added_by: uuid::Uuid,
+    // None once the member who added it is gone
+    pub added_by: Option<uuid::Uuid>,
+    // only ever sent to the address itself
+    #[serde(skip)]
+    pub confirm_token: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum VerifiedEmailStatus {
+    VERIFIED,
+    PENDING,
+    EXPIRED,
+    FAILED,
+}
+
+// a verified email as listed to the admins of a workspace
+#[derive(Serialize, Deserialize, FromRow, Clone)]
+pub struct VerifiedEmailSummary {
+    pub id: uuid::Uuid,
+    pub email: String,
+    pub is_verified: bool,
+    pub send_count: i32,
+    pub last_sent_at: Option<NaiveDateTime>,
+    pub token_expires_at: Option<NaiveDateTime>,
+    pub send_error: Option<String>,
+    pub verified_at: Option<NaiveDateTime>,
+    pub expired: bool,
+    #[sqlx(skip)]
+    pub status: Option<VerifiedEmailStatus>,
 }
 
-pub fn parse_verified_emails(form: AddVerifiedEmailsForm, added_by: uuid::Uuid) -> Vec<VerifiedEmail> {
-    form.emails.into_iter().map(|email| VerifiedEmail {
+impl VerifiedEmailSummary {
+    pub fn with_status(mut self) -> Self {
+        self.status = Some(if self.is_verified {
+            VerifiedEmailStatus::VERIFIED
+        } else if self.send_error.is_some() {
+            VerifiedEmailStatus::FAILED
+        } else if self.expired {
+            VerifiedEmailStatus::EXPIRED
+        } else {
+            VerifiedEmailStatus::PENDING
+        });
+        self
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct VerifiedEmailPage {
+    pub emails: Vec<VerifiedEmailSummary>,
+    pub page: i64,
+    pub per_page: i64,
+    pub total: i64,
+}
+
+// duplicates were already in the workspace or repeated in the request
+#[derive(Serialize, Deserialize)]
+pub struct AddVerifiedEmailsResponse {
+    pub added: Vec<VerifiedEmail>,
+    pub duplicates: Vec<String>,
+}
+
+// the row behind a confirmation link
+#[derive(FromRow, Clone)]
+pub struct EmailConfirmation {
+    pub id: uuid::Uuid,
+    pub workspace: uuid::Uuid,
+    pub email: String,
+    pub is_verified: bool,
+    pub expired: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ConfirmVerifiedEmailForm {
+    pub token: String,
+}
+
+// emails are expected to be normalized already
+pub fn parse_verified_emails(emails: Vec<String>, added_by: uuid::Uuid) -> Vec<VerifiedEmail> {
+    emails.into_iter().map(|email| VerifiedEmail {
         id: uuid::Uuid::new_v4(),
         email,
-        added_by
+        added_by: Some(added_by),
+        confirm_token: Some(generate_token(CONFIRM_TOKEN_LEN)),
     }).collect()
 }
+
+pub fn confirmation_email(link: &str, branding: &WorkspaceBranding) -> (String, String) {
+    (
+        format!("{}: Confirm your email for breach monitoring", branding.name),
+        format!(
+            "<p>This address was added for breach monitoring by {}.</p>\
+            <p><a href=\"{}\">Confirm that you own it</a></p>\
+            <p>The link expires in {} hours. Ignore this email if you don't want it monitored.</p>",
+            escape_html(&branding.name),
+            link,
+            VERIFIED_EMAIL_TTL_HOURS
+        ),
+    )
+}
@@ -0,0 +1,192 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, Type};
+use uuid::Uuid;
+
+pub const DEFAULT_DELIVERIES_PER_PAGE: i64 = 25;
+pub const MAX_DELIVERIES_PER_PAGE: i64 = 100;
+// a delivery is given up on after this many attempts
+pub const MAX_DELIVERY_ATTEMPTS: i32 = 8;
+// the longest wait between two attempts
+pub const MAX_RETRY_MINUTES: i32 = 6 * 60;
+// how long the old secret keeps signing after a rotation
+pub const SECRET_GRACE_HOURS: i32 = 24;
+pub const WEBHOOK_SECRET_LEN: usize = 40;
+
+#[allow(non_camel_case_types)]
+#[derive(Serialize, Deserialize, Clone, Copy, Type, PartialEq, Debug)]
+#[sqlx(type_name = "WEBHOOK_EVENT")]
+pub enum WebhookEvent {
+    BREACH_DETECTED,
+    DARK_SEARCH_HIT,
+    MEMBER_JOINED,
+}
+
+impl WebhookEvent {
+    // the name stored in WEBHOOK_ENDPOINTS.EVENTS
+    pub fn name(&self) -> &'static str {
+        match self {
+            WebhookEvent::BREACH_DETECTED => "BREACH_DETECTED",
+            WebhookEvent::DARK_SEARCH_HIT => "DARK_SEARCH_HIT",
+            WebhookEvent::MEMBER_JOINED => "MEMBER_JOINED",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Type, PartialEq, Debug)]
+#[sqlx(type_name = "WEBHOOK_DELIVERY_STATUS")]
+pub enum DeliveryStatus {
+    PENDING,
+    SUCCEEDED,
+    FAILED,
+}
+
+#[derive(Serialize, Deserialize, FromRow, Clone)]
+pub struct WebhookEndpoint {
+    pub id: Uuid,
+    pub workspace: Uuid,
+    pub url: String,
+    pub events: Vec<String>,
+    // only shown when the endpoint is created and when it is rotated
+    #[serde(skip)]
+    pub secret: String,
+    #[serde(skip)]
+    pub previous_secret: Option<String>,
+    pub previous_secret_expires_at: Option<NaiveDateTime>,
+    pub enabled: bool,
+    pub created_by: Option<Uuid>,
+    pub created_at: Option<NaiveDateTime>,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CreateWebhookForm {
+    pub url: String,
+    pub events: Vec<WebhookEvent>,
+}
+
+// fields left out keep their value
+#[derive(Serialize, Deserialize)]
+pub struct UpdateWebhookForm {
+    pub url: Option<String>,
+    pub events: Option<Vec<WebhookEvent>>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct WebhookEndpointSecret {
+    pub endpoint: WebhookEndpoint,
+    pub secret: String,
+}
+
+#[derive(Serialize, Deserialize, FromRow, Clone)]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub workspace: Uuid,
+    pub endpoint: Uuid,
+    pub event_id: Uuid,
+    pub event: WebhookEvent,
+    pub payload: String,
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    pub next_attempt_at: NaiveDateTime,
+    pub last_attempt_at: Option<NaiveDateTime>,
+    pub response_status: Option<i32>,
+    pub error: Option<String>,
+    pub replay_of: Option<Uuid>,
+    pub created_at: Option<NaiveDateTime>,
+}
+
+// a delivery claimed for sending, with what's needed to sign it
+#[derive(FromRow, Clone)]
+pub struct DueDelivery {
+    pub id: Uuid,
+    pub event_id: Uuid,
+    pub event: WebhookEvent,
+    pub payload: String,
+    // counting the attempt about to be made
+    pub attempts: i32,
+    pub url: String,
+    pub secret: String,
+    // set while the grace period of a rotation runs
+    pub previous_secret: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DeliveryListQuery {
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+    pub endpoint: Option<Uuid>,
+    pub status: Option<DeliveryStatus>,
+}
+
+impl DeliveryListQuery {
+    // (page, per_page)
+    pub fn pagination(&self) -> (i64, i64) {
+        let page = self.page.unwrap_or(1).max(1);
+        let per_page = self
+            .per_page
+            .unwrap_or(DEFAULT_DELIVERIES_PER_PAGE)
+            .clamp(1, MAX_DELIVERIES_PER_PAGE);
+        (page, per_page)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct WebhookDeliveryPage {
+    pub deliveries: Vec<WebhookDelivery>,
+    pub page: i64,
+    pub per_page: i64,
+    pub total: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct WebhookPayload {
+    pub id: Uuid,
+    pub event: WebhookEvent,
+    pub workspace: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub data: serde_json::Value,
+}
+
+// Minutes to wait after a failed attempt, doubling from one minute
+pub fn retry_delay_minutes(attempts: i32) -> i32 {
+    let exponent = (attempts - 1).clamp(0, 16) as u32;
+    2i32.pow(exponent).min(MAX_RETRY_MINUTES)
+}
+
+// subscriptions are stored by name, once each
+pub fn event_names(events: &[WebhookEvent]) -> Vec<String> {
+    let mut names: Vec<String> = events.iter().map(|e| e.name().to_string()).collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delay() {
+        assert_eq!(retry_delay_minutes(1), 1);
+        assert_eq!(retry_delay_minutes(2), 2);
+        assert_eq!(retry_delay_minutes(5), 16);
+        assert_eq!(retry_delay_minutes(20), MAX_RETRY_MINUTES);
+    }
+
+    #[test]
+    fn test_event_names() {
+        assert_eq!(
+            event_names(&[
+                WebhookEvent::MEMBER_JOINED,
+                WebhookEvent::BREACH_DETECTED,
+                WebhookEvent::MEMBER_JOINED,
+            ]),
+            vec![
+                String::from("BREACH_DETECTED"),
+                String::from("MEMBER_JOINED")
+            ]
+        );
+    }
+}
@@ -1,21 +1,88 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use sqlx::Type;
 use uuid::Uuid;
 
+use super::invitations::TransportType;
+use super::member::MemberRole;
 use super::user::UserRecord;
 
-#[derive(Serialize, Deserialize, sqlx::FromRow, sqlx::Encode, sqlx::Decode, Debug, Clone)]
+pub const DEFAULT_WORKSPACE_TITLE: &str = "default";
+
+#[derive(Serialize, Deserialize, sqlx::FromRow, Debug, Clone)]
 pub struct Workspace {
     pub id: Uuid,
     pub title: Option<String>,
     pub details: Option<String>,
     pub created_by: Uuid,
+    pub display_picture: Option<String>,
+    pub country_code: Option<String>,
+    pub country: Option<String>,
+}
+
+// name and logo put on the emails and reports of a workspace
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WorkspaceBranding {
+    pub name: String,
+    pub logo_url: Option<String>,
+}
+
+// used when a workspace has no name of its own or can't be loaded
+pub const DEFAULT_BRAND_NAME: &str = "DarkScout";
+
+impl Default for WorkspaceBranding {
+    fn default() -> Self {
+        WorkspaceBranding {
+            name: String::from(DEFAULT_BRAND_NAME),
+            logo_url: None,
+        }
+    }
+}
+
+impl From<&Workspace> for WorkspaceBranding {
+    fn from(w: &Workspace) -> Self {
+        let name = match w.title.as_deref() {
+            Some(t) if !t.trim().is_empty() && t != DEFAULT_WORKSPACE_TITLE => t.to_string(),
+            _ => String::from(DEFAULT_BRAND_NAME),
+        };
+        WorkspaceBranding {
+            name,
+            logo_url: w.display_picture.clone().filter(|p| !p.is_empty()),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Type, PartialEq, Debug)]
+#[sqlx(type_name = "WORKSPACE_STATUS")]
+pub enum WorkspaceStatus {
+    VERIFIED,
+    UNVERIFIED,
+    DISABLED,
+    SUSPENDED,
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Serialize, Deserialize, Clone, Copy, Type, PartialEq, Debug)]
+#[sqlx(type_name = "WORKSPACE_TYPE")]
+pub enum WorkspaceType {
+    B2C_FREE,
+    B2B_FREE,
+    B2B_PRO,
+    B2C_PRO,
+    B2B_ENTP,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct WorkspaceStatusForm {
+    pub status: WorkspaceStatus,
+    pub reason: String,
 }
 
-#[derive(Serialize, Deserialize, sqlx::FromRow, sqlx::Encode, sqlx::Decode, Debug, Clone)]
+// a row without member_id is the workspace wide value of a setting
+#[derive(Serialize, Deserialize, sqlx::FromRow, Debug, Clone)]
 pub struct WorkspaceSettings{
     pub id: Uuid,
-    pub member_id: Uuid,
+    pub member_id: Option<Uuid>,
     pub workspace_id: Uuid,
     pub settings_name: String,
     pub settings_value: String,
@@ -23,10 +90,15 @@ pub struct WorkspaceSettings{
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Serialize, Deserialize, Clone, Copy)]
-pub struct WorkspaceSettingsForm<'a> {
-    pub settings_name: & 'a str,
-    pub settings_value: & 'a str,
+#[derive(Serialize, Deserialize, Clone)]
+pub struct WorkspaceSettingsForm {
+    pub settings_name: String,
+    pub settings_value: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct UpdateSettingsForm {
+    pub settings: Vec<WorkspaceSettingsForm>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -36,21 +108,42 @@ pub struct EditWorkspaceForm {
     pub details: Option<String>,
     pub order: Option<i32>,
     pub display_picture: Option<String>,
+    pub country_code: Option<String>,
+    pub country: Option<String>,
     pub is_default: Option<bool>,
     pub is_fav: Option<bool>
 }
 
-#[derive(Serialize, Deserialize, sqlx::FromRow, sqlx::Encode,sqlx::Decode )]
+impl EditWorkspaceForm {
+    // order, favourite and default are the member's own preferences and can
+    // be set on any workspace they belong to, the rest needs an admin
+    pub fn edits_workspace(&self) -> bool {
+        self.title.is_some()
+            || self.details.is_some()
+            || self.display_picture.is_some()
+            || self.country_code.is_some()
+            || self.country.is_some()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
 pub struct WorkspaceForm {
     pub title: Option<String>,
     pub details: Option<String>,
+    pub display_picture: Option<String>,
+    pub country_code: Option<String>,
+    pub country: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct InvitationForm {
     pub email: String,
+    pub role: Option<MemberRole>,
     pub first_name: Option<String>,
     pub last_name: Option<String>,
+    pub phone: Option<String>,
+    // EMAIL_LINK unless the invitation should go out by sms as a PHONE_LINK
+    pub transport: Option<TransportType>,
 }
 #[derive(Serialize, Deserialize)]
 pub struct MemberInvitationsForm {
@@ -68,11 +161,11 @@ pub enum InvitationResultStatus {
 pub struct MemberInvitationResponseData {
     pub email: String,
     pub status: InvitationResultStatus,
+    pub reason: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct AcceptMemberInvitationForm {
-    pub user_id: Uuid,
     pub token: String,
 }
     
@@ -84,6 +177,8 @@ pub struct MemberWorkspace {
     pub created_by: Uuid,
     pub workspace_order: i32,
     pub is_default: bool,
+    #[serde(default)]
+    pub is_fav: bool,
     pub member_id: Uuid,
 }
 
@@ -93,7 +188,36 @@ pub struct MemberWorkspaceDetails {
     pub workspace_order: i32,
     pub is_default: bool,
     pub is_fav: bool,
-    pub member_id: Uuid
+    pub member_id: Uuid,
+    pub workspace_id: Uuid,
+    pub user_id: Uuid,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct WorkspaceOrderForm {
+    pub workspace_id: Uuid,
+    pub order: i32,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ReorderWorkspacesForm {
+    pub workspaces: Vec<WorkspaceOrderForm>,
+}
+
+// The workspace tokens are issued for: `workspace_id` when given, otherwise
+// the member's default. Workspaces come sorted by the member's order, the
+// first one is only a fallback for accounts whose default was removed.
+pub fn token_workspace(
+    workspaces: &[MemberWorkspace],
+    workspace_id: Option<Uuid>,
+) -> Option<&MemberWorkspace> {
+    match workspace_id {
+        Some(id) => workspaces.iter().find(|w| w.id == id),
+        None => workspaces
+            .iter()
+            .find(|w| w.is_default)
+            .or_else(|| workspaces.first()),
+    }
 }
 impl TryFrom<UserRecord> for Workspace {
     type Error = &'static str;
@@ -101,9 +225,58 @@ impl TryFrom<UserRecord> for Workspace {
     fn try_from(value: UserRecord) -> Result<Self, Self::Error> {
         return Ok(Workspace {
             id: Uuid::new_v4(),
-            title: Some(String::from("default")),
+            title: Some(String::from(DEFAULT_WORKSPACE_TITLE)),
             details: Some(format!("Default workspace for {:}", value.email)),
             created_by: value.id,
+            display_picture: None,
+            country_code: None,
+            country: None,
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn member_workspace(order: i32, is_default: bool) -> MemberWorkspace {
+        MemberWorkspace {
+            id: Uuid::new_v4(),
+            title: None,
+            details: None,
+            created_by: Uuid::new_v4(),
+            workspace_order: order,
+            is_default,
+            is_fav: false,
+            member_id: Uuid::new_v4(),
+        }
+    }
+
+    #[test]
+    fn test_token_workspace() {
+        let workspaces = vec![member_workspace(0, false), member_workspace(1, true)];
+        assert_eq!(token_workspace(&workspaces, None).unwrap().id, workspaces[1].id);
+        assert_eq!(
+            token_workspace(&workspaces, Some(workspaces[0].id)).unwrap().id,
+            workspaces[0].id
+        );
+        assert!(token_workspace(&workspaces, Some(Uuid::new_v4())).is_none());
+
+        let without_default = vec![member_workspace(0, false), member_workspace(1, false)];
+        assert_eq!(
+            token_workspace(&without_default, None).unwrap().id,
+            without_default[0].id
+        );
+        assert!(token_workspace(&[], None).is_none());
+    }
+
+    #[test]
+    fn test_edits_workspace() {
+        let body = format!(r#"{{"id":"{}","order":2,"is_fav":true}}"#, Uuid::new_v4());
+        let mut form: EditWorkspaceForm = serde_json::from_str(&body).unwrap();
+        form.is_default = Some(true);
+        assert!(!form.edits_workspace());
+        form.title = Some(String::from("Renamed"));
+        assert!(form.edits_workspace());
+    }
+}
@@ -0,0 +1,153 @@
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::darkscout::types::alerts::{breach_alert_email, AlertSeverity, BreachAlert};
+use crate::darkscout::types::settings::EffectiveSetting;
+use crate::darkscout::types::store::AlertsStore;
+use crate::darkscout::types::webhooks::WebhookEvent;
+use crate::darkscout::types::workspace::WorkspaceBranding;
+use crate::darkscout::types::AppState;
+use crate::darkscout::utils::emails::send_workspace_email;
+use crate::darkscout::utils::settings::effective_settings;
+use crate::darkscout::utils::webhooks::emit_webhook_event;
+
+const NOTIFY_BATCH_SIZE: i64 = 100;
+const NOTIFY_LEASE_MINUTES: i32 = 10;
+
+pub fn alerts_link(app_url: &str) -> String {
+    format!("{}/alerts", app_url.trim_end_matches('/'))
+}
+
+// The lowest severity a member wants emailed, None when they turned alert
+// emails off
+pub fn alert_email_threshold(settings: &[EffectiveSetting]) -> Option<AlertSeverity> {
+    let value = |name: &str| {
+        settings
+            .iter()
+            .find(|s| s.name == name)
+            .map(|s| s.value.as_str())
+    };
+    if value("email_notifications") == Some("false") {
+        return None;
+    }
+    Some(
+        value("alert_min_severity")
+            .and_then(AlertSeverity::from_setting)
+            .unwrap_or(AlertSeverity::LOW),
+    )
+}
+
+async fn email_alert_recipients(
+    state: &AppState,
+    workspace_id: Uuid,
+    asset: &str,
+    alerts: &[BreachAlert],
+) -> Result<(), sqlx::Error> {
+    let recipients = state.db.alerts.get_alert_recipients(workspace_id).await?;
+    let link = alerts_link(&state.settings.config.app_url);
+    for recipient in recipients {
+        let settings =
+            match effective_settings(state, workspace_id, Some(recipient.member_id)).await {
+                Ok(settings) => settings,
+                Err(e) => {
+                    tracing::debug!("Failed to load the settings of a recipient: {}", e);
+                    continue;
+                }
+            };
+        let Some(threshold) = alert_email_threshold(&settings) else {
+            continue;
+        };
+        let wanted: Vec<&BreachAlert> = alerts.iter().filter(|a| a.severity >= threshold).collect();
+        if wanted.is_empty() {
+            continue;
+        }
+        let render = |branding: &WorkspaceBranding| {
+            breach_alert_email(asset, &wanted, &link, &settings, branding)
+        };
+        if let Err(e) = send_workspace_email(state, workspace_id, recipient.email, render).await {
+            tracing::debug!("AlertDeliveryError : {}", e);
+        }
+    }
+    Ok(())
+}
+
+// Emails the members and calls the webhooks of the workspaces about the
+// stored alerts that haven't gone out yet. Alerts whose recipients couldn't
+// be loaded are retried once their lease is up.
+pub async fn notify_breach_alerts(state: &AppState) -> Result<(), sqlx::Error> {
+    let alerts = state
+        .db
+        .alerts
+        .claim_unnotified_alerts(NOTIFY_BATCH_SIZE, NOTIFY_LEASE_MINUTES)
+        .await?;
+    // one email per asset with all of its new breaches
+    let mut groups: Vec<Vec<BreachAlert>> = Vec::new();
+    for alert in alerts {
+        match groups.iter_mut().find(|g| g[0].asset_id == alert.asset_id) {
+            Some(group) => group.push(alert),
+            None => groups.push(vec![alert]),
+        }
+    }
+    for group in groups {
+        let (workspace_id, asset) = (group[0].workspace, group[0].asset.clone());
+        if let Err(e) = email_alert_recipients(state, workspace_id, &asset, &group).await {
+            tracing::debug!("Failed to email the alerts of {}: {}", asset, e);
+            continue;
+        }
+        for alert in group.iter() {
+            let data = json!({
+                "alert_id": alert.id,
+                "asset_type": alert.asset_type,
+                "asset": alert.asset,
+                "breach_name": alert.breach_name,
+                "data_classes": alert.data_classes,
+                "severity": alert.severity,
+            });
+            emit_webhook_event(&state.db, workspace_id, WebhookEvent::BREACH_DETECTED, data).await;
+        }
+        let ids = group.iter().map(|a| a.id).collect();
+        state.db.alerts.mark_alerts_notified(ids).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::darkscout::types::settings::SettingSource;
+
+    fn setting(name: &str, value: &str) -> EffectiveSetting {
+        EffectiveSetting {
+            name: String::from(name),
+            value: String::from(value),
+            source: SettingSource::MEMBER,
+        }
+    }
+
+    #[test]
+    fn test_alert_email_threshold() {
+        assert_eq!(
+            alert_email_threshold(&[
+                setting("email_notifications", "true"),
+                setting("alert_min_severity", "HIGH"),
+            ]),
+            Some(AlertSeverity::HIGH)
+        );
+        assert_eq!(
+            alert_email_threshold(&[
+                setting("email_notifications", "false"),
+                setting("alert_min_severity", "LOW"),
+            ]),
+            None
+        );
+        assert_eq!(alert_email_threshold(&[]), Some(AlertSeverity::LOW));
+    }
+
+    #[test]
+    fn test_alerts_link() {
+        assert_eq!(
+            alerts_link("https://app.darkscout.io/"),
+            "https://app.darkscout.io/alerts"
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use bcrypt::{hash, verify, BcryptError, DEFAULT_COST};
+use rand::distributions::Alphanumeric;
 use rand::Rng;
 
 pub fn encrypt_password(string: String) -> Result<String, BcryptError> {
@@ -8,6 +9,16 @@ pub fn encrypt_password(string: String) -> Result<String, BcryptError> {
     }
 }
 
+// bcrypt ignores everything after 72 bytes
+pub const PASSWORD_MIN_LENGTH: usize = 8;
+pub const PASSWORD_MAX_LENGTH: usize = 72;
+
+pub fn is_password_allowed(password: &str) -> bool {
+    (PASSWORD_MIN_LENGTH..=PASSWORD_MAX_LENGTH).contains(&password.len())
+        && password.chars().any(|c| c.is_alphabetic())
+        && password.chars().any(|c| c.is_ascii_digit())
+}
+
 pub fn verify_password(
     password_string: String,
     password_hash: String,
@@ -18,8 +29,31 @@ pub fn verify_password(
     }
 }
 
+// url safe random token for links that are shared around
+pub fn generate_token(len: usize) -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(len)
+        .map(char::from)
+        .collect()
+}
+
 pub fn generate_code() -> String {
     let mut rng = rand::thread_rng();
     let unique_code: u32 = rng.gen_range(100000..=999999);
     return format!("{unique_code}");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_password_policy() {
+        assert!(is_password_allowed("correct horse 1"));
+        assert!(!is_password_allowed("short1"));
+        assert!(!is_password_allowed("onlyletters"));
+        assert!(!is_password_allowed("1234567890"));
+        assert!(!is_password_allowed(&format!("a1{}", "x".repeat(71))));
+    }
+}
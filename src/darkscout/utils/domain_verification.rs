@@ -0,0 +1,230 @@
+use crate::darkscout::adapters::{DnsResolver, HttpFetcher};
+use crate::darkscout::types::verified_domains::{DomainChallenge, DomainProofMethod};
+
+pub const TXT_RECORD_PREFIX: &str = "darkscout-verification=";
+pub const VERIFICATION_FILE_PATH: &str = "/.well-known/darkscout-verification.txt";
+pub const META_TAG_NAME: &str = "darkscout-verification";
+
+pub struct DomainCheck {
+    pub method: Option<DomainProofMethod>,
+    // which checks didn't pass, for the admins. The underlying lookup and
+    // fetch errors are only logged, they could describe hosts we can reach.
+    pub errors: Vec<String>,
+}
+
+pub fn domain_challenge(domain: &str, token: &str) -> DomainChallenge {
+    DomainChallenge {
+        domain: domain.to_string(),
+        token: token.to_string(),
+        txt_record: format!("{}{}", TXT_RECORD_PREFIX, token),
+        file_url: format!("https://{}{}", domain, VERIFICATION_FILE_PATH),
+        file_content: token.to_string(),
+        meta_tag: format!(r#"<meta name="{}" content="{}">"#, META_TAG_NAME, token),
+    }
+}
+
+// value of a name="..." or name='...' attribute inside a single tag
+fn attr_value<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let lower = tag.to_ascii_lowercase();
+    let mut from = 0;
+    while let Some(pos) = lower[from..].find(name) {
+        let start = from + pos;
+        from = start + name.len();
+        let boundary = start == 0 || lower.as_bytes()[start - 1].is_ascii_whitespace();
+        let rest = lower[from..].trim_start();
+        if !boundary || !rest.starts_with('=') {
+            continue;
+        }
+        let value_start = tag.len() - rest[1..].trim_start().len();
+        let quote = tag[value_start..].chars().next()?;
+        if quote != '"' && quote != '\'' {
+            return tag[value_start..].split_whitespace().next();
+        }
+        let value = &tag[value_start + 1..];
+        return value.find(quote).map(|end| &value[..end]);
+    }
+    None
+}
+
+pub fn html_has_meta_token(html: &str, token: &str) -> bool {
+    let lower = html.to_ascii_lowercase();
+    let mut from = 0;
+    while let Some(pos) = lower[from..].find("<meta") {
+        let start = from + pos;
+        let end = lower[start..]
+            .find('>')
+            .map(|e| start + e)
+            .unwrap_or(lower.len());
+        let tag = &html[start + "<meta".len()..end];
+        if attr_value(tag, "name").map(|n| n.eq_ignore_ascii_case(META_TAG_NAME)) == Some(true)
+            && attr_value(tag, "content").map(str::trim) == Some(token)
+        {
+            return true;
+        }
+        from = end;
+    }
+    false
+}
+
+// Looks for the token in the dns, the well-known file and the home page, in
+// that order, and stops at the first proof found
+pub async fn find_domain_proof<R: DnsResolver, F: HttpFetcher>(
+    resolver: &R,
+    fetcher: &F,
+    domain: &str,
+    token: &str,
+) -> DomainCheck {
+    let mut errors = vec![];
+    let expected = format!("{}{}", TXT_RECORD_PREFIX, token);
+    match resolver.txt_records(domain).await {
+        Ok(records) if records.iter().any(|r| r.trim() == expected) => {
+            return DomainCheck {
+                method: Some(DomainProofMethod::DNS_TXT),
+                errors,
+            }
+        }
+        Ok(_) => errors.push(String::from("No matching TXT record")),
+        Err(e) => {
+            tracing::debug!("TXT lookup for {} failed {}", domain, e);
+            errors.push(String::from("No matching TXT record"))
+        }
+    }
+    let file_url = format!("https://{}{}", domain, VERIFICATION_FILE_PATH);
+    match fetcher.fetch_text(&file_url).await {
+        Ok(body) if body.lines().any(|l| l.trim() == token) => {
+            return DomainCheck {
+                method: Some(DomainProofMethod::WELL_KNOWN_FILE),
+                errors,
+            }
+        }
+        Ok(_) => errors.push(String::from("Verification file not found")),
+        Err(e) => {
+            tracing::debug!("Fetching {} failed {}", file_url, e);
+            errors.push(String::from("Verification file not found"))
+        }
+    }
+    match fetcher.fetch_text(&format!("https://{}/", domain)).await {
+        Ok(body) if html_has_meta_token(&body, token) => {
+            return DomainCheck {
+                method: Some(DomainProofMethod::META_TAG),
+                errors,
+            }
+        }
+        Ok(_) => errors.push(String::from("No matching meta tag on the home page")),
+        Err(e) => {
+            tracing::debug!("Fetching the home page of {} failed {}", domain, e);
+            errors.push(String::from("No matching meta tag on the home page"))
+        }
+    }
+    DomainCheck {
+        method: None,
+        errors,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    struct StubResolver(HashMap<String, Vec<String>>);
+
+    impl DnsResolver for StubResolver {
+        async fn txt_records(&self, name: &str) -> Result<Vec<String>, String> {
+            self.0
+                .get(name)
+                .cloned()
+                .ok_or_else(|| String::from("NXDOMAIN"))
+        }
+    }
+
+    struct StubFetcher(HashMap<String, String>);
+
+    impl HttpFetcher for StubFetcher {
+        async fn fetch_text(&self, url: &str) -> Result<String, String> {
+            self.0.get(url).cloned().ok_or_else(|| String::from("404"))
+        }
+    }
+
+    fn stubs(txt: &[(&str, &str)], pages: &[(&str, &str)]) -> (StubResolver, StubFetcher) {
+        let mut records: HashMap<String, Vec<String>> = HashMap::new();
+        for (name, value) in txt {
+            records
+                .entry(name.to_string())
+                .or_default()
+                .push(value.to_string());
+        }
+        let pages = pages
+            .iter()
+            .map(|(url, body)| (url.to_string(), body.to_string()))
+            .collect();
+        (StubResolver(records), StubFetcher(pages))
+    }
+
+    #[tokio::test]
+    async fn test_dns_txt_proof() {
+        let (resolver, fetcher) = stubs(
+            &[
+                ("acme.com", "v=spf1 -all"),
+                ("acme.com", "darkscout-verification=tok"),
+            ],
+            &[],
+        );
+        let check = find_domain_proof(&resolver, &fetcher, "acme.com", "tok").await;
+        assert_eq!(check.method, Some(DomainProofMethod::DNS_TXT));
+    }
+
+    #[tokio::test]
+    async fn test_well_known_file_proof() {
+        let (resolver, fetcher) = stubs(
+            &[],
+            &[(
+                "https://acme.com/.well-known/darkscout-verification.txt",
+                "tok\n",
+            )],
+        );
+        let check = find_domain_proof(&resolver, &fetcher, "acme.com", "tok").await;
+        assert_eq!(check.method, Some(DomainProofMethod::WELL_KNOWN_FILE));
+        assert_eq!(check.errors.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_meta_tag_proof() {
+        let html =
+            r#"<html><head><META content='tok' Name="darkscout-verification" /></head></html>"#;
+        let (resolver, fetcher) = stubs(&[], &[("https://acme.com/", html)]);
+        let check = find_domain_proof(&resolver, &fetcher, "acme.com", "tok").await;
+        assert_eq!(check.method, Some(DomainProofMethod::META_TAG));
+    }
+
+    #[tokio::test]
+    async fn test_missing_proof() {
+        let (resolver, fetcher) = stubs(
+            &[("acme.com", "darkscout-verification=other")],
+            &[
+                (
+                    "https://acme.com/.well-known/darkscout-verification.txt",
+                    "other",
+                ),
+                (
+                    "https://acme.com/",
+                    r#"<meta name="description" content="tok">"#,
+                ),
+            ],
+        );
+        let check = find_domain_proof(&resolver, &fetcher, "acme.com", "tok").await;
+        assert_eq!(check.method, None);
+        assert_eq!(check.errors.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_errors_stay_hidden() {
+        let (resolver, fetcher) = stubs(&[], &[]);
+        let check = find_domain_proof(&resolver, &fetcher, "acme.com", "tok").await;
+        assert_eq!(check.method, None);
+        assert!(check
+            .errors
+            .iter()
+            .all(|e| !e.contains("404") && !e.contains("NXDOMAIN")));
+    }
+}
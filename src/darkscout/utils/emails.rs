@@ -0,0 +1,45 @@
+use uuid::Uuid;
+
+use crate::darkscout::adapters::smtp_mailer::{brand_email, send_smtp_email};
+use crate::darkscout::types::store::WorkspaceStore;
+use crate::darkscout::types::workspace::WorkspaceBranding;
+use crate::darkscout::types::AppState;
+
+pub async fn workspace_branding(
+    state: &AppState,
+    workspace_id: Uuid,
+) -> Result<WorkspaceBranding, sqlx::Error> {
+    let workspace = state.db.workspace.get_workspace(workspace_id).await?;
+    Ok(WorkspaceBranding::from(&workspace))
+}
+
+// Sends an email on behalf of a workspace. `render` gets the branding of the
+// workspace and returns the subject and html content, which is then wrapped
+// with its name and logo. The default branding is used when the workspace
+// can't be loaded.
+pub async fn send_workspace_email<F>(
+    state: &AppState,
+    workspace_id: Uuid,
+    rx: String,
+    render: F,
+) -> Result<String, String>
+where
+    F: FnOnce(&WorkspaceBranding) -> (String, String),
+{
+    let branding = match workspace_branding(state, workspace_id).await {
+        Ok(branding) => branding,
+        Err(e) => {
+            tracing::debug!("Failed to load workspace branding {}", e);
+            WorkspaceBranding::default()
+        }
+    };
+    let (subject, content) = render(&branding);
+    send_smtp_email(
+        state.settings.smtp.username.clone(),
+        rx,
+        brand_email(content, &branding),
+        &subject,
+        &state.smtp_client,
+    )
+    .await
+}
@@ -0,0 +1,256 @@
+use chrono::Local;
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::darkscout::types::invitations::{
+    new_workspace_invitation, Invitation, InvitationClaims, TransportType, IMPORT_BATCH_INTERVAL_SECS,
+    IMPORT_BATCH_SIZE, INVITATION_TTL_HOURS,
+};
+use crate::darkscout::adapters::SmsProvider;
+use crate::darkscout::types::member::MemberRole;
+use crate::darkscout::types::store::{InvitationsStore, MemberStore, UserStore};
+use crate::darkscout::types::user::UserRecord;
+use crate::darkscout::types::workspace::InvitationForm;
+use crate::darkscout::types::AppState;
+use crate::darkscout::utils::emails::send_workspace_email;
+use crate::darkscout::utils::jwt::{decode_jwt, encode_jwt, TokenClaims};
+
+const INVITATION_SUBJECT: &str = "member-invitation";
+
+// Signs the token put on the accept link of a member invitation
+pub fn create_invitation_token(
+    invitation_id: Uuid,
+    workspace_id: Uuid,
+    jwt_secret: &str,
+) -> Result<String, jsonwebtoken::errors::Error> {
+    let ts = Local::now().to_utc().timestamp();
+    let claims = TokenClaims {
+        sub: INVITATION_SUBJECT.to_string(),
+        iat: ts as usize,
+        exp: (ts + INVITATION_TTL_HOURS * 60 * 60) as usize,
+        payload: InvitationClaims {
+            invitation_id,
+            workspace_id,
+        },
+    };
+    encode_jwt(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(jwt_secret.as_bytes()),
+    )
+}
+
+// Fails for tampered, expired or non invitation tokens
+pub fn decode_invitation_token(
+    token: &str,
+    jwt_secret: &str,
+) -> Result<InvitationClaims, jsonwebtoken::errors::Error> {
+    let mut validation = Validation::new(Algorithm::HS256);
+    validation.sub = Some(INVITATION_SUBJECT.to_string());
+    let data = decode_jwt::<TokenClaims<InvitationClaims>>(
+        token.to_string(),
+        &DecodingKey::from_secret(jwt_secret.as_bytes()),
+        &validation,
+    )?;
+    Ok(data.claims.payload)
+}
+
+pub fn invitation_accept_link(app_url: &str, token: &str) -> String {
+    format!(
+        "{}/invitations/accept?token={}",
+        app_url.trim_end_matches('/'),
+        token
+    )
+}
+
+pub fn invite_link_url(app_url: &str, token: &str) -> String {
+    format!("{}/join?token={}", app_url.trim_end_matches('/'), token)
+}
+
+pub enum InvitationError {
+    Token(jsonwebtoken::errors::Error),
+    Db(sqlx::Error),
+}
+
+impl From<sqlx::Error> for InvitationError {
+    fn from(e: sqlx::Error) -> Self {
+        InvitationError::Db(e)
+    }
+}
+
+impl std::fmt::Display for InvitationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InvitationError::Token(e) => write!(f, "InvitationTokenError: {}", e),
+            InvitationError::Db(e) => write!(f, "InvitationDbError: {}", e),
+        }
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub enum InviteeCheck {
+    New,
+    AlreadyMember,
+    AlreadyInvited,
+}
+
+// Tells whether an email can be invited to the workspace, nothing is created
+pub async fn check_invitee(
+    state: &AppState,
+    workspace_id: Uuid,
+    email: &str,
+) -> Result<InviteeCheck, sqlx::Error> {
+    let user = match state.db.user.get_by_email(email.to_string()).await {
+        Ok(u) => u,
+        Err(sqlx::Error::RowNotFound) => return Ok(InviteeCheck::New),
+        Err(e) => return Err(e),
+    };
+    if state
+        .db
+        .member
+        .get_member_profile(user.id, workspace_id)
+        .await
+        .is_ok()
+    {
+        return Ok(InviteeCheck::AlreadyMember);
+    }
+    if state
+        .db
+        .invitation
+        .has_pending_invitation(workspace_id, user.id)
+        .await?
+    {
+        return Ok(InviteeCheck::AlreadyInvited);
+    }
+    Ok(InviteeCheck::New)
+}
+
+// Finds the account of an invitee or creates one without a password, the
+// invitee picks it after accepting
+async fn get_or_create_invitee(
+    state: &AppState,
+    invitee: &InvitationForm,
+) -> Result<UserRecord, sqlx::Error> {
+    match state.db.user.get_by_email(invitee.email.clone()).await {
+        Ok(user) => Ok(user),
+        Err(sqlx::Error::RowNotFound) => {
+            let user = UserRecord {
+                id: Uuid::new_v4(),
+                first_name: invitee.first_name.clone(),
+                created_at: None,
+                updated_at: None,
+                last_name: invitee.last_name.clone(),
+                username: invitee.email.clone(),
+                email: invitee.email.clone(),
+                ip_str: "".to_string(),
+                password: None,
+                phone: invitee.phone.clone(),
+                phone_verified: false,
+            };
+            state.db.user.create_user(&user).await?;
+            Ok(user)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+// Stores a QUEUED invitation with its signed accept link, the caller
+// delivers it. The invitee email is expected to be normalized already.
+pub async fn create_member_invitation(
+    state: &AppState,
+    inviter: Uuid,
+    workspace_id: Uuid,
+    workspace_name: &str,
+    invitee: &InvitationForm,
+    role: MemberRole,
+) -> Result<Invitation, InvitationError> {
+    let user = get_or_create_invitee(state, invitee).await?;
+    let id = Uuid::new_v4();
+    let token = create_invitation_token(id, workspace_id, &state.settings.jwt.jwt_secret)
+        .map_err(InvitationError::Token)?;
+    let invitation = new_workspace_invitation(
+        id,
+        inviter,
+        invitee.first_name.clone(),
+        workspace_name.to_string(),
+        invitee.email.clone(),
+        workspace_id,
+        user.id,
+        role,
+        invitation_accept_link(&state.settings.config.app_url, &token),
+        invitee.transport.unwrap_or(TransportType::EMAIL_LINK),
+        invitee.phone.clone(),
+    );
+    state.db.invitation.create_new(invitation.clone()).await?;
+    Ok(invitation)
+}
+
+// Sends the message of an invitation or verification code over its transport
+pub async fn send_invitation_message(state: &AppState, invitation: &Invitation) -> Result<(), String> {
+    if invitation.transport.is_phone() {
+        let Some(phone) = invitation.phone.as_deref() else {
+            return Err(String::from("No phone number to send the sms to"));
+        };
+        return state.sms_client.send_sms(phone, &invitation.msg).await;
+    }
+    // rendered with the workspace name when the invitation was created
+    send_workspace_email(state, invitation.workspace, invitation.email.clone(), |_| {
+        (invitation.subject.clone(), invitation.msg.clone())
+    })
+    .await
+    .map(|_| ())
+}
+
+// Sends the invitation and records whether the provider took it
+pub async fn deliver_invitation(state: &AppState, invitation: Invitation) {
+    let result = send_invitation_message(state, &invitation).await;
+    let updated = match result {
+        Ok(_) => {
+            state
+                .db
+                .invitation
+                .mark_invitation_sent(invitation.id)
+                .await
+        }
+        Err(e) => {
+            tracing::debug!("InvitationDeliveryError : {}", e);
+            state
+                .db
+                .invitation
+                .mark_invitation_failed(invitation.id, e)
+                .await
+        }
+    };
+    updated.unwrap_or_else(|e| tracing::debug!("UpdateInvitationFailed {}", e));
+}
+
+// Delivers imported invitations a batch at a time so a large import doesn't
+// hit the smtp sending limits
+pub async fn deliver_in_batches(state: AppState, invitations: Vec<Invitation>) {
+    let mut batches = invitations.chunks(IMPORT_BATCH_SIZE).peekable();
+    while let Some(batch) = batches.next() {
+        for invitation in batch {
+            deliver_invitation(&state, invitation.clone()).await;
+        }
+        if batches.peek().is_some() {
+            tokio::time::sleep(Duration::from_secs(IMPORT_BATCH_INTERVAL_SECS)).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invitation_token() {
+        let (invitation_id, workspace_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let token = create_invitation_token(invitation_id, workspace_id, "secret").unwrap();
+        let claims = decode_invitation_token(&token, "secret").unwrap();
+        assert_eq!(claims.invitation_id, invitation_id);
+        assert_eq!(claims.workspace_id, workspace_id);
+        assert!(decode_invitation_token(&token, "other-secret").is_err());
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::HashSet;
+
+use csv::{ReaderBuilder, Trim};
+use validator::ValidateEmail;
+
+use crate::darkscout::types::errors::{INVALID_IMPORT_FILE, TOO_MANY_IMPORT_ROWS};
+use crate::darkscout::types::invitations::{ImportRowStatus, MemberImportRow, MAX_IMPORT_ROWS};
+use crate::darkscout::types::member::MemberRole;
+
+// Column positions found in the header row, names are matched loosely so
+// "First Name", "first_name" and "firstname" all work
+struct ImportColumns {
+    email: usize,
+    first_name: Option<usize>,
+    last_name: Option<usize>,
+    role: Option<usize>,
+}
+
+fn column_key(header: &str) -> String {
+    header
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_lowercase()
+}
+
+fn find_columns(headers: &csv::StringRecord) -> Option<ImportColumns> {
+    let position = |names: &[&str]| {
+        headers
+            .iter()
+            .position(|h| names.contains(&column_key(h).as_str()))
+    };
+    Some(ImportColumns {
+        email: position(&["email", "emailaddress"])?,
+        first_name: position(&["firstname", "givenname"]),
+        last_name: position(&["lastname", "surname", "familyname"]),
+        role: position(&["role"]),
+    })
+}
+
+fn parse_role(value: &str) -> Result<Option<MemberRole>, ()> {
+    match value.to_uppercase().as_str() {
+        "" => Ok(None),
+        "ADMIN" => Ok(Some(MemberRole::ADMIN)),
+        "MEMBER" => Ok(Some(MemberRole::MEMBER)),
+        "GUEST" => Ok(Some(MemberRole::GUEST)),
+        // owners can't be invited
+        _ => Err(()),
+    }
+}
+
+fn optional(value: Option<&str>) -> Option<String> {
+    value.filter(|v| !v.is_empty()).map(String::from)
+}
+
+// Parses and validates an uploaded csv. Rows that pass get NEW_INVITE, the
+// caller still has to check them against the workspace.
+pub fn parse_member_csv(body: &str) -> Result<Vec<MemberImportRow>, &'static str> {
+    let mut reader = ReaderBuilder::new()
+        .trim(Trim::All)
+        .flexible(true)
+        .from_reader(body.as_bytes());
+    let headers = reader.headers().map_err(|_| INVALID_IMPORT_FILE)?.clone();
+    let columns = find_columns(&headers).ok_or(INVALID_IMPORT_FILE)?;
+
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut rows: Vec<MemberImportRow> = vec![];
+    for record in reader.records() {
+        let record = record.map_err(|_| INVALID_IMPORT_FILE)?;
+        if record.iter().all(|f| f.is_empty()) {
+            continue;
+        }
+        if rows.len() == MAX_IMPORT_ROWS {
+            return Err(TOO_MANY_IMPORT_ROWS);
+        }
+        let line = record.position().map(|p| p.line()).unwrap_or(0);
+        let email = record.get(columns.email).unwrap_or("").to_lowercase();
+        let role = parse_role(columns.role.and_then(|i| record.get(i)).unwrap_or(""));
+        let status = if !email.validate_email() {
+            ImportRowStatus::INVALID_EMAIL
+        } else if role.is_err() {
+            ImportRowStatus::INVALID_ROLE
+        } else if !seen.insert(email.clone()) {
+            ImportRowStatus::DUPLICATE
+        } else {
+            ImportRowStatus::NEW_INVITE
+        };
+        rows.push(MemberImportRow {
+            line,
+            email,
+            first_name: optional(columns.first_name.and_then(|i| record.get(i))),
+            last_name: optional(columns.last_name.and_then(|i| record.get(i))),
+            role: role.unwrap_or(None),
+            status,
+        });
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_member_csv() {
+        let body = "Email,First Name,Last Name,Role\n\
+            Jane@Acme.com,Jane,Doe,admin\n\
+            not-an-email,John,,\n\
+            jane@acme.com,Jane,Doe,\n\
+            bob@acme.com,,,owner\n\
+            \n\
+            alice@acme.com,Alice\n";
+        let rows = parse_member_csv(body).unwrap();
+        let statuses: Vec<ImportRowStatus> = rows.iter().map(|r| r.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ImportRowStatus::NEW_INVITE,
+                ImportRowStatus::INVALID_EMAIL,
+                ImportRowStatus::DUPLICATE,
+                ImportRowStatus::INVALID_ROLE,
+                ImportRowStatus::NEW_INVITE,
+            ]
+        );
+        assert_eq!(rows[0].email, "jane@acme.com");
+        assert_eq!(rows[0].role, Some(MemberRole::ADMIN));
+        assert_eq!(rows[4].first_name.as_deref(), Some("Alice"));
+        assert_eq!(rows[4].last_name, None);
+        assert!(parse_member_csv("name\njane").is_err());
+    }
+}
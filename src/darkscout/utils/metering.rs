@@ -0,0 +1,153 @@
+use std::time::Instant;
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::darkscout::adapters::CacheTier;
+use crate::darkscout::types::store::UsageStore;
+use crate::darkscout::types::usage::{UsageEvent, UsageProvider};
+use crate::darkscout::types::user::UserData;
+use crate::darkscout::types::workspace::MemberWorkspace;
+use crate::darkscout::types::AppState;
+
+// Times one provider lookup of a request, start it before calling the
+// provider and finish it with the outcome.
+pub struct LookupMeter {
+    workspace_id: Uuid,
+    member_id: Uuid,
+    user_id: Uuid,
+    provider: UsageProvider,
+    query: String,
+    started: Instant,
+}
+
+impl LookupMeter {
+    pub fn start(
+        workspace: &MemberWorkspace,
+        user: &UserData,
+        provider: UsageProvider,
+        query: &str,
+    ) -> Self {
+        LookupMeter {
+            workspace_id: workspace.id,
+            member_id: workspace.member_id,
+            user_id: user.id,
+            provider,
+            query: query.trim().to_lowercase().chars().take(255).collect(),
+            started: Instant::now(),
+        }
+    }
+
+    pub fn event(&self, tier: CacheTier, succeeded: bool) -> UsageEvent {
+        UsageEvent {
+            id: Uuid::new_v4(),
+            workspace: self.workspace_id,
+            member: Some(self.member_id),
+            user_id: Some(self.user_id),
+            query: self.query.clone(),
+            provider: self.provider,
+            tier,
+            latency_ms: self.started.elapsed().as_millis() as i64,
+            succeeded,
+            created_at: Utc::now(),
+        }
+    }
+
+    // Records the lookup in the background so metering never slows down or
+    // fails the request itself.
+    pub fn finish(self, state: &AppState, tier: CacheTier, succeeded: bool) {
+        let event = self.event(tier, succeeded);
+        let db = state.db.clone();
+        tokio::spawn(async move { save_lookup_event(&*db.usage, event).await });
+    }
+}
+
+pub async fn save_lookup_event(store: &impl UsageStore, event: UsageEvent) {
+    if let Err(e) = store.record_usage_event(event).await {
+        tracing::debug!("Failed to record usage event: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::darkscout::types::plans::Quota;
+    use crate::darkscout::types::usage::{LookupRefusal, UsageRollup};
+    use chrono::NaiveDate;
+    use sqlx::Error;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct MemoryUsage {
+        events: Mutex<Vec<UsageEvent>>,
+    }
+
+    impl UsageStore for MemoryUsage {
+        async fn consume_daily_usage(
+            &self,
+            _: Uuid,
+            _: Quota,
+            _: NaiveDate,
+            _: Option<i64>,
+        ) -> Result<bool, Error> {
+            Ok(Default::default())
+        }
+        async fn get_daily_usage(&self, _: Uuid, _: Quota, _: NaiveDate) -> Result<i64, Error> {
+            Ok(Default::default())
+        }
+        async fn record_usage_event(&self, event: UsageEvent) -> Result<(), Error> {
+            self.events.lock().unwrap().push(event);
+            Ok(())
+        }
+        async fn record_lookup_refusal(&self, _: LookupRefusal) -> Result<(), Error> {
+            Ok(())
+        }
+        async fn rollup_usage_events(&self, _: NaiveDate) -> Result<(), Error> {
+            Ok(())
+        }
+        async fn get_usage_rollups(
+            &self,
+            _: NaiveDate,
+            _: NaiveDate,
+            _: Option<Uuid>,
+        ) -> Result<Vec<UsageRollup>, Error> {
+            Ok(vec![])
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lookup_writes_one_event() {
+        let workspace = MemberWorkspace {
+            id: Uuid::new_v4(),
+            title: None,
+            details: None,
+            created_by: Uuid::new_v4(),
+            workspace_order: 0,
+            is_default: true,
+            is_fav: false,
+            member_id: Uuid::new_v4(),
+        };
+        let user = UserData {
+            id: Uuid::new_v4(),
+            first_name: None,
+            last_name: None,
+            username: String::from("jane"),
+            email: String::from("jane@acme.com"),
+        };
+        let store = MemoryUsage::default();
+        let meter =
+            LookupMeter::start(&workspace, &user, UsageProvider::DSBREACH, " Jane@Acme.com");
+        save_lookup_event(&store, meter.event(CacheTier::L2, true)).await;
+
+        let events = store.events.lock().unwrap();
+        assert_eq!(events.len(), 1);
+        let event = &events[0];
+        assert_eq!(event.workspace, workspace.id);
+        assert_eq!(event.member, Some(workspace.member_id));
+        assert_eq!(event.user_id, Some(user.id));
+        assert_eq!(event.query, "jane@acme.com");
+        assert_eq!(event.provider, UsageProvider::DSBREACH);
+        assert_eq!(event.tier, CacheTier::L2);
+        assert!(event.succeeded);
+    }
+}
@@ -1,7 +1,7 @@
 use axum::http::StatusCode;
 use axum::{
     body::Body,
-    extract::Request,
+    extract::{MatchedPath, Request, State},
     http,
     http::Response,
     middleware::Next,
@@ -11,13 +11,32 @@ use jsonwebtoken::{Algorithm, DecodingKey, Validation};
 use serde::{Deserialize, Serialize};
 
 use crate::darkscout::types::auth::LoggedInUserClaims;
+use crate::darkscout::types::store::MemberStore;
 use crate::darkscout::types::user::{MemberProfile, UserData};
 use crate::darkscout::types::workspace::MemberWorkspace;
+use crate::darkscout::types::AppState;
 use crate::darkscout::utils::jwt::{decode_jwt, TokenClaims};
 
 // type DSCode = StatusCode;
 
+// Routes that work with a token issued without a workspace, as registered on
+// the router. Everything else needs the workspace the token was issued for.
+const WORKSPACELESS_ROUTES: [&str; 6] = [
+    "/users/auth/reset-password/",
+    "/workspace/create/",
+    "/workspace/invite-links/redeem",
+    "/admin/workspaces/:workspace_id/status",
+    "/admin/usage",
+    "/admin/usage/export",
+];
+
+// `route` is the matched route, not the request path
+pub fn needs_workspace(route: &str) -> bool {
+    !WORKSPACELESS_ROUTES.contains(&route)
+}
+
 pub async fn authorization_middleware(
+    State(state): State<AppState>,
     mut req: Request,
     next: Next,
 ) -> Result<Response<Body>, StatusCode> {
@@ -37,7 +56,7 @@ pub async fn authorization_middleware(
 
     let token_data = match decode_jwt::<TokenClaims<LoggedInUserClaims>>(
         token.unwrap().to_string(),
-        &DecodingKey::from_secret("randomkeysuppose".as_bytes()),
+        &DecodingKey::from_secret(state.settings.jwt.jwt_secret.as_bytes()),
         &Validation::new(Algorithm::HS256),
     ) {
         Ok(data) => data,
@@ -59,7 +78,17 @@ pub async fn authorization_middleware(
             req.extensions_mut().insert(w);
         }
         None => {
-            tracing::debug!("DS-WARNING | No workspace found");
+            // users whose join request is pending or was declined, or who
+            // were removed from every workspace
+            let route = req
+                .extensions()
+                .get::<MatchedPath>()
+                .map(|p| p.as_str().to_string())
+                .unwrap_or_default();
+            if needs_workspace(&route) {
+                tracing::debug!("DS-10403 | No workspace found for {}", route);
+                return Err(StatusCode::FORBIDDEN);
+            }
         }
     };
     match token_data.claims.payload.member {
@@ -67,10 +96,46 @@ pub async fn authorization_middleware(
             tracing::debug!("DS-10404 | no member payload found");
             return Ok(next.run(req).await);
         }
-        Some(member) => {
-            dbg!("Member was found {:?}", member.clone());
+        Some(mut member) => {
+            // tokens outlive role changes, suspensions and removals, the
+            // member row is the source of truth
+            let (status, role) = match state.db.member.get_member_access(member.id).await {
+                Ok(access) => access,
+                Err(e) => {
+                    tracing::debug!("DS-10404 | member {} not found: {}", member.id, e);
+                    return Err(StatusCode::UNAUTHORIZED);
+                }
+            };
+            if !status.is_active() {
+                tracing::debug!("DS-10403 | {:?} member {} rejected", status, member.id);
+                return Err(StatusCode::UNAUTHORIZED);
+            }
+            member.role = role;
             req.extensions_mut().insert(member)
         }
     };
     Ok(next.run(req).await)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_needs_workspace() {
+        assert!(!needs_workspace("/workspace/create/"));
+        assert!(!needs_workspace("/admin/usage"));
+        assert!(!needs_workspace("/workspace/invite-links/redeem"));
+        assert!(needs_workspace("/users/edit/profile"));
+        assert!(needs_workspace("/workspace/members"));
+        assert!(needs_workspace(""));
+    }
+
+    #[test]
+    fn test_workspaceless_routes_are_registered() {
+        let source = include_str!("../../../webapi/mod.rs");
+        for route in WORKSPACELESS_ROUTES {
+            assert!(source.contains(&format!("\"{}\"", route)), "{}", route);
+        }
+    }
+}
@@ -0,0 +1,236 @@
+use std::collections::HashMap;
+
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::{
+    body::Body,
+    extract::{Path, Request, State},
+    http::Response,
+    middleware::Next,
+};
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::darkscout::types::errors::LOOKUP_NOT_ALLOWED;
+use crate::darkscout::types::store::{UsageStore, VerifiedEmailsStore};
+use crate::darkscout::types::usage::{LookupRefusal, LookupTarget, LookupTargetType};
+use crate::darkscout::types::user::UserData;
+use crate::darkscout::types::workspace::MemberWorkspace;
+use crate::darkscout::types::AppState;
+use crate::darkscout::utils::quotas::plan_limits;
+use crate::darkscout::utils::verified_assets::{
+    covering_verified_domain, normalize_domain, normalize_email,
+};
+use crate::darkscout::web::json_error;
+
+// The path param the dark monitor and dark search routes carry their target in
+pub fn lookup_target(params: &HashMap<String, String>) -> Option<(LookupTargetType, &str)> {
+    if let Some(email) = params.get("email") {
+        return Some((LookupTargetType::EMAIL, email.as_str()));
+    }
+    params
+        .get("domain")
+        .map(|domain| (LookupTargetType::DOMAIN, domain.as_str()))
+}
+
+pub fn normalize_target(target_type: LookupTargetType, target: &str) -> Option<String> {
+    match target_type {
+        LookupTargetType::EMAIL => normalize_email(target, false),
+        LookupTargetType::DOMAIN => normalize_domain(target),
+    }
+}
+
+// the domain an email lives on, a domain is its own
+pub fn target_domain(target_type: LookupTargetType, target: &str) -> &str {
+    match target_type {
+        LookupTargetType::EMAIL => target.rsplit_once('@').map_or(target, |(_, d)| d),
+        LookupTargetType::DOMAIN => target,
+    }
+}
+
+pub fn is_exempt(exceptions: &[String], target_type: LookupTargetType, target: &str) -> bool {
+    let domain = target_domain(target_type, target);
+    exceptions.iter().any(|exception| {
+        let exception = exception.trim().to_lowercase();
+        exception == target || exception == domain
+    })
+}
+
+async fn is_verified(
+    state: &AppState,
+    workspace_id: Uuid,
+    target_type: LookupTargetType,
+    target: &str,
+) -> Result<bool, sqlx::Error> {
+    // a verified domain covers every address on it, and the ones on its
+    // subdomains when it includes them
+    if target_type == LookupTargetType::EMAIL
+        && state
+            .db
+            .verified_emails
+            .is_email_verified(workspace_id, target)
+            .await?
+    {
+        return Ok(true);
+    }
+    let domain = target_domain(target_type, target);
+    Ok(covering_verified_domain(state, workspace_id, domain)
+        .await?
+        .is_some())
+}
+
+fn record_refusal(
+    state: &AppState,
+    workspace: &MemberWorkspace,
+    user: &UserData,
+    target_type: LookupTargetType,
+    target: &str,
+    path: &str,
+) {
+    let refusal = LookupRefusal {
+        id: Uuid::new_v4(),
+        workspace: workspace.id,
+        member: Some(workspace.member_id),
+        user_id: user.id,
+        target_type,
+        target: target.chars().take(255).collect(),
+        path: path.chars().take(512).collect(),
+        created_at: Utc::now(),
+    };
+    let db = state.db.clone();
+    tokio::spawn(async move {
+        if let Err(e) = db.usage.record_lookup_refusal(refusal).await {
+            tracing::debug!("Failed to record lookup refusal: {}", e);
+        }
+    });
+}
+
+fn refused() -> Response<Body> {
+    json_error::<&str>(Some(StatusCode::FORBIDDEN), Some(LOOKUP_NOT_ALLOWED)).into_response()
+}
+
+// Lets breach lookups through for the caller's own email, the emails and
+// domains verified in the active workspace and the exceptions of its plan.
+// Must be layered after the authorization middleware. Allowed requests carry
+// the normalized LookupTarget in their extensions.
+pub async fn lookup_policy_middleware(
+    State(state): State<AppState>,
+    Path(params): Path<HashMap<String, String>>,
+    mut req: Request,
+    next: Next,
+) -> Result<Response<Body>, StatusCode> {
+    let Some((target_type, raw_target)) = lookup_target(&params) else {
+        return Ok(next.run(req).await);
+    };
+    let (Some(user), Some(workspace)) = (
+        req.extensions().get::<UserData>(),
+        req.extensions().get::<MemberWorkspace>(),
+    ) else {
+        return Err(StatusCode::FORBIDDEN);
+    };
+    let path = req.uri().path();
+    let Some(target) = normalize_target(target_type, raw_target) else {
+        record_refusal(&state, workspace, user, target_type, raw_target, path);
+        return Ok(refused());
+    };
+    let allowed = if target_type == LookupTargetType::EMAIL
+        && normalize_email(&user.email, false).as_deref() == Some(target.as_str())
+    {
+        Ok(true)
+    } else {
+        match plan_limits(&state, workspace.id).await {
+            Ok((_, limits)) if is_exempt(&limits.lookup_exceptions, target_type, &target) => {
+                Ok(true)
+            }
+            Ok(_) => is_verified(&state, workspace.id, target_type, &target).await,
+            Err(e) => Err(e),
+        }
+    };
+    match allowed {
+        Ok(true) => {
+            req.extensions_mut().insert(LookupTarget {
+                target_type,
+                value: target,
+            });
+            Ok(next.run(req).await)
+        }
+        Ok(false) => {
+            tracing::debug!(
+                "DS-10403 | workspace {} refused lookup of {}",
+                workspace.id,
+                target
+            );
+            record_refusal(&state, workspace, user, target_type, &target, path);
+            Ok(refused())
+        }
+        Err(e) => {
+            tracing::debug!("DS-10500 | Failed to check the lookup policy: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_target() {
+        let mut params = HashMap::new();
+        params.insert(String::from("email"), String::from("a@b.com"));
+        assert_eq!(
+            lookup_target(&params),
+            Some((LookupTargetType::EMAIL, "a@b.com"))
+        );
+        params.clear();
+        params.insert(String::from("domain"), String::from("b.com"));
+        assert_eq!(
+            lookup_target(&params),
+            Some((LookupTargetType::DOMAIN, "b.com"))
+        );
+    }
+
+    #[test]
+    fn test_targets_cant_reshape_the_upstream_path() {
+        for target in [
+            "x/../../domains/victim.com#@mine.com",
+            "x/../victim%40other.com#@mine.com",
+        ] {
+            assert_eq!(normalize_target(LookupTargetType::EMAIL, target), None);
+        }
+        assert_eq!(
+            normalize_target(LookupTargetType::DOMAIN, "victim.com/../x"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_plan_exceptions() {
+        let exceptions = vec![String::from("Example.com"), String::from("ceo@acme.com")];
+        assert!(is_exempt(
+            &exceptions,
+            LookupTargetType::DOMAIN,
+            "example.com"
+        ));
+        assert!(is_exempt(
+            &exceptions,
+            LookupTargetType::EMAIL,
+            "john@example.com"
+        ));
+        assert!(is_exempt(
+            &exceptions,
+            LookupTargetType::EMAIL,
+            "ceo@acme.com"
+        ));
+        assert!(!is_exempt(
+            &exceptions,
+            LookupTargetType::EMAIL,
+            "cfo@acme.com"
+        ));
+        assert!(!is_exempt(
+            &exceptions,
+            LookupTargetType::DOMAIN,
+            "acme.com"
+        ));
+    }
+}
@@ -1 +1,4 @@
-pub mod jwt_auth;
\ No newline at end of file
+pub mod jwt_auth;
+pub mod lookup_policy;
+pub mod super_admin;
+pub mod workspace_status;
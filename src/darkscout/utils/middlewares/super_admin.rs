@@ -0,0 +1,33 @@
+use axum::http::StatusCode;
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::Response,
+    middleware::Next,
+};
+
+use crate::darkscout::types::user::UserData;
+use crate::darkscout::types::AppState;
+
+// Must be layered after the authorization middleware, it reads the
+// UserData that one puts into the request extensions.
+pub async fn super_admin_middleware(
+    State(state): State<AppState>,
+    req: Request,
+    next: Next,
+) -> Result<Response<Body>, StatusCode> {
+    let Some(user) = req.extensions().get::<UserData>() else {
+        return Err(StatusCode::UNAUTHORIZED);
+    };
+    let is_super_admin = state
+        .settings
+        .admin
+        .super_admins
+        .iter()
+        .any(|email| email.eq_ignore_ascii_case(&user.email));
+    if !is_super_admin {
+        tracing::debug!("DS-10403 | {} is not a super admin", user.email);
+        return Err(StatusCode::FORBIDDEN);
+    }
+    Ok(next.run(req).await)
+}
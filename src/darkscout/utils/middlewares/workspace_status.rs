@@ -0,0 +1,196 @@
+use axum::http::{Method, StatusCode};
+use axum::{
+    body::Body,
+    extract::{MatchedPath, Request, State},
+    http::Response,
+    middleware::Next,
+};
+
+use crate::darkscout::types::store::WorkspaceStore;
+use crate::darkscout::types::workspace::{MemberWorkspace, WorkspaceStatus};
+use crate::darkscout::types::AppState;
+
+// Routes an unverified workspace can still use to finish setting itself up,
+// as registered on the router. Anything added later stays blocked until it's
+// listed here.
+const UNVERIFIED_ALLOWED_ROUTES: [&str; 19] = [
+    "/users/auth/reset-password/",
+    "/users/edit/profile",
+    "/users/phone",
+    "/users/phone/verify",
+    "/workspace/create/",
+    "/workspace/edit/:workspace_id",
+    "/workspace/reorder",
+    "/workspace/settings",
+    "/workspace/verified-emails",
+    "/workspace/verified-emails/:email_id/resend",
+    "/workspace/verified-emails/:email_id/reverify",
+    "/workspace/verified-emails/:email_id/delete",
+    "/workspace/verified-domains",
+    "/workspace/verified-domains/:domain_id/challenge",
+    "/workspace/verified-domains/:domain_id/verify",
+    "/workspace/verified-domains/:domain_id/reverify",
+    "/workspace/verified-domains/:domain_id/subdomains",
+    "/workspace/verified-domains/:domain_id/delete",
+    "/settings/:member_id",
+];
+
+// Routes that act on another workspace than the caller's current one, so its
+// status doesn't apply. Joining through a link is how users leave an
+// unverified or suspended workspace behind.
+const STATUS_EXEMPT_ROUTES: [&str; 1] = ["/workspace/invite-links/redeem"];
+
+// `route` is the matched route, not the request path
+pub fn is_request_allowed(status: WorkspaceStatus, method: &Method, route: &str) -> bool {
+    if STATUS_EXEMPT_ROUTES.contains(&route) {
+        return true;
+    }
+    match status {
+        WorkspaceStatus::VERIFIED => true,
+        WorkspaceStatus::DISABLED => false,
+        // read only
+        WorkspaceStatus::SUSPENDED => {
+            method == Method::GET || method == Method::HEAD || method == Method::OPTIONS
+        }
+        WorkspaceStatus::UNVERIFIED => UNVERIFIED_ALLOWED_ROUTES.contains(&route),
+    }
+}
+
+// Must be layered after the authorization middleware, it reads the
+// MemberWorkspace that one puts into the request extensions.
+pub async fn workspace_status_middleware(
+    State(state): State<AppState>,
+    req: Request,
+    next: Next,
+) -> Result<Response<Body>, StatusCode> {
+    let Some(workspace_id) = req.extensions().get::<MemberWorkspace>().map(|w| w.id) else {
+        return Ok(next.run(req).await);
+    };
+    let status = match state.db.workspace.get_workspace_status(workspace_id).await {
+        Ok(status) => status,
+        Err(e) => {
+            tracing::debug!("DS-10500 | Failed to load workspace status: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+    // only set for requests that matched a route, the others 404 anyway
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_default();
+    if !is_request_allowed(status, req.method(), &route) {
+        tracing::debug!(
+            "DS-10403 | {:?} workspace {} blocked from {} {}",
+            status,
+            workspace_id,
+            req.method(),
+            req.uri().path()
+        );
+        return Err(StatusCode::FORBIDDEN);
+    }
+    Ok(next.run(req).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disabled_blocks_everything() {
+        assert!(!is_request_allowed(
+            WorkspaceStatus::DISABLED,
+            &Method::GET,
+            "/users/edit/profile"
+        ));
+        assert!(!is_request_allowed(
+            WorkspaceStatus::DISABLED,
+            &Method::POST,
+            "/workspace/create/"
+        ));
+    }
+
+    #[test]
+    fn test_suspended_is_read_only() {
+        assert!(is_request_allowed(
+            WorkspaceStatus::SUSPENDED,
+            &Method::GET,
+            "/dark-monitor/email/a@b.com"
+        ));
+        assert!(!is_request_allowed(
+            WorkspaceStatus::SUSPENDED,
+            &Method::POST,
+            "/workspace/verified-emails"
+        ));
+        assert!(!is_request_allowed(
+            WorkspaceStatus::SUSPENDED,
+            &Method::POST,
+            "/workspace/create/"
+        ));
+    }
+
+    #[test]
+    fn test_unverified_only_setup_routes() {
+        assert!(is_request_allowed(
+            WorkspaceStatus::UNVERIFIED,
+            &Method::POST,
+            "/users/edit/profile"
+        ));
+        assert!(is_request_allowed(
+            WorkspaceStatus::UNVERIFIED,
+            &Method::POST,
+            "/workspace/verified-domains"
+        ));
+        assert!(is_request_allowed(
+            WorkspaceStatus::UNVERIFIED,
+            &Method::POST,
+            "/workspace/verified-domains/:domain_id/verify"
+        ));
+        assert!(!is_request_allowed(
+            WorkspaceStatus::UNVERIFIED,
+            &Method::GET,
+            "/dark-search/domain/:domain"
+        ));
+        for route in [
+            "/workspace/members",
+            "/workspace/members/invite",
+            "/workspace/invitations",
+            "/workspace/invite-links",
+            "/workspace/webhooks",
+            "/workspace/alerts",
+        ] {
+            assert!(!is_request_allowed(
+                WorkspaceStatus::UNVERIFIED,
+                &Method::GET,
+                route
+            ));
+        }
+    }
+
+    #[test]
+    fn test_redeeming_invite_links_is_exempt() {
+        for status in [
+            WorkspaceStatus::UNVERIFIED,
+            WorkspaceStatus::SUSPENDED,
+            WorkspaceStatus::DISABLED,
+        ] {
+            assert!(is_request_allowed(
+                status,
+                &Method::POST,
+                "/workspace/invite-links/redeem"
+            ));
+        }
+    }
+
+    #[test]
+    fn test_unverified_routes_are_registered() {
+        // a renamed route would silently lock unverified workspaces out
+        let source = include_str!("../../../webapi/mod.rs");
+        for route in UNVERIFIED_ALLOWED_ROUTES
+            .iter()
+            .chain(&STATUS_EXEMPT_ROUTES)
+        {
+            assert!(source.contains(&format!("\"{}\"", route)), "{}", route);
+        }
+    }
+}
@@ -1,6 +1,21 @@
+pub mod alerts;
 pub mod auth;
+pub mod domain_verification;
 pub mod jwt;
 
 pub mod countries;
+pub mod emails;
+pub mod invitations;
+pub mod member_import;
 pub mod macros;
+pub mod metering;
+pub mod phone;
 pub mod middlewares;
+pub mod monitoring;
+pub mod network;
+pub mod public_suffix;
+pub mod quotas;
+pub mod settings;
+pub mod verified_assets;
+pub mod verified_emails;
+pub mod webhooks;
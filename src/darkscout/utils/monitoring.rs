@@ -0,0 +1,198 @@
+use axum::http::StatusCode;
+use rand::Rng;
+use sqlx::Error;
+use uuid::Uuid;
+
+use crate::darkscout::types::darkmonitor::{BreachInfo, BreachTrend, TREND_PERIOD_DAYS};
+use crate::darkscout::types::errors;
+use crate::darkscout::types::monitoring::{
+    BreachSnapshot, BreachTimeline, ScannedBreach, SnapshotTotals,
+};
+use crate::darkscout::types::plans::{Plans, ALL_PLANS};
+use crate::darkscout::types::store::MonitoringStore;
+use crate::darkscout::types::usage::LookupTargetType;
+use crate::darkscout::types::workspace::WorkspaceType;
+use crate::darkscout::types::AppState;
+use crate::darkscout::utils::verified_assets::{normalize_domain, normalize_email};
+
+// the scan interval of a plan in minutes, None when it isn't monitored
+pub fn scan_interval_minutes(plans: &Plans, plan: WorkspaceType) -> Option<i32> {
+    plans
+        .limits(plan)
+        .monitoring_interval_hours
+        .filter(|h| *h > 0)
+        .map(|h| h.saturating_mul(60))
+}
+
+// names of the plans with monitoring, as WORKSPACE_TYPE text
+pub fn monitored_plans(plans: &Plans) -> Vec<String> {
+    ALL_PLANS
+        .iter()
+        .filter(|p| scan_interval_minutes(plans, **p).is_some())
+        .map(|p| format!("{:?}", p))
+        .collect()
+}
+
+// Moves a scan up to a tenth of the interval either way so assets added
+// together don't keep coming due in the same tick
+pub fn jittered(minutes: i32) -> i32 {
+    let spread = minutes / 10;
+    if spread == 0 {
+        return minutes.max(1);
+    }
+    minutes + rand::thread_rng().gen_range(-spread..=spread)
+}
+
+// the breaches of a lookup in the shape the scans record them
+pub fn lookup_breaches(info: &[BreachInfo]) -> Vec<ScannedBreach> {
+    info.iter()
+        .map(|b| ScannedBreach {
+            name: b.name.clone(),
+            data_classes: b.data_classes.clone().unwrap_or_default(),
+            pwn_count: b.pwn_count.unwrap_or(0) as i64,
+            is_sensitive: b.is_sensitive.unwrap_or(false),
+        })
+        .collect()
+}
+
+// Keeps a lookup of one of the workspace's verified emails or domains as the
+// asset's snapshot of the day and returns the asset, None for other targets.
+pub async fn record_lookup_snapshot(
+    state: &AppState,
+    workspace_id: Uuid,
+    asset_type: LookupTargetType,
+    target: &str,
+    info: &[BreachInfo],
+) -> Option<Uuid> {
+    let asset = match asset_type {
+        LookupTargetType::EMAIL => normalize_email(target, false),
+        LookupTargetType::DOMAIN => normalize_domain(target),
+    }?;
+    let asset_id = match state
+        .db
+        .monitoring
+        .find_monitored_asset(workspace_id, asset_type, &asset)
+        .await
+    {
+        Ok(id) => id,
+        Err(Error::RowNotFound) => return None,
+        Err(e) => {
+            tracing::debug!("Failed to look up the monitored asset {}", e);
+            return None;
+        }
+    };
+    let snapshot = BreachSnapshot::new(
+        workspace_id,
+        asset_type,
+        asset_id,
+        &asset,
+        &lookup_breaches(info),
+    );
+    if let Err(e) = state.db.monitoring.save_breach_snapshot(&snapshot).await {
+        tracing::debug!("Failed to save the breach snapshot of {}: {}", asset, e);
+    }
+    Some(asset_id)
+}
+
+// Records an email lookup and loads what its analytics are compared to.
+pub async fn lookup_trend(
+    state: &AppState,
+    workspace_id: Uuid,
+    email: &str,
+    info: &[BreachInfo],
+) -> BreachTrend {
+    let asset_id =
+        match record_lookup_snapshot(state, workspace_id, LookupTargetType::EMAIL, email, info)
+            .await
+        {
+            Some(id) => id,
+            None => return BreachTrend::default(),
+        };
+    BreachTrend {
+        current: snapshot_totals(state, workspace_id, asset_id, 0).await,
+        previous: snapshot_totals(state, workspace_id, asset_id, TREND_PERIOD_DAYS).await,
+    }
+}
+
+async fn snapshot_totals(
+    state: &AppState,
+    workspace_id: Uuid,
+    asset_id: Uuid,
+    days_back: i32,
+) -> Option<SnapshotTotals> {
+    match state
+        .db
+        .monitoring
+        .get_snapshot_totals(workspace_id, asset_id, days_back)
+        .await
+    {
+        Ok(t) => Some(t),
+        Err(Error::RowNotFound) => None,
+        Err(e) => {
+            tracing::debug!("Failed to load the breach snapshot totals {}", e);
+            None
+        }
+    }
+}
+
+// first and last day each breach was seen for a verified asset
+pub async fn breach_timeline(
+    state: &AppState,
+    workspace_id: Uuid,
+    asset_id: Uuid,
+    asset: String,
+) -> Result<BreachTimeline, (StatusCode, &'static str)> {
+    match state
+        .db
+        .monitoring
+        .get_breach_timeline(workspace_id, asset_id)
+        .await
+    {
+        Ok(breaches) => Ok(BreachTimeline {
+            asset_id,
+            asset,
+            breaches,
+        }),
+        Err(e) => {
+            tracing::debug!("Failed to load the breach timeline {}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                errors::INTERNAL_SERVER_ERROR,
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::darkscout::types::plans::PlanLimits;
+
+    #[test]
+    fn test_monitored_plans() {
+        let mut plans = Plans::default();
+        plans.b2b_pro = PlanLimits {
+            monitoring_interval_hours: Some(24),
+            ..Default::default()
+        };
+        plans.b2c_pro = PlanLimits {
+            monitoring_interval_hours: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(monitored_plans(&plans), vec![String::from("B2B_PRO")]);
+        assert_eq!(
+            scan_interval_minutes(&plans, WorkspaceType::B2B_PRO),
+            Some(1440)
+        );
+        assert_eq!(scan_interval_minutes(&plans, WorkspaceType::B2C_PRO), None);
+    }
+
+    #[test]
+    fn test_jittered() {
+        for _ in 0..100 {
+            let m = jittered(1440);
+            assert!((1296..=1584).contains(&m));
+        }
+        assert_eq!(jittered(5), 5);
+    }
+}
@@ -0,0 +1,204 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::Url;
+
+// Addresses the server may connect to on behalf of users. Anything private,
+// local or reserved could reach our own network or the cloud metadata
+// endpoints.
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_ipv4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(v4) => is_public_ipv4(v4),
+            None => is_public_ipv6(ip),
+        },
+    }
+}
+
+fn is_public_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        // this network, shared address space (CGNAT), protocol assignments,
+        // benchmarking and reserved
+        || a == 0
+        || (a == 100 && (64..128).contains(&b))
+        || (a == 192 && b == 0 && c == 0)
+        || (a == 198 && (b == 18 || b == 19))
+        || a >= 240)
+}
+
+fn is_public_ipv6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        // unique local, link local and documentation
+        || (first & 0xfe00) == 0xfc00
+        || (first & 0xffc0) == 0xfe80
+        || first == 0x2001 && ip.segments()[1] == 0x0db8)
+}
+
+// Loopback is only ever allowed for local testing
+fn is_allowed_ip(ip: IpAddr, allow_loopback: bool) -> bool {
+    is_public_ip(ip) || (allow_loopback && ip.is_loopback())
+}
+
+// Checks the host of a url the server is about to fetch. IP literals are
+// checked here, names are checked by `PublicResolver` when connecting so a
+// name can't be pointed somewhere else between the check and the request.
+pub fn check_url_host(url: &Url, allow_loopback: bool) -> Result<(), &'static str> {
+    let Some(host) = url.host_str() else {
+        return Err("missing host");
+    };
+    match host
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse::<IpAddr>()
+    {
+        Ok(ip) if !is_allowed_ip(ip, allow_loopback) => Err("address not allowed"),
+        _ => Ok(()),
+    }
+}
+
+// Whether the url points at the local machine, by address or as localhost
+pub fn is_loopback_url(url: &Url) -> bool {
+    match url.host_str() {
+        Some(host) => {
+            host.eq_ignore_ascii_case("localhost")
+                || host
+                    .trim_start_matches('[')
+                    .trim_end_matches(']')
+                    .parse::<IpAddr>()
+                    .is_ok_and(|ip| ip.is_loopback())
+        }
+        None => false,
+    }
+}
+
+// Checks the host of a url by resolving it now, for urls that are stored and
+// fetched later. The fetch still goes through `PublicResolver`.
+pub async fn check_resolved_host(url: &Url, allow_loopback: bool) -> Result<(), String> {
+    check_url_host(url, allow_loopback)?;
+    let Some(host) = url.host_str() else {
+        return Err(String::from("missing host"));
+    };
+    if host.starts_with('[') || host.parse::<IpAddr>().is_ok() {
+        return Ok(());
+    }
+    let name: Name = host.parse().map_err(|_| String::from("invalid host"))?;
+    PublicResolver { allow_loopback }
+        .resolve(name)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+// Resolves names for outgoing requests and refuses the ones that point at
+// anything but public addresses
+pub struct PublicResolver {
+    pub allow_loopback: bool,
+}
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let host = name.as_str().to_string();
+        let allow_loopback = self.allow_loopback;
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> =
+                tokio::net::lookup_host((host.as_str(), 0)).await?.collect();
+            if addrs.is_empty() || addrs.iter().any(|a| !is_allowed_ip(a.ip(), allow_loopback)) {
+                return Err(format!("{} doesn't resolve to a public address", host).into());
+            }
+            let addrs: Addrs = Box::new(addrs.into_iter());
+            Ok(addrs)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_is_public_ip() {
+        for private in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "255.255.255.255",
+            "::1",
+            "fe80::1",
+            "fd00::1",
+            "::ffff:127.0.0.1",
+            "::ffff:169.254.169.254",
+        ] {
+            assert!(!is_public_ip(ip(private)), "{}", private);
+        }
+        for public in ["93.184.216.34", "1.1.1.1", "2606:4700:4700::1111"] {
+            assert!(is_public_ip(ip(public)), "{}", public);
+        }
+    }
+
+    #[test]
+    fn test_check_url_host() {
+        let url = |s: &str| Url::parse(s).unwrap();
+        assert!(check_url_host(&url("https://hooks.acme.com/x"), false).is_ok());
+        assert!(check_url_host(&url("https://169.254.169.254/latest"), false).is_err());
+        assert!(check_url_host(&url("https://[::1]:8080/"), false).is_err());
+        assert!(check_url_host(&url("http://127.0.0.1:9000/"), true).is_ok());
+        assert!(check_url_host(&url("http://10.0.0.1/"), true).is_err());
+    }
+
+    #[test]
+    fn test_is_loopback_url() {
+        let url = |s: &str| Url::parse(s).unwrap();
+        assert!(is_loopback_url(&url("http://localhost:8080/hook")));
+        assert!(is_loopback_url(&url("http://127.0.0.1/hook")));
+        assert!(is_loopback_url(&url("http://[::1]/hook")));
+        assert!(!is_loopback_url(&url("https://hooks.acme.com/")));
+    }
+
+    #[tokio::test]
+    async fn test_check_resolved_host() {
+        let url = |s: &str| Url::parse(s).unwrap();
+        assert!(check_resolved_host(&url("https://localhost/hook"), false)
+            .await
+            .is_err());
+        assert!(check_resolved_host(&url("https://10.0.0.8/hook"), false)
+            .await
+            .is_err());
+        assert!(check_resolved_host(&url("http://localhost/hook"), true)
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_resolver_refuses_loopback() {
+        let resolver = PublicResolver {
+            allow_loopback: false,
+        };
+        assert!(resolver
+            .resolve("localhost".parse().unwrap())
+            .await
+            .is_err());
+        let resolver = PublicResolver {
+            allow_loopback: true,
+        };
+        assert!(resolver.resolve("localhost".parse().unwrap()).await.is_ok());
+    }
+}
@@ -0,0 +1,36 @@
+// Normalizes a phone number to E.164, spaces, dashes, dots and brackets are
+// dropped. Numbers have to come with their country code.
+pub fn normalize_phone(value: &str) -> Option<String> {
+    let cleaned: String = value
+        .trim()
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '-' | '.' | '(' | ')'))
+        .collect();
+    let digits = cleaned
+        .strip_prefix('+')
+        .or_else(|| cleaned.strip_prefix("00"))?;
+    let valid = (8..=15).contains(&digits.len())
+        && digits.chars().all(|c| c.is_ascii_digit())
+        && !digits.starts_with('0');
+    valid.then(|| format!("+{}", digits))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_phone() {
+        assert_eq!(
+            normalize_phone(" +1 (555) 123-4567 ").as_deref(),
+            Some("+15551234567")
+        );
+        assert_eq!(
+            normalize_phone("0044 20.7946.0958").as_deref(),
+            Some("+442079460958")
+        );
+        assert_eq!(normalize_phone("5551234567"), None);
+        assert_eq!(normalize_phone("+0123456789"), None);
+        assert_eq!(normalize_phone("+1555abc4567"), None);
+    }
+}
@@ -0,0 +1,103 @@
+use std::sync::OnceLock;
+
+use publicsuffix::{List, Psl};
+
+// The full Public Suffix List, ICANN and private sections, as published on
+// https://publicsuffix.org/list/public_suffix_list.dat
+fn suffix_list() -> &'static List {
+    static LIST: OnceLock<List> = OnceLock::new();
+    LIST.get_or_init(|| {
+        include_str!("public_suffix_list.dat")
+            .parse()
+            .expect("Couldn't parse the public suffix list")
+    })
+}
+
+// How many labels at the end of the host form its public suffix, a host
+// that matches no rule falls back to the implicit "*" rule
+fn suffix_len(host: &str) -> usize {
+    match suffix_list().suffix(host.as_bytes()) {
+        Some(suffix) => suffix.as_bytes().split(|b| *b == b'.').count(),
+        None => 1,
+    }
+}
+
+// The domain a company can register, "mail.example.co.uk" gives
+// "example.co.uk". None for a public suffix itself. Hosts are expected to
+// be normalized already.
+pub fn registrable_domain(host: &str) -> Option<String> {
+    let labels: Vec<&str> = host.split('.').collect();
+    let suffix = suffix_len(host);
+    if labels.len() <= suffix {
+        return None;
+    }
+    Some(labels[labels.len() - suffix - 1..].join("."))
+}
+
+// The parents of a host that could cover it as a verified domain, closest
+// first and never above the registrable domain
+pub fn parent_domains(host: &str) -> Vec<String> {
+    let labels: Vec<&str> = host.split('.').collect();
+    let suffix = suffix_len(host);
+    if labels.len() <= suffix + 1 {
+        return Vec::new();
+    }
+    (1..labels.len() - suffix)
+        .map(|i| labels[i..].join("."))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registrable_domain() {
+        assert_eq!(
+            registrable_domain("mail.example.co.uk"),
+            Some(String::from("example.co.uk"))
+        );
+        assert_eq!(
+            registrable_domain("eu.example.com"),
+            Some(String::from("example.com"))
+        );
+        assert_eq!(
+            registrable_domain("shop.example.unknowntld"),
+            Some(String::from("example.unknowntld"))
+        );
+        assert_eq!(registrable_domain("co.uk"), None);
+        assert_eq!(registrable_domain("com"), None);
+        // wildcard and exception rules
+        assert_eq!(registrable_domain("acme.ck"), None);
+        assert_eq!(
+            registrable_domain("shop.acme.ck"),
+            Some(String::from("shop.acme.ck"))
+        );
+        assert_eq!(registrable_domain("www.ck"), Some(String::from("www.ck")));
+        // private section suffixes belong to the platform's tenants
+        assert_eq!(registrable_domain("github.io"), None);
+        assert_eq!(
+            registrable_domain("docs.evil.github.io"),
+            Some(String::from("evil.github.io"))
+        );
+        assert_eq!(
+            registrable_domain("shop.acme.com.br"),
+            Some(String::from("acme.com.br"))
+        );
+    }
+
+    #[test]
+    fn test_parent_domains() {
+        assert_eq!(
+            parent_domains("a.eu.example.com"),
+            vec![String::from("eu.example.com"), String::from("example.com")]
+        );
+        assert_eq!(
+            parent_domains("mail.example.co.uk"),
+            vec![String::from("example.co.uk")]
+        );
+        assert!(parent_domains("example.co.uk").is_empty());
+        assert!(parent_domains("co.uk").is_empty());
+        assert!(parent_domains("evil.herokuapp.com").is_empty());
+    }
+}
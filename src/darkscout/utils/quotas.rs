@@ -0,0 +1,141 @@
+use axum::http::StatusCode;
+use axum::Json;
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::darkscout::types::plans::{
+    has_room, quota_day, PlanLimits, Quota, QuotaUsage, WorkspaceUsage, ALL_QUOTAS,
+};
+use crate::darkscout::types::store::{
+    MemberStore, UsageStore, VerifiedDomainsStore, VerifiedEmailsStore, WorkspaceStore,
+};
+use crate::darkscout::types::workspace::WorkspaceType;
+use crate::darkscout::types::{AppState, DSResponse};
+use crate::darkscout::web::json_error;
+
+pub enum QuotaError {
+    Exceeded(Quota),
+    Db(sqlx::Error),
+}
+
+impl From<sqlx::Error> for QuotaError {
+    fn from(e: sqlx::Error) -> Self {
+        QuotaError::Db(e)
+    }
+}
+
+impl QuotaError {
+    pub fn into_json<T>(self) -> (StatusCode, Json<DSResponse<T>>) {
+        match self {
+            QuotaError::Exceeded(quota) => {
+                let code = if quota.is_daily() {
+                    StatusCode::TOO_MANY_REQUESTS
+                } else {
+                    StatusCode::FORBIDDEN
+                };
+                json_error(Some(code), Some(quota.exceeded_msg()))
+            }
+            QuotaError::Db(e) => {
+                tracing::debug!("QuotaCheckFailed: {}", e);
+                json_error(None, None)
+            }
+        }
+    }
+}
+
+pub async fn plan_limits(
+    state: &AppState,
+    workspace_id: Uuid,
+) -> Result<(WorkspaceType, PlanLimits), sqlx::Error> {
+    let plan = state.db.workspace.get_workspace_type(workspace_id).await?;
+    Ok((plan, state.settings.plans.limits(plan).clone()))
+}
+
+pub async fn current_usage(
+    state: &AppState,
+    workspace_id: Uuid,
+    quota: Quota,
+) -> Result<i64, sqlx::Error> {
+    match quota {
+        Quota::DAILY_LOOKUPS | Quota::DARK_SEARCH_PAGES => {
+            state
+                .db
+                .usage
+                .get_daily_usage(workspace_id, quota, quota_day(Utc::now()))
+                .await
+        }
+        Quota::VERIFIED_EMAILS => {
+            state
+                .db
+                .verified_emails
+                .count_workspace_emails(workspace_id)
+                .await
+        }
+        Quota::VERIFIED_DOMAINS => {
+            state
+                .db
+                .verified_domains
+                .count_workspace_domains(workspace_id)
+                .await
+        }
+        Quota::MEMBERS => state.db.member.count_workspace_members(workspace_id).await,
+    }
+}
+
+// Counts one use of a daily quota, call it before going to the provider.
+pub async fn consume_daily_quota(
+    state: &AppState,
+    workspace_id: Uuid,
+    quota: Quota,
+) -> Result<(), QuotaError> {
+    let (_, limits) = plan_limits(state, workspace_id).await?;
+    match state
+        .db
+        .usage
+        .consume_daily_usage(
+            workspace_id,
+            quota,
+            quota_day(Utc::now()),
+            quota.limit(&limits),
+        )
+        .await?
+    {
+        true => Ok(()),
+        false => Err(QuotaError::Exceeded(quota)),
+    }
+}
+
+// Checks that `adding` more rows still fit in the plan.
+pub async fn check_capacity(
+    state: &AppState,
+    workspace_id: Uuid,
+    quota: Quota,
+    adding: i64,
+) -> Result<(), QuotaError> {
+    let (_, limits) = plan_limits(state, workspace_id).await?;
+    let limit = quota.limit(&limits);
+    if limit.is_none() {
+        return Ok(());
+    }
+    let used = current_usage(state, workspace_id, quota).await?;
+    if !has_room(limit, used, adding) {
+        return Err(QuotaError::Exceeded(quota));
+    }
+    Ok(())
+}
+
+pub async fn workspace_usage(
+    state: &AppState,
+    workspace_id: Uuid,
+) -> Result<WorkspaceUsage, sqlx::Error> {
+    let (plan, limits) = plan_limits(state, workspace_id).await?;
+    let mut usage = vec![];
+    for quota in ALL_QUOTAS {
+        usage.push(QuotaUsage {
+            quota,
+            used: current_usage(state, workspace_id, quota).await?,
+            limit: quota.limit(&limits),
+        });
+    }
+    Ok(WorkspaceUsage { plan, usage })
+}
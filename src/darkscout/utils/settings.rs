@@ -0,0 +1,71 @@
+use uuid::Uuid;
+
+use crate::darkscout::types::settings::{
+    find_setting, resolve_settings, validate_setting, EffectiveSetting, DEFAULT_SESSION_HOURS,
+};
+use crate::darkscout::types::store::SettingsStore;
+use crate::darkscout::types::workspace::UpdateSettingsForm;
+use crate::darkscout::types::AppState;
+
+pub async fn effective_settings(
+    state: &AppState,
+    workspace_id: Uuid,
+    member_id: Option<Uuid>,
+) -> Result<Vec<EffectiveSetting>, sqlx::Error> {
+    let rows = state
+        .db
+        .settings
+        .get_settings(workspace_id, member_id)
+        .await?;
+    Ok(resolve_settings(&rows, member_id))
+}
+
+// Effective value of a single setting, falls back to the schema default
+pub async fn get_setting(
+    state: &AppState,
+    workspace_id: Uuid,
+    member_id: Option<Uuid>,
+    name: &str,
+) -> Result<String, sqlx::Error> {
+    let settings = effective_settings(state, workspace_id, member_id).await?;
+    Ok(settings
+        .into_iter()
+        .find(|s| s.name == name)
+        .map(|s| s.value)
+        .or_else(|| find_setting(name).map(|d| d.default.to_string()))
+        .unwrap_or_default())
+}
+
+// how long the tokens issued for the workspace stay valid
+pub async fn session_timeout_hours(state: &AppState, workspace_id: Uuid) -> i64 {
+    let value = match get_setting(state, workspace_id, None, "session_timeout_hours").await {
+        Ok(v) => v,
+        Err(e) => {
+            tracing::debug!("Failed to load the session timeout {}", e);
+            String::new()
+        }
+    };
+    value.parse().unwrap_or(DEFAULT_SESSION_HOURS)
+}
+
+// whether verified emails are stored without their +tag
+pub async fn fold_plus_addresses(
+    state: &AppState,
+    workspace_id: Uuid,
+) -> Result<bool, sqlx::Error> {
+    let value = get_setting(state, workspace_id, None, "fold_plus_addresses").await?;
+    Ok(value == "true")
+}
+
+pub fn validate_settings_form(
+    form: UpdateSettingsForm,
+    member_scope: bool,
+) -> Result<Vec<(String, String)>, &'static str> {
+    form.settings
+        .into_iter()
+        .map(|s| {
+            let value = validate_setting(&s.settings_name, &s.settings_value, member_scope)?;
+            Ok((s.settings_name, value))
+        })
+        .collect()
+}
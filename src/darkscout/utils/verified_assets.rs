@@ -0,0 +1,171 @@
+use std::collections::HashSet;
+
+use uuid::Uuid;
+use validator::ValidateEmail;
+
+use crate::darkscout::types::store::VerifiedDomainsStore;
+use crate::darkscout::types::AppState;
+use crate::darkscout::utils::public_suffix::{parent_domains, registrable_domain};
+
+// Lowercases the domain, drops the trailing dot of fully qualified names and
+// turns IDNs into punycode so "Bücher.Example." and "xn--bcher-kva.example"
+// are stored once. IP addresses and public suffixes aren't domains anyone
+// can verify, there has to be a name registered under a public suffix.
+pub fn normalize_domain(domain: &str) -> Option<String> {
+    let domain = domain.trim().trim_end_matches('.');
+    if domain.is_empty() {
+        return None;
+    }
+    let ascii = idna::domain_to_ascii(domain).ok()?;
+    let labels: Vec<&str> = ascii.split('.').collect();
+    let valid_label = |l: &&str| {
+        !l.is_empty()
+            && l.len() <= 63
+            && !l.starts_with('-')
+            && !l.ends_with('-')
+            && l.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    };
+    if ascii.len() > 253 || labels.len() < 2 || !labels.iter().all(valid_label) {
+        return None;
+    }
+    // top level domains are never numeric, this also turns away IPv4 literals
+    if labels[labels.len() - 1].chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let registrable = registrable_domain(&ascii)?;
+    let suffix_labels = registrable.split('.').count() - 1;
+    let named = labels[..labels.len() - suffix_labels]
+        .iter()
+        .any(|l| l.chars().any(|c| c.is_ascii_alphabetic()));
+    named.then_some(ascii)
+}
+
+// Normalizes the domain part and lowercases the mailbox. With fold_plus the
+// "+tag" suffix is dropped as most providers deliver it to the same inbox.
+pub fn normalize_email(email: &str, fold_plus: bool) -> Option<String> {
+    let (local, domain) = email.trim().rsplit_once('@')?;
+    let mut local = local.to_lowercase();
+    if fold_plus {
+        if let Some((mailbox, _)) = local.split_once('+') {
+            local = mailbox.to_string();
+        }
+    }
+    // valid in an address but they'd reshape the URL the address is looked
+    // up through
+    if local.contains(['/', '\\', '?', '#', '%']) {
+        return None;
+    }
+    let email = format!("{}@{}", local, normalize_domain(domain)?);
+    if local.is_empty() || !email.validate_email() {
+        return None;
+    }
+    Some(email)
+}
+
+// Splits normalized values into the ones to add and the ones the workspace
+// already has or that were repeated in the same request
+pub fn split_new_values(values: Vec<String>, existing: &[String]) -> (Vec<String>, Vec<String>) {
+    let mut seen: HashSet<String> = existing.iter().cloned().collect();
+    let mut duplicates = Vec::new();
+    let mut new_values = Vec::new();
+    for value in values {
+        if seen.insert(value.clone()) {
+            new_values.push(value);
+        } else {
+            duplicates.push(value);
+        }
+    }
+    (new_values, duplicates)
+}
+
+// The verified domain of the workspace that vouches for host, host itself or
+// a parent verified with its subdomains
+pub async fn covering_verified_domain(
+    state: &AppState,
+    workspace_id: Uuid,
+    host: &str,
+) -> Result<Option<String>, sqlx::Error> {
+    state
+        .db
+        .verified_domains
+        .find_covering_domain(workspace_id, host, &parent_domains(host))
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_domain() {
+        assert_eq!(
+            normalize_domain(" Example.COM. "),
+            Some(String::from("example.com"))
+        );
+        assert_eq!(
+            normalize_domain("Bücher.example"),
+            Some(String::from("xn--bcher-kva.example"))
+        );
+        assert_eq!(normalize_domain("localhost"), None);
+        assert_eq!(normalize_domain("bad_domain.com"), None);
+        assert_eq!(normalize_domain("-bad.com"), None);
+    }
+
+    #[test]
+    fn test_normalize_domain_needs_a_name() {
+        assert_eq!(normalize_domain("127.0.0.1"), None);
+        assert_eq!(normalize_domain("169.254.169.254."), None);
+        assert_eq!(normalize_domain("123.456"), None);
+        assert_eq!(normalize_domain("co.uk"), None);
+        assert_eq!(normalize_domain("123.co.uk"), None);
+        assert_eq!(
+            normalize_domain("mail.123.co.uk"),
+            Some(String::from("mail.123.co.uk"))
+        );
+        assert_eq!(
+            normalize_domain("acme.co.uk"),
+            Some(String::from("acme.co.uk"))
+        );
+    }
+
+    #[test]
+    fn test_normalize_email() {
+        assert_eq!(
+            normalize_email("John.Doe+News@Example.com.", false),
+            Some(String::from("john.doe+news@example.com"))
+        );
+        assert_eq!(
+            normalize_email("John.Doe+News@Example.com", true),
+            Some(String::from("john.doe@example.com"))
+        );
+        assert_eq!(normalize_email("+news@example.com", true), None);
+        assert_eq!(normalize_email("not-an-email", false), None);
+        assert_eq!(
+            normalize_email("x/../../domains/victim.com#@mine.com", false),
+            None
+        );
+        assert_eq!(
+            normalize_email("x/../victim%40other.com#@mine.com", false),
+            None
+        );
+        assert_eq!(normalize_email("a?b@mine.com", false), None);
+    }
+
+    #[test]
+    fn test_split_new_values() {
+        let existing = vec![String::from("a@example.com")];
+        let (new_values, duplicates) = split_new_values(
+            vec![
+                String::from("a@example.com"),
+                String::from("b@example.com"),
+                String::from("b@example.com"),
+            ],
+            &existing,
+        );
+        assert_eq!(new_values, vec![String::from("b@example.com")]);
+        assert_eq!(
+            duplicates,
+            vec![String::from("a@example.com"), String::from("b@example.com")]
+        );
+    }
+}
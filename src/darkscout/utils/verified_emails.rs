@@ -0,0 +1,82 @@
+use crate::darkscout::types::store::VerifiedEmailsStore;
+use crate::darkscout::types::verified_emails::{confirmation_email, VerifiedEmail};
+use crate::darkscout::types::AppState;
+use crate::darkscout::utils::emails::send_workspace_email;
+use uuid::Uuid;
+
+pub fn verified_email_confirm_link(app_url: &str, token: &str) -> String {
+    format!(
+        "{}/verified-emails/confirm?token={}",
+        app_url.trim_end_matches('/'),
+        token
+    )
+}
+
+// Emails the confirmation link and records whether the smtp server took it
+pub async fn send_email_confirmation(state: &AppState, workspace_id: Uuid, email: VerifiedEmail) {
+    let Some(token) = email.confirm_token.as_deref() else {
+        return;
+    };
+    match state
+        .db
+        .verified_emails
+        .claim_address_send(&email.email)
+        .await
+    {
+        Ok(true) => {}
+        Ok(false) => {
+            let error = "Too many confirmation emails for this address, try again later";
+            if let Err(e) = state
+                .db
+                .verified_emails
+                .mark_confirmation_failed(email.id, error.to_string())
+                .await
+            {
+                tracing::debug!("Failed to record the confirmation delivery {}", e);
+            }
+            return;
+        }
+        Err(e) => {
+            tracing::debug!("Failed to check the confirmation sends {}", e);
+            return;
+        }
+    }
+    let link = verified_email_confirm_link(&state.settings.config.app_url, token);
+    let result = send_workspace_email(state, workspace_id, email.email.clone(), |branding| {
+        confirmation_email(&link, branding)
+    })
+    .await;
+    let updated = match result {
+        Ok(_) => {
+            state
+                .db
+                .verified_emails
+                .mark_confirmation_sent(email.id)
+                .await
+        }
+        Err(e) => {
+            tracing::debug!("ConfirmationDeliveryError : {}", e);
+            state
+                .db
+                .verified_emails
+                .mark_confirmation_failed(email.id, e)
+                .await
+        }
+    };
+    if let Err(e) = updated {
+        tracing::debug!("Failed to record the confirmation delivery {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_confirm_link() {
+        assert_eq!(
+            verified_email_confirm_link("https://app.darkscout.io/", "abc"),
+            "https://app.darkscout.io/verified-emails/confirm?token=abc"
+        );
+    }
+}
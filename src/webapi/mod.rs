@@ -1,9 +1,20 @@
 use crate::darkscout::adapters::dsbreach::BreachClient;
+use crate::darkscout::adapters::dns::SystemDnsResolver;
+use crate::darkscout::adapters::http_fetcher::ReqwestFetcher;
+use crate::darkscout::adapters::sms::SmsClient;
+use crate::darkscout::adapters::webhooks::WebhookClient;
 use crate::darkscout::adapters::smtp_mailer::create_smtp_client;
 use crate::darkscout::adapters::ds_darkengine::api::DarkSearchClient;
 use crate::darkscout::adapters::{DSProvider, DarkSearchProvider};
+use crate::darkscout::jobs::asset_monitor::run_asset_monitoring;
+use crate::darkscout::jobs::domain_recheck::run_domain_rechecks;
+use crate::darkscout::jobs::usage_rollup::run_usage_rollups;
+use crate::darkscout::jobs::webhook_delivery::run_webhook_deliveries;
 use crate::darkscout::types::store::DSCache;
 use crate::darkscout::utils::middlewares::jwt_auth::authorization_middleware;
+use crate::darkscout::utils::middlewares::lookup_policy::lookup_policy_middleware;
+use crate::darkscout::utils::middlewares::super_admin::super_admin_middleware;
+use crate::darkscout::utils::middlewares::workspace_status::workspace_status_middleware;
 use crate::darkscout::{
     handlers,
     types::{
@@ -43,15 +54,53 @@ pub async fn web_api() -> axum::Router {
 
     let smtp_client =
         create_smtp_client(app_env.smtp.username.clone(), app_env.smtp.password.clone());
+    let sms_client = SmsClient::from_settings(&app_env.sms);
+    let dns_resolver = match SystemDnsResolver::new() {
+        Ok(r) => r,
+        Err(e) => {
+            tracing::debug!("Failed to create the dns resolver: {}", e);
+            panic!("Couldn't create dns resolver")
+        }
+    };
+    let http_fetcher = match ReqwestFetcher::new() {
+        Ok(f) => f,
+        Err(e) => {
+            tracing::debug!("Failed to create the http fetcher: {}", e);
+            panic!("Couldn't create http fetcher")
+        }
+    };
+    let webhook_client = match WebhookClient::new(app_env.webhooks.allow_loopback) {
+        Ok(c) => c,
+        Err(e) => {
+            tracing::debug!("Failed to create the webhook client: {}", e);
+            panic!("Couldn't create webhook client")
+        }
+    };
     let app_state: AppState = AppState {
         settings: app_env,
         db: Arc::new(PgStore::new(&settings).await), // new will panic if can't be connected to db
         cache: Arc::new(cache),
         ds_provider,
         smtp_client,
+        sms_client,
+        dns_resolver,
+        http_fetcher,
+        webhook_client,
         ds_darkengine_provider,
     };
 
+    tokio::spawn(run_usage_rollups(app_state.db.clone()));
+    tokio::spawn(run_domain_rechecks(
+        app_state.db.clone(),
+        app_state.dns_resolver.clone(),
+        app_state.http_fetcher.clone(),
+    ));
+    tokio::spawn(run_asset_monitoring(app_state.clone()));
+    tokio::spawn(run_webhook_deliveries(
+        app_state.db.clone(),
+        app_state.webhook_client.clone(),
+    ));
+
     let cors = CorsLayer::new()
         // allow `GET` and `POST` when accessing the resource
         .allow_methods([Method::GET, Method::POST, Method::OPTIONS])
@@ -64,96 +113,304 @@ pub async fn web_api() -> axum::Router {
         // allow requests from any origin
         .allow_origin(Any);
 
-    axum::Router::new()
-        // Unauthorized Routes
-        .route("/", get(test))
-        .route("/users/auth/signup", post(handlers::users::sign_up))
-        .route("/users/auth/login", post(handlers::users::log_in))
-        .route("/users/auth/verify/", post(handlers::users::verify_user))
+    // breach lookups, only for targets the lookup policy allows
+    let lookup_routes = axum::Router::new()
+        // dark monitor
         .route(
-            "/users/auth/request-reset-token/",
-            post(handlers::auth::request_password_reset_link),
+            "/dark-monitor/email/:email",
+            get(handlers::dark_monitor::get_stats_by_email),
         )
         .route(
-            "/users/auth/reset-with-token/",
-            post(handlers::auth::reset_password_with_token),
+            "/dark-monitor/analytics/email/:email",
+            get(handlers::dark_monitor::get_analytics_by_email),
         )
-        // Authorized Routes
+        .route(
+            "/dark-monitor/domain/:domain",
+            get(handlers::dark_monitor::get_stats_by_domain),
+        )
+        // DARK SEARCH
+        .route(
+            "/dark-search/domain/:domain",
+            get(handlers::dark_monitor::get_dark_search_by_domain),
+        )
+        .route(
+            "/dark-search/email/:email",
+            get(handlers::dark_monitor::get_dark_search_by_email),
+        )
+        .route_layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            lookup_policy_middleware,
+        ));
+
+    let authorized_routes = axum::Router::new()
+        .merge(lookup_routes)
         // Auth
         .route(
             "/users/auth/reset-password/",
-            post(handlers::users::change_password)
-                .layer(middleware::from_fn(authorization_middleware)),
+            post(handlers::users::change_password),
         )
         // Users
         .route(
             "/users/edit/profile",
-            post(handlers::users::edit_user_profile)
-                .layer(middleware::from_fn(authorization_middleware)),
+            post(handlers::users::edit_user_profile),
         )
-        // dark monitor
+        .route("/users/phone", post(handlers::users::update_user_phone))
         .route(
-            "/dark-monitor/email/:email",
-            get(handlers::dark_monitor::get_stats_by_email)
-                .layer(middleware::from_fn(authorization_middleware)),
+            "/users/phone/verify",
+            post(handlers::users::verify_user_phone),
         )
+        // workspace
         .route(
-            "/dark-monitor/analytics/email/:email",
-            get(handlers::dark_monitor::get_analytics_by_email)
-                .layer(middleware::from_fn(authorization_middleware)),
+            "/workspace/create/",
+            post(handlers::workspaces::create_workspace),
         )
         .route(
-            "/dark-monitor/domain/:domain",
-            get(handlers::dark_monitor::get_stats_by_domain)
-                .layer(middleware::from_fn(authorization_middleware)),
+            "/workspace/members/invite",
+            post(handlers::workspaces::invite_new_members),
         )
-        // DARK SEARCH
         .route(
-            "/dark-search/domain/:domain",
-            get(handlers::dark_monitor::get_dark_search_by_domain)
-                .layer(middleware::from_fn(authorization_middleware)),
+            "/workspace/members/import",
+            post(handlers::invitations::import_members),
         )
         .route(
-            "/dark-search/email/:email",
-            get(handlers::dark_monitor::get_dark_search_by_email)
-                .layer(middleware::from_fn(authorization_middleware)),
+            "/workspace/invitations",
+            get(handlers::invitations::list_invitations),
         )
-        // workspace
-        // workspace
         .route(
-            "/workspace/create/",
-            get(handlers::workspaces::create_workspace)
-                .layer(middleware::from_fn(authorization_middleware)),
+            "/workspace/invitations/:invitation_id/resend",
+            post(handlers::invitations::resend_invitation),
         )
         .route(
-            "/workspace/members/invite",
-            get(handlers::workspaces::invite_new_members)
-                .layer(middleware::from_fn(authorization_middleware)),
+            "/workspace/invitations/:invitation_id/revoke",
+            post(handlers::invitations::revoke_invitation),
+        )
+        .route(
+            "/workspace/invite-links",
+            get(handlers::invitations::list_invite_links)
+                .post(handlers::invitations::create_invite_link),
+        )
+        .route(
+            "/workspace/invite-links/:link_id/revoke",
+            post(handlers::invitations::revoke_invite_link),
+        )
+        .route(
+            "/workspace/invite-links/redeem",
+            post(handlers::invitations::redeem_invite_link),
         )
         // add verified emails
         .route(
             "/workspace/verified-emails",
-            post(handlers::verified_emails::add_verified_emails)
-                .layer(middleware::from_fn(authorization_middleware)),
+            get(handlers::verified_emails::list_verified_emails)
+                .post(handlers::verified_emails::add_verified_emails),
+        )
+        .route(
+            "/workspace/verified-emails/:email_id/resend",
+            post(handlers::verified_emails::resend_email_confirmation),
+        )
+        .route(
+            "/workspace/verified-emails/:email_id/reverify",
+            post(handlers::verified_emails::reverify_verified_email),
+        )
+        .route(
+            "/workspace/verified-emails/:email_id/delete",
+            post(handlers::verified_emails::delete_verified_email),
+        )
+        .route(
+            "/workspace/verified-emails/:email_id/timeline",
+            get(handlers::verified_emails::get_verified_email_timeline),
         )
         // add verified domains
         .route(
             "/workspace/verified-domains",
-            post(handlers::verified_domains::add_verified_domains)
-                .layer(middleware::from_fn(authorization_middleware)),
+            get(handlers::verified_domains::list_verified_domains)
+                .post(handlers::verified_domains::add_verified_domains),
+        )
+        .route(
+            "/workspace/verified-domains/:domain_id/challenge",
+            get(handlers::verified_domains::get_domain_challenge),
+        )
+        .route(
+            "/workspace/verified-domains/:domain_id/verify",
+            post(handlers::verified_domains::verify_domain),
+        )
+        .route(
+            "/workspace/verified-domains/:domain_id/reverify",
+            post(handlers::verified_domains::reverify_domain),
+        )
+        .route(
+            "/workspace/verified-domains/:domain_id/subdomains",
+            post(handlers::verified_domains::update_domain_subdomains),
+        )
+        .route(
+            "/workspace/verified-domains/:domain_id/delete",
+            post(handlers::verified_domains::delete_verified_domain),
         )
         .route(
-            "/workspace/edit/{workspace_id}",
-            post(handlers::workspaces::edit_workspace)
-                .layer(middleware::from_fn(authorization_middleware)),
+            "/workspace/verified-domains/:domain_id/timeline",
+            get(handlers::verified_domains::get_verified_domain_timeline),
+        )
+        // breach alerts
+        .route(
+            "/workspace/alerts",
+            get(handlers::alerts::list_breach_alerts),
+        )
+        .route(
+            "/workspace/alerts/:alert_id/acknowledge",
+            post(handlers::alerts::acknowledge_breach_alert),
+        )
+        .route(
+            "/workspace/alerts/:alert_id/resolve",
+            post(handlers::alerts::resolve_breach_alert),
+        )
+        // webhooks
+        .route(
+            "/workspace/webhooks",
+            get(handlers::webhooks::list_webhooks).post(handlers::webhooks::create_webhook),
+        )
+        .route(
+            "/workspace/webhooks/deliveries",
+            get(handlers::webhooks::list_webhook_deliveries),
+        )
+        .route(
+            "/workspace/webhooks/deliveries/:delivery_id/replay",
+            post(handlers::webhooks::replay_webhook_delivery),
+        )
+        .route(
+            "/workspace/webhooks/:webhook_id/update",
+            post(handlers::webhooks::update_webhook),
+        )
+        .route(
+            "/workspace/webhooks/:webhook_id/rotate-secret",
+            post(handlers::webhooks::rotate_webhook_secret),
+        )
+        .route(
+            "/workspace/webhooks/:webhook_id/delete",
+            post(handlers::webhooks::delete_webhook),
+        )
+        .route(
+            "/workspace/usage",
+            get(handlers::workspaces::get_workspace_usage),
+        )
+        .route(
+            "/workspace/edit/:workspace_id",
+            post(handlers::workspaces::edit_workspace),
+        )
+        .route(
+            "/workspace/reorder",
+            post(handlers::workspaces::reorder_workspaces),
+        )
+        .route(
+            "/workspace/settings",
+            get(handlers::workspaces::get_workspace_settings)
+                .post(handlers::workspaces::update_workspace_settings),
+        )
+        // members
+        .route("/workspace/members", get(handlers::members::list_members))
+        .route(
+            "/workspace/members/:member_id/role",
+            post(handlers::members::change_member_role),
+        )
+        .route(
+            "/workspace/members/:member_id/suspend",
+            post(handlers::members::suspend_member),
+        )
+        .route(
+            "/workspace/members/:member_id/reactivate",
+            post(handlers::members::reactivate_member),
+        )
+        .route(
+            "/workspace/members/:member_id/remove",
+            post(handlers::members::remove_member),
+        )
+        // join requests from sign ups on verified domains
+        .route(
+            "/workspace/join-policy",
+            get(handlers::join_requests::get_join_policy)
+                .post(handlers::join_requests::update_join_policy),
+        )
+        .route(
+            "/workspace/join-requests",
+            get(handlers::join_requests::list_join_requests),
+        )
+        .route(
+            "/workspace/join-requests/:request_id/approve",
+            post(handlers::join_requests::approve_join_request),
+        )
+        .route(
+            "/workspace/join-requests/:request_id/reject",
+            post(handlers::join_requests::reject_join_request),
         )
         // member settings
-        .layer(cors)
         .route(
-            "/settings/{member_id}", // don't need workspace id because its added from login
+            "/settings/:member_id", // don't need workspace id because its added from login
             get(handlers::members::get_member_settings)
-                .layer(middleware::from_fn(authorization_middleware)),
+                .post(handlers::members::update_member_settings),
+        )
+        // route layers run bottom to top, the token has to be decoded
+        // before the workspace status can be checked
+        .route_layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            workspace_status_middleware,
+        ))
+        .route_layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            authorization_middleware,
+        ));
+
+    // Super admin routes, these skip the workspace status check
+    let admin_routes = axum::Router::new()
+        .route(
+            "/admin/workspaces/:workspace_id/status",
+            post(handlers::admin::update_workspace_status),
+        )
+        .route("/admin/usage", get(handlers::admin::get_usage_report))
+        .route(
+            "/admin/usage/export",
+            get(handlers::admin::export_usage_report),
+        )
+        .route_layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            super_admin_middleware,
+        ))
+        .route_layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            authorization_middleware,
+        ));
+
+    axum::Router::new()
+        // Unauthorized Routes
+        .route("/", get(test))
+        .route("/users/auth/signup", post(handlers::users::sign_up))
+        .route("/users/auth/login", post(handlers::users::log_in))
+        .route("/users/auth/verify/", post(handlers::users::verify_user))
+        .route(
+            "/invitations/accept",
+            post(handlers::workspaces::accept_member_invitation),
+        )
+        .route(
+            "/verified-emails/confirm",
+            post(handlers::verified_emails::confirm_verified_email),
+        )
+        .route(
+            "/invitations/onboard",
+            post(handlers::workspaces::onboard_invited_user),
+        )
+        .route(
+            "/invitations/reject",
+            post(handlers::workspaces::reject_member_invitation),
+        )
+        .route(
+            "/users/auth/request-reset-token/",
+            post(handlers::auth::request_password_reset_link),
         )
+        .route(
+            "/users/auth/reset-with-token/",
+            post(handlers::auth::reset_password_with_token),
+        )
+        // Authorized Routes
+        .merge(authorized_routes)
+        .merge(admin_routes)
+        .layer(cors)
         .with_state(app_state)
         .layer(TraceLayer::new_for_http())
 }